@@ -21,8 +21,14 @@ fn main() -> Result<(), Box<dyn Error>> {
             "ContentMetadata",
             "#[derive(serde::Deserialize, serde::Serialize)]",
         )
+        .bytes([".indexify_ingestion"])
         .compile(
-            &["proto/coordinator_service.proto", "proto/raft.proto"],
+            &[
+                "proto/coordinator_service.proto",
+                "proto/raft.proto",
+                "proto/extractor_service.proto",
+                "proto/ingestion_service.proto",
+            ],
             &["proto"],
         )
         .unwrap();
@@ -86,6 +86,8 @@ pub enum OutputSchema {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ExtractorDescription {
     pub name: String,
+    #[serde(default)]
+    pub version: String,
     pub description: String,
     pub input_params: serde_json::Value,
     pub outputs: HashMap<String, OutputSchema>,
@@ -107,6 +109,7 @@ impl From<ExtractorDescription> for indexify_coordinator::Extractor {
             input_params: value.input_params.to_string(),
             outputs: output_schema,
             input_mime_types: value.input_mime_types,
+            version: value.version,
         }
     }
 }
@@ -120,6 +123,7 @@ impl From<indexify_coordinator::Extractor> for ExtractorDescription {
         }
         Self {
             name: value.name,
+            version: value.version,
             description: value.description,
             input_params: serde_json::from_str(&value.input_params).unwrap(),
             outputs: output_schema,
@@ -133,12 +137,16 @@ pub struct ExecutorInfo {
     pub id: String,
     pub last_seen: u64,
     pub addr: String,
-    pub extractor: ExtractorDescription,
+    pub extractors: Vec<ExtractorDescription>,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExtractRequest {
     pub content: Content,
     pub input_params: Option<serde_json::Value>,
+    /// Extractor to run, can be omitted if the executor hosts a single
+    /// extractor
+    #[serde(default)]
+    pub extractor_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -146,6 +154,14 @@ pub struct ExtractResponse {
     pub content: Vec<Content>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrainResponse {
+    /// Tasks handed back to the coordinator without running them
+    pub returned_tasks: usize,
+    /// Tasks still running when the drain timed out
+    pub unfinished_tasks: usize,
+}
+
 #[derive(
     Debug, PartialEq, Eq, Serialize, Clone, Deserialize, EnumString, Display, SmartDefault,
 )]
@@ -211,6 +227,7 @@ pub enum TaskOutcome {
     Unknown,
     Success,
     Failed,
+    Cancelled,
 }
 
 impl From<indexify_coordinator::TaskOutcome> for TaskOutcome {
@@ -219,6 +236,7 @@ impl From<indexify_coordinator::TaskOutcome> for TaskOutcome {
             indexify_coordinator::TaskOutcome::Unknown => TaskOutcome::Unknown,
             indexify_coordinator::TaskOutcome::Success => TaskOutcome::Success,
             indexify_coordinator::TaskOutcome::Failed => TaskOutcome::Failed,
+            indexify_coordinator::TaskOutcome::Cancelled => TaskOutcome::Cancelled,
         }
     }
 }
@@ -229,6 +247,7 @@ impl From<TaskOutcome> for indexify_coordinator::TaskOutcome {
             TaskOutcome::Unknown => indexify_coordinator::TaskOutcome::Unknown,
             TaskOutcome::Success => indexify_coordinator::TaskOutcome::Success,
             TaskOutcome::Failed => indexify_coordinator::TaskOutcome::Failed,
+            TaskOutcome::Cancelled => indexify_coordinator::TaskOutcome::Cancelled,
         }
     }
 }
@@ -243,6 +262,33 @@ pub struct Task {
     pub content_metadata: ContentMetadata,
     pub input_params: serde_json::Value,
     pub outcome: TaskOutcome,
+
+    // Tasks with a higher priority are scheduled first
+    #[serde(default)]
+    pub priority: u32,
+
+    // Executor the task was last assigned to, kept after the task finishes
+    #[serde(default)]
+    pub executor_id: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub assigned_at: Option<u64>,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    // Blob of the output of the extractor while it ran the task
+    #[serde(default)]
+    pub logs_url: Option<String>,
+}
+
+impl Task {
+    pub fn is_terminal(&self) -> bool {
+        self.outcome != TaskOutcome::Unknown
+    }
 }
 
 impl From<Task> for indexify_coordinator::Task {
@@ -257,6 +303,14 @@ impl From<Task> for indexify_coordinator::Task {
             extractor_binding: value.extractor_binding,
             output_index_mapping: value.output_index_table_mapping,
             outcome: outcome as i32,
+            executor_id: value.executor_id.unwrap_or_default(),
+            attempts: value.attempts,
+            created_at: value.created_at,
+            assigned_at: value.assigned_at.unwrap_or_default(),
+            finished_at: value.finished_at.unwrap_or_default(),
+            error_message: value.error_message.unwrap_or_default(),
+            priority: value.priority,
+            logs_url: value.logs_url.unwrap_or_default(),
         }
     }
 }
@@ -277,10 +331,135 @@ impl TryFrom<indexify_coordinator::Task> for Task {
             extractor_binding: value.extractor_binding,
             output_index_table_mapping: value.output_index_mapping,
             outcome,
+            executor_id: Some(value.executor_id).filter(|e| !e.is_empty()),
+            attempts: value.attempts,
+            created_at: value.created_at,
+            assigned_at: Some(value.assigned_at).filter(|t| *t != 0),
+            finished_at: Some(value.finished_at).filter(|t| *t != 0),
+            error_message: Some(value.error_message).filter(|e| !e.is_empty()),
+            priority: value.priority,
+            logs_url: Some(value.logs_url).filter(|u| !u.is_empty()),
         })
     }
 }
 
+/// Processing counters of an extractor binding, maintained by the
+/// coordinator as tasks move through their lifecycle.
+#[derive(Serialize, Debug, Deserialize, Default, Clone, PartialEq)]
+pub struct ExtractorBindingStats {
+    pub content_matched: u64,
+    pub tasks_pending: u64,
+    pub tasks_assigned: u64,
+    pub tasks_succeeded: u64,
+    pub tasks_failed: u64,
+    pub tasks_cancelled: u64,
+    // Sum of assignment to completion times of finished tasks
+    pub total_latency_secs: u64,
+    pub latency_samples: u64,
+    pub last_error: Option<String>,
+}
+
+impl ExtractorBindingStats {
+    pub fn task_created(&mut self) {
+        self.content_matched += 1;
+        self.tasks_pending += 1;
+    }
+
+    pub fn task_assigned(&mut self) {
+        self.tasks_pending = self.tasks_pending.saturating_sub(1);
+        self.tasks_assigned += 1;
+    }
+
+    /// Records an assigned task going back to the queue
+    pub fn task_unassigned(&mut self) {
+        self.tasks_assigned = self.tasks_assigned.saturating_sub(1);
+        self.tasks_pending += 1;
+    }
+
+    /// Records a task reaching a terminal outcome. `was_pending` tells
+    /// whether the task finished before it was ever assigned.
+    pub fn task_finished(&mut self, task: &Task, was_pending: bool) {
+        if was_pending {
+            self.tasks_pending = self.tasks_pending.saturating_sub(1);
+        } else {
+            self.tasks_assigned = self.tasks_assigned.saturating_sub(1);
+        }
+        match task.outcome {
+            TaskOutcome::Success => self.tasks_succeeded += 1,
+            TaskOutcome::Failed => {
+                self.tasks_failed += 1;
+                if task.error_message.is_some() {
+                    self.last_error = task.error_message.clone();
+                }
+            }
+            TaskOutcome::Cancelled => self.tasks_cancelled += 1,
+            TaskOutcome::Unknown => {}
+        }
+        if task.outcome == TaskOutcome::Cancelled {
+            return;
+        }
+        if let (Some(assigned_at), Some(finished_at)) = (task.assigned_at, task.finished_at) {
+            self.total_latency_secs += finished_at.saturating_sub(assigned_at);
+            self.latency_samples += 1;
+        }
+    }
+
+    pub fn tasks_finished(&self) -> u64 {
+        self.tasks_succeeded + self.tasks_failed + self.tasks_cancelled
+    }
+
+    pub fn average_latency_secs(&self) -> f64 {
+        if self.latency_samples == 0 {
+            return 0.0;
+        }
+        self.total_latency_secs as f64 / self.latency_samples as f64
+    }
+
+    /// Percentage of matched content whose task has finished
+    pub fn percent_complete(&self) -> f64 {
+        if self.content_matched == 0 {
+            return 100.0;
+        }
+        self.tasks_finished() as f64 * 100.0 / self.content_matched as f64
+    }
+
+    pub fn to_proto(
+        &self,
+        repository: &str,
+        extractor_binding: &str,
+    ) -> indexify_coordinator::ExtractorBindingStats {
+        indexify_coordinator::ExtractorBindingStats {
+            repository: repository.to_string(),
+            extractor_binding: extractor_binding.to_string(),
+            content_matched: self.content_matched,
+            tasks_pending: self.tasks_pending,
+            tasks_assigned: self.tasks_assigned,
+            tasks_succeeded: self.tasks_succeeded,
+            tasks_failed: self.tasks_failed,
+            tasks_cancelled: self.tasks_cancelled,
+            total_latency_secs: self.total_latency_secs,
+            latency_samples: self.latency_samples,
+            last_error: self.last_error.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<indexify_coordinator::ExtractorBindingStats> for ExtractorBindingStats {
+    fn from(value: indexify_coordinator::ExtractorBindingStats) -> Self {
+        Self {
+            content_matched: value.content_matched,
+            tasks_pending: value.tasks_pending,
+            tasks_assigned: value.tasks_assigned,
+            tasks_succeeded: value.tasks_succeeded,
+            tasks_failed: value.tasks_failed,
+            tasks_cancelled: value.tasks_cancelled,
+            total_latency_secs: value.total_latency_secs,
+            latency_samples: value.latency_samples,
+            last_error: Some(value.last_error).filter(|e| !e.is_empty()),
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Deserialize, Display, Clone, PartialEq)]
 pub enum ExtractionEventPayload {
     ExtractorBindingAdded {
@@ -299,6 +478,84 @@ pub struct ExtractionEvent {
     pub payload: ExtractionEventPayload,
     pub created_at: u64,
     pub processed_at: Option<u64>,
+    // Priority of the tasks created for this event, overriding the priority
+    // of the bindings
+    #[serde(default)]
+    pub priority: Option<u32>,
+}
+
+/// Labels added to every content derived by an extractor, on top of the
+/// labels the extractor sets and the labels inherited from the parent
+pub const ROOT_CONTENT_ID_LABEL: &str = "indexify.root_content_id";
+pub const BINDING_LABEL: &str = "indexify.binding";
+pub const EXTRACTOR_LABEL: &str = "indexify.extractor";
+pub const EXTRACTOR_VERSION_LABEL: &str = "indexify.extractor_version";
+
+/// Label an extractor sets to name the content it extracts, e.g. the path of
+/// a file in an archive
+pub const FILE_NAME_LABEL: &str = "indexify.file_name";
+
+/// Which labels of a content are copied to the content extracted from it
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelInheritance {
+    #[default]
+    All,
+    None,
+    /// Only the labels with the listed keys
+    Allow(Vec<String>),
+}
+
+impl LabelInheritance {
+    /// Parses the JSON encoded label inheritance of a binding, bindings
+    /// created before it existed inherit all labels
+    pub fn from_json(value: &str) -> Result<Self> {
+        if value.is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(value).map_err(|e| anyhow::anyhow!("invalid label inheritance: {}", e))
+    }
+
+    pub fn inherited_labels(&self, labels: &HashMap<String, String>) -> HashMap<String, String> {
+        match self {
+            LabelInheritance::All => labels.clone(),
+            LabelInheritance::None => HashMap::new(),
+            LabelInheritance::Allow(keys) => labels
+                .iter()
+                .filter(|(key, _)| keys.contains(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Where the tasks of a binding run, according to the labels of the
+/// executors
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Placement {
+    /// Labels an executor must have to run the tasks
+    #[serde(default)]
+    pub constraints: HashMap<String, String>,
+    /// Labels of the executors preferred to run the tasks, executors
+    /// matching more of them are picked first
+    #[serde(default)]
+    pub preferences: HashMap<String, String>,
+}
+
+impl Placement {
+    pub fn allows(&self, labels: &HashMap<String, String>) -> bool {
+        self.constraints
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value))
+    }
+
+    /// Number of preferences matched by the labels
+    pub fn score(&self, labels: &HashMap<String, String>) -> usize {
+        self.preferences
+            .iter()
+            .filter(|(key, value)| labels.get(*key) == Some(*value))
+            .count()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Deserialize)]
@@ -321,6 +578,18 @@ pub struct ExtractorBinding {
     // The source of the content - ingestion, name of some extractor binding
     // which produces the content by invoking an extractor
     pub content_source: String,
+
+    // Priority of the tasks created by this binding
+    #[serde(default)]
+    pub priority: u32,
+
+    // Labels of the content copied to the content extracted from it
+    #[serde(default)]
+    pub label_inheritance: LabelInheritance,
+
+    // Executors the tasks of the binding run on
+    #[serde(default)]
+    pub placement: Placement,
 }
 
 impl std::hash::Hash for ExtractorBinding {
@@ -343,6 +612,10 @@ impl From<ExtractorBinding> for indexify_coordinator::ExtractorBinding {
             filters,
             input_params: value.input_params.to_string(),
             content_source: value.content_source,
+            priority: value.priority,
+            label_inheritance: serde_json::to_string(&value.label_inheritance).unwrap(),
+            placement_constraints: value.placement.constraints,
+            placement_preferences: value.placement.preferences,
         }
     }
 }
@@ -399,7 +672,40 @@ pub struct ExecutorMetadata {
     pub id: String,
     pub last_seen: u64,
     pub addr: String,
-    pub extractor: ExtractorDescription,
+    #[serde(alias = "extractor", deserialize_with = "one_or_many")]
+    pub extractors: Vec<ExtractorDescription>,
+    /// Number of tasks the executor prefers to receive at once
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+    /// Draining executors aren't assigned new tasks
+    #[serde(default)]
+    pub draining: bool,
+    /// Labels matched against the placement of bindings
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+fn default_batch_size() -> u32 {
+    1
+}
+
+/// Deserializes a list that used to be a single value, so that state
+/// written before the field became a list can still be read
+pub fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+    match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => Ok(vec![value]),
+        OneOrMany::Many(values) => Ok(values),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -419,6 +725,9 @@ pub struct TaskResult {
     pub outcome: TaskOutcome,
     pub extracted_content: Vec<Content>,
     pub error_msg: Option<String>,
+    // Output of the extractor while it ran the task
+    #[serde(default)]
+    pub logs: String,
 }
 
 impl TaskResult {
@@ -428,6 +737,7 @@ impl TaskResult {
             outcome: TaskOutcome::Failed,
             extracted_content: Vec::new(),
             error_msg: msg,
+            logs: String::new(),
         }
     }
 
@@ -437,6 +747,23 @@ impl TaskResult {
             outcome: TaskOutcome::Success,
             extracted_content,
             error_msg: None,
+            logs: String::new(),
+        }
+    }
+
+    pub fn with_logs(mut self, logs: String) -> Self {
+        self.logs = logs;
+        self
+    }
+
+    /// Content extracted by a task which is still running
+    pub fn partial(task_id: &str, extracted_content: Vec<Content>) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            outcome: TaskOutcome::Unknown,
+            extracted_content,
+            error_msg: None,
+            logs: String::new(),
         }
     }
 }
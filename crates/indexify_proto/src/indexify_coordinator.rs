@@ -21,12 +21,100 @@ pub struct UpdateTaskRequest {
     pub outcome: i32,
     #[prost(message, repeated, tag = "4")]
     pub content_list: ::prost::alloc::vec::Vec<ContentMetadata>,
+    #[prost(string, tag = "5")]
+    pub error_message: ::prost::alloc::string::String,
+    /// Blob of the output of the extractor while it ran the task
+    #[prost(string, tag = "6")]
+    pub logs_url: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UpdateTaskResponse {}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTasksRequest {
+    #[prost(string, tag = "1")]
+    pub repository: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub extractor_binding: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub content_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "TaskOutcome", optional, tag = "4")]
+    pub outcome: ::core::option::Option<i32>,
+    #[prost(string, tag = "5")]
+    pub executor_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub start_id: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "7")]
+    pub limit: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTasksResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub tasks: ::prost::alloc::vec::Vec<Task>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTaskRequest {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetTaskResponse {
+    #[prost(message, optional, tag = "1")]
+    pub task: ::core::option::Option<Task>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelTaskRequest {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelTaskResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBindingStatsRequest {
+    #[prost(string, tag = "1")]
+    pub repository: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListBindingStatsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub stats: ::prost::alloc::vec::Vec<ExtractorBindingStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExtractorBindingStats {
+    #[prost(string, tag = "1")]
+    pub repository: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub extractor_binding: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub content_matched: u64,
+    #[prost(uint64, tag = "4")]
+    pub tasks_pending: u64,
+    #[prost(uint64, tag = "5")]
+    pub tasks_assigned: u64,
+    #[prost(uint64, tag = "6")]
+    pub tasks_succeeded: u64,
+    #[prost(uint64, tag = "7")]
+    pub tasks_failed: u64,
+    #[prost(uint64, tag = "8")]
+    pub tasks_cancelled: u64,
+    #[prost(uint64, tag = "9")]
+    pub total_latency_secs: u64,
+    #[prost(uint64, tag = "10")]
+    pub latency_samples: u64,
+    #[prost(string, tag = "11")]
+    pub last_error: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetExtractorCoordinatesRequest {
     #[prost(string, tag = "2")]
     pub extractor: ::prost::alloc::string::String,
@@ -36,6 +124,8 @@ pub struct GetExtractorCoordinatesRequest {
 pub struct GetExtractorCoordinatesResponse {
     #[prost(string, repeated, tag = "1")]
     pub addrs: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "2")]
+    pub extractor: ::core::option::Option<Extractor>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -127,8 +217,18 @@ pub struct RegisterExecutorRequest {
     pub executor_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub addr: ::prost::alloc::string::String,
+    /// deprecated, only read if extractors is empty
     #[prost(message, optional, tag = "3")]
     pub extractor: ::core::option::Option<Extractor>,
+    #[prost(uint32, tag = "4")]
+    pub batch_size: u32,
+    #[prost(message, repeated, tag = "5")]
+    pub extractors: ::prost::alloc::vec::Vec<Extractor>,
+    #[prost(map = "string, string", tag = "6")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -138,17 +238,52 @@ pub struct RegisterExecutorResponse {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DrainExecutorRequest {
+    #[prost(string, tag = "1")]
+    pub executor_id: ::prost::alloc::string::String,
+    /// tasks the executor hands back without running them
+    #[prost(string, repeated, tag = "2")]
+    pub unstarted_task_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DrainExecutorResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveExecutorRequest {
+    #[prost(string, tag = "1")]
+    pub executor_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveExecutorResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HeartbeatRequest {
     #[prost(string, tag = "1")]
     pub executor_id: ::prost::alloc::string::String,
+    /// version of the last response received on the stream, 0 before the first one
+    #[prost(uint64, tag = "2")]
+    pub acked_version: u64,
+    #[prost(uint32, tag = "3")]
+    pub running_tasks: u32,
+    #[prost(uint32, tag = "4")]
+    pub pending_tasks: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HeartbeatResponse {
     #[prost(string, tag = "1")]
     pub executor_id: ::prost::alloc::string::String,
+    /// tasks assigned since the previous response, all the assigned tasks when full is set
     #[prost(message, repeated, tag = "2")]
     pub tasks: ::prost::alloc::vec::Vec<Task>,
+    #[prost(string, repeated, tag = "3")]
+    pub cancelled_task_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag = "4")]
+    pub version: u64,
+    #[prost(bool, tag = "5")]
+    pub full: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -172,6 +307,22 @@ pub struct Task {
     >,
     #[prost(enumeration = "TaskOutcome", tag = "8")]
     pub outcome: i32,
+    #[prost(string, tag = "9")]
+    pub executor_id: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "10")]
+    pub attempts: u32,
+    #[prost(uint64, tag = "11")]
+    pub created_at: u64,
+    #[prost(uint64, tag = "12")]
+    pub assigned_at: u64,
+    #[prost(uint64, tag = "13")]
+    pub finished_at: u64,
+    #[prost(string, tag = "14")]
+    pub error_message: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "15")]
+    pub priority: u32,
+    #[prost(string, tag = "16")]
+    pub logs_url: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -198,6 +349,8 @@ pub struct Extractor {
     >,
     #[prost(string, repeated, tag = "5")]
     pub input_mime_types: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, tag = "6")]
+    pub version: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -293,6 +446,24 @@ pub struct ExtractorBinding {
     >,
     #[prost(string, tag = "6")]
     pub content_source: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "7")]
+    pub priority: u32,
+    /// JSON encoded label inheritance of the derived content, all labels
+    /// are inherited when empty
+    #[prost(string, tag = "8")]
+    pub label_inheritance: ::prost::alloc::string::String,
+    /// labels an executor must have to run the tasks of the binding
+    #[prost(map = "string, string", tag = "9")]
+    pub placement_constraints: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// labels of the executors preferred to run the tasks of the binding
+    #[prost(map = "string, string", tag = "10")]
+    pub placement_preferences: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -354,6 +525,12 @@ pub struct ContentMetadata {
 pub struct CreateContentRequest {
     #[prost(message, optional, tag = "2")]
     pub content: ::core::option::Option<ContentMetadata>,
+    /// Overrides the priority of the bindings for tasks created for this content
+    #[prost(uint32, optional, tag = "3")]
+    pub priority: ::core::option::Option<u32>,
+    /// Further content created along with content, in a single write
+    #[prost(message, repeated, tag = "4")]
+    pub content_list: ::prost::alloc::vec::Vec<ContentMetadata>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -375,6 +552,7 @@ pub enum TaskOutcome {
     Unknown = 0,
     Failed = 1,
     Success = 2,
+    Cancelled = 3,
 }
 impl TaskOutcome {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -386,6 +564,7 @@ impl TaskOutcome {
             TaskOutcome::Unknown => "UNKNOWN",
             TaskOutcome::Failed => "FAILED",
             TaskOutcome::Success => "SUCCESS",
+            TaskOutcome::Cancelled => "CANCELLED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -394,6 +573,7 @@ impl TaskOutcome {
             "UNKNOWN" => Some(Self::Unknown),
             "FAILED" => Some(Self::Failed),
             "SUCCESS" => Some(Self::Success),
+            "CANCELLED" => Some(Self::Cancelled),
             _ => None,
         }
     }
@@ -813,6 +993,66 @@ pub mod coordinator_service_client {
                 );
             self.inner.streaming(req, path, codec).await
         }
+        pub async fn drain_executor(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DrainExecutorRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DrainExecutorResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/DrainExecutor",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_coordinator.CoordinatorService",
+                        "DrainExecutor",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn remove_executor(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RemoveExecutorRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RemoveExecutorResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/RemoveExecutor",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_coordinator.CoordinatorService",
+                        "RemoveExecutor",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn list_indexes(
             &mut self,
             request: impl tonic::IntoRequest<super::ListIndexesRequest>,
@@ -963,6 +1203,123 @@ pub mod coordinator_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        pub async fn list_tasks(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListTasksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListTasksResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/ListTasks",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_coordinator.CoordinatorService",
+                        "ListTasks",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn get_task(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::GetTaskResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/GetTask",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("indexify_coordinator.CoordinatorService", "GetTask"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn cancel_task(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CancelTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelTaskResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/CancelTask",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_coordinator.CoordinatorService",
+                        "CancelTask",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn list_binding_stats(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListBindingStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBindingStatsResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_coordinator.CoordinatorService/ListBindingStats",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_coordinator.CoordinatorService",
+                        "ListBindingStats",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -1052,6 +1409,20 @@ pub mod coordinator_service_server {
             &self,
             request: tonic::Request<tonic::Streaming<super::HeartbeatRequest>>,
         ) -> std::result::Result<tonic::Response<Self::HeartbeatStream>, tonic::Status>;
+        async fn drain_executor(
+            &self,
+            request: tonic::Request<super::DrainExecutorRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DrainExecutorResponse>,
+            tonic::Status,
+        >;
+        async fn remove_executor(
+            &self,
+            request: tonic::Request<super::RemoveExecutorRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::RemoveExecutorResponse>,
+            tonic::Status,
+        >;
         async fn list_indexes(
             &self,
             request: tonic::Request<super::ListIndexesRequest>,
@@ -1087,6 +1458,31 @@ pub mod coordinator_service_server {
             tonic::Response<super::UpdateTaskResponse>,
             tonic::Status,
         >;
+        async fn list_tasks(
+            &self,
+            request: tonic::Request<super::ListTasksRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListTasksResponse>,
+            tonic::Status,
+        >;
+        async fn get_task(
+            &self,
+            request: tonic::Request<super::GetTaskRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetTaskResponse>, tonic::Status>;
+        async fn cancel_task(
+            &self,
+            request: tonic::Request<super::CancelTaskRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CancelTaskResponse>,
+            tonic::Status,
+        >;
+        async fn list_binding_stats(
+            &self,
+            request: tonic::Request<super::ListBindingStatsRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListBindingStatsResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct CoordinatorServiceServer<T: CoordinatorService> {
@@ -1698,6 +2094,100 @@ pub mod coordinator_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/indexify_coordinator.CoordinatorService/DrainExecutor" => {
+                    #[allow(non_camel_case_types)]
+                    struct DrainExecutorSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::DrainExecutorRequest>
+                    for DrainExecutorSvc<T> {
+                        type Response = super::DrainExecutorResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DrainExecutorRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::drain_executor(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DrainExecutorSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/indexify_coordinator.CoordinatorService/RemoveExecutor" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveExecutorSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::RemoveExecutorRequest>
+                    for RemoveExecutorSvc<T> {
+                        type Response = super::RemoveExecutorResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RemoveExecutorRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::remove_executor(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RemoveExecutorSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/indexify_coordinator.CoordinatorService/ListIndexes" => {
                     #[allow(non_camel_case_types)]
                     struct ListIndexesSvc<T: CoordinatorService>(pub Arc<T>);
@@ -1937,6 +2427,195 @@ pub mod coordinator_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/indexify_coordinator.CoordinatorService/ListTasks" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListTasksSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::ListTasksRequest>
+                    for ListTasksSvc<T> {
+                        type Response = super::ListTasksResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListTasksRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::list_tasks(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListTasksSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/indexify_coordinator.CoordinatorService/GetTask" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTaskSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::GetTaskRequest>
+                    for GetTaskSvc<T> {
+                        type Response = super::GetTaskResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetTaskRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::get_task(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetTaskSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/indexify_coordinator.CoordinatorService/CancelTask" => {
+                    #[allow(non_camel_case_types)]
+                    struct CancelTaskSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::CancelTaskRequest>
+                    for CancelTaskSvc<T> {
+                        type Response = super::CancelTaskResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CancelTaskRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::cancel_task(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = CancelTaskSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/indexify_coordinator.CoordinatorService/ListBindingStats" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListBindingStatsSvc<T: CoordinatorService>(pub Arc<T>);
+                    impl<
+                        T: CoordinatorService,
+                    > tonic::server::UnaryService<super::ListBindingStatsRequest>
+                    for ListBindingStatsSvc<T> {
+                        type Response = super::ListBindingStatsResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListBindingStatsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as CoordinatorService>::list_binding_stats(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListBindingStatsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -0,0 +1,538 @@
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TaskHeader {
+    #[prost(string, tag = "1")]
+    pub task_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub repository: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub parent_content_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub executor_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub extractor_binding: ::prost::alloc::string::String,
+    #[prost(enumeration = "super::indexify_coordinator::TaskOutcome", tag = "6")]
+    pub outcome: i32,
+    #[prost(string, tag = "7")]
+    pub error_message: ::prost::alloc::string::String,
+    /// Index table of every feature name
+    #[prost(map = "string, string", tag = "8")]
+    pub index_table_names: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    /// Writes of a task with an idempotency key that was already written
+    /// are acknowledged without writing anything
+    #[prost(string, tag = "9")]
+    pub idempotency_key: ::prost::alloc::string::String,
+    /// Output of the extractor while it ran the task
+    #[prost(string, tag = "10")]
+    pub logs: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContentStart {
+    #[prost(string, tag = "1")]
+    pub mime: ::prost::alloc::string::String,
+    #[prost(message, repeated, tag = "2")]
+    pub features: ::prost::alloc::vec::Vec<super::indexify_extractor::Feature>,
+    #[prost(map = "string, string", tag = "3")]
+    pub labels: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContentEnd {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WriteExtractedContentRequest {
+    #[prost(oneof = "write_extracted_content_request::Message", tags = "1, 2, 3, 4")]
+    pub message: ::core::option::Option<write_extracted_content_request::Message>,
+}
+/// Nested message and enum types in `WriteExtractedContentRequest`.
+pub mod write_extracted_content_request {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        #[prost(message, tag = "1")]
+        Task(super::TaskHeader),
+        #[prost(message, tag = "2")]
+        ContentStart(super::ContentStart),
+        /// Part of the data of the current content
+        #[prost(bytes, tag = "3")]
+        Chunk(::prost::bytes::Bytes),
+        #[prost(message, tag = "4")]
+        ContentEnd(super::ContentEnd),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContentWritten {
+    /// Position of the content in the stream, starting at 0
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(string, tag = "2")]
+    pub content_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TaskWritten {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WriteExtractedContentResponse {
+    #[prost(oneof = "write_extracted_content_response::Message", tags = "1, 2")]
+    pub message: ::core::option::Option<write_extracted_content_response::Message>,
+}
+/// Nested message and enum types in `WriteExtractedContentResponse`.
+pub mod write_extracted_content_response {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        #[prost(message, tag = "1")]
+        Content(super::ContentWritten),
+        #[prost(message, tag = "2")]
+        Task(super::TaskWritten),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadContentRequest {
+    #[prost(string, tag = "1")]
+    pub content_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadContentResponse {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub chunk: ::prost::bytes::Bytes,
+}
+/// Generated client implementations.
+pub mod ingestion_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    /// IngestionService is served by the ingestion server, executors read the
+    /// content of their tasks and write the content extracted by them through
+    /// it. When the server has an ingestion token, every call has to carry it
+    /// as a bearer token in the authorization metadata.
+    #[derive(Debug, Clone)]
+    pub struct IngestionServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl IngestionServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> IngestionServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> IngestionServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + Send + Sync,
+        {
+            IngestionServiceClient::new(InterceptedService::new(inner, interceptor))
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Streams the data of a content in chunks
+        pub async fn read_content(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadContentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::ReadContentResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_ingestion.IngestionService/ReadContent",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("indexify_ingestion.IngestionService", "ReadContent"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Writes the output of a task. The executor sends the task header
+        /// first, then every content as a ContentStart, the chunks of its data
+        /// and a ContentEnd. The server acknowledges every content once it's
+        /// stored, and the task once the executor closed its side of the stream
+        /// and the outcome was recorded.
+        pub async fn write_extracted_content(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::WriteExtractedContentRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<
+                tonic::codec::Streaming<super::WriteExtractedContentResponse>,
+            >,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/indexify_ingestion.IngestionService/WriteExtractedContent",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "indexify_ingestion.IngestionService",
+                        "WriteExtractedContent",
+                    ),
+                );
+            self.inner.streaming(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod ingestion_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with IngestionServiceServer.
+    #[async_trait]
+    pub trait IngestionService: Send + Sync + 'static {
+        /// Server streaming response type for the ReadContent method.
+        type ReadContentStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::ReadContentResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Streams the data of a content in chunks
+        async fn read_content(
+            &self,
+            request: tonic::Request<super::ReadContentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::ReadContentStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the WriteExtractedContent method.
+        type WriteExtractedContentStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<
+                    super::WriteExtractedContentResponse,
+                    tonic::Status,
+                >,
+            >
+            + Send
+            + 'static;
+        /// Writes the output of a task. The executor sends the task header
+        /// first, then every content as a ContentStart, the chunks of its data
+        /// and a ContentEnd. The server acknowledges every content once it's
+        /// stored, and the task once the executor closed its side of the stream
+        /// and the outcome was recorded.
+        async fn write_extracted_content(
+            &self,
+            request: tonic::Request<
+                tonic::Streaming<super::WriteExtractedContentRequest>,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<Self::WriteExtractedContentStream>,
+            tonic::Status,
+        >;
+    }
+    /// IngestionService is served by the ingestion server, executors read the
+    /// content of their tasks and write the content extracted by them through
+    /// it. When the server has an ingestion token, every call has to carry it
+    /// as a bearer token in the authorization metadata.
+    #[derive(Debug)]
+    pub struct IngestionServiceServer<T: IngestionService> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: IngestionService> IngestionServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for IngestionServiceServer<T>
+    where
+        T: IngestionService,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/indexify_ingestion.IngestionService/ReadContent" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReadContentSvc<T: IngestionService>(pub Arc<T>);
+                    impl<
+                        T: IngestionService,
+                    > tonic::server::ServerStreamingService<super::ReadContentRequest>
+                    for ReadContentSvc<T> {
+                        type Response = super::ReadContentResponse;
+                        type ResponseStream = T::ReadContentStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadContentRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as IngestionService>::read_content(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReadContentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/indexify_ingestion.IngestionService/WriteExtractedContent" => {
+                    #[allow(non_camel_case_types)]
+                    struct WriteExtractedContentSvc<T: IngestionService>(pub Arc<T>);
+                    impl<
+                        T: IngestionService,
+                    > tonic::server::StreamingService<
+                        super::WriteExtractedContentRequest,
+                    > for WriteExtractedContentSvc<T> {
+                        type Response = super::WriteExtractedContentResponse;
+                        type ResponseStream = T::WriteExtractedContentStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::WriteExtractedContentRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as IngestionService>::write_extracted_content(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WriteExtractedContentSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", "12")
+                                .header("content-type", "application/grpc")
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T: IngestionService> Clone for IngestionServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    impl<T: IngestionService> Clone for _Inner<T> {
+        fn clone(&self) -> Self {
+            Self(Arc::clone(&self.0))
+        }
+    }
+    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+    impl<T: IngestionService> tonic::server::NamedService for IngestionServiceServer<T> {
+        const NAME: &'static str = "indexify_ingestion.IngestionService";
+    }
+}
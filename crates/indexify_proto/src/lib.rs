@@ -2,3 +2,7 @@
 pub mod indexify_coordinator;
 #[rustfmt::skip]
 pub mod indexify_raft;
+#[rustfmt::skip]
+pub mod indexify_extractor;
+#[rustfmt::skip]
+pub mod indexify_ingestion;
@@ -22,6 +22,12 @@ class TestMockExtractor(unittest.TestCase):
         self.assertEqual(len(extracted_content), 1)
         self.assertEqual(len(extracted_content[0]), 3)
 
+    def test_extractor_wrapper_batch(self):
+        e = ExtractorWrapper("indexify_extractor_sdk.mock_extractor", "MockBatchExtractor")
+        extracted_content = e.extract([Content.from_text("foo"), Content.from_text("bar")], "{}")
+        self.assertEqual(e._instance.batches, [2])
+        self.assertEqual([[c.data for c in batch] for batch in extracted_content], [[b"foo"], [b"bar"]])
+
     def test_extractor_schema(self):
         e = ExtractorWrapper("indexify_extractor_sdk.mock_extractor", "MockExtractor")
         schemas = e.schemas()
@@ -1,5 +1,6 @@
 from abc import ABC, abstractmethod
-from typing import Dict, List, Type, Optional, Union
+from typing import Dict, Iterable, Iterator, List, Type, Optional, Union
+import inspect
 import json
 from importlib import import_module
 from typing import get_type_hints
@@ -27,6 +28,7 @@ class ExtractorDescription(BaseModel):
     metadata_schemas: dict[str, str]
     input_params: Optional[str]
     input_mime_types: List[str]
+    streaming: bool = False
 
 
 class Feature(BaseModel):
@@ -88,18 +90,31 @@ class Extractor(ABC):
     @abstractmethod
     def extract(
         self, content: Content, params: Type[BaseModel] = None
-    ) -> List[Content]:
+    ) -> Iterable[Content]:
         """
-        Extracts information from the content.
+        Extracts information from the content. Extractors implemented as
+        generators stream their output, the executor writes the content
+        they yield while the extraction is still running.
         """
         pass
 
+    def extract_batch(
+        self, content: List[Content], params: Type[BaseModel] = None
+    ) -> List[List[Content]]:
+        """
+        Extracts information from a batch of content, returns the extracted
+        content of every input in the same order. Extractors which run faster
+        on batches, e.g. embedding models, override it, by default every
+        content is extracted on its own.
+        """
+        return [list(self.extract(c, params)) for c in content]
+
     @abstractmethod
     def sample_input(self) -> Content:
         pass
 
     def extract_sample_input(self) -> List[Content]:
-        return self.extract(self.sample_input())
+        return list(self.extract(self.sample_input()))
 
 
 class ExtractorWrapper:
@@ -109,27 +124,35 @@ class ExtractorWrapper:
         self._param_cls = get_type_hints(self._cls.extract).get("params", None)
         self._instance: Extractor = self._cls()
 
-    def extract(self, content: List[Content], params: Json) -> List[List[Content]]:
+    def _params(self, params: Json):
         params_dict = json.loads(params)
-        param_instance = (
-            self._param_cls.model_validate(params_dict) if self._param_cls else None
-        )
+        return self._param_cls.model_validate(params_dict) if self._param_cls else None
 
-        # This is because the rust side does batching and on python we don't batch
-        out = []
-        for c in content:
-            extracted_data = self._instance.extract(
-                Content(content_type=c.content_type, data=bytes(c.data)), param_instance
+    def extract(self, content: List[Content], params: Json) -> List[List[Content]]:
+        param_instance = self._params(params)
+        content = [
+            Content(content_type=c.content_type, data=bytes(c.data)) for c in content
+        ]
+        out = self._instance.extract_batch(content, param_instance)
+        if len(out) != len(content):
+            raise ValueError(
+                f"extract_batch returned {len(out)} results for {len(content)} inputs"
             )
-            out.append(extracted_data)
-        return out
+        return [list(extracted) for extracted in out]
+
+    def extract_stream(self, content: Content, params: Json) -> Iterator[Content]:
+        param_instance = self._params(params)
+        yield from self._instance.extract(
+            Content(content_type=content.content_type, data=bytes(content.data)),
+            param_instance,
+        )
 
     def describe(self, input_params: Type[BaseModel] = None) -> ExtractorDescription:
         s_input = self._instance.sample_input()
         # Come back to this when we can support schemas based on user defined input params
         if input_params is None:
             input_params = self._param_cls() if self._param_cls else None
-        out_c: List[Content] = self._instance.extract(s_input, input_params)
+        out_c: List[Content] = list(self._instance.extract(s_input, input_params))
         embedding_schemas = {}
         metadata_schemas = {}
         json_schema = self._param_cls.model_json_schema() if self._param_cls else {}
@@ -155,4 +178,5 @@ class ExtractorWrapper:
             metadata_schemas=metadata_schemas,
             input_mime_types=self._instance.input_mime_types,
             input_params=json.dumps(json_schema),
+            streaming=inspect.isgeneratorfunction(self._cls.extract),
         )
@@ -54,3 +54,19 @@ class MockExtractorNoInputParams(Extractor):
 
     def sample_input(self) -> Content:
         return Content.from_text("hello world")
+
+
+class MockBatchExtractor(Extractor):
+    def __init__(self):
+        super().__init__()
+        self.batches = []
+
+    def extract(self, content: Content, params=None) -> List[Content]:
+        raise NotImplementedError("extracts batches only")
+
+    def extract_batch(self, content: List[Content], params=None) -> List[List[Content]]:
+        self.batches.append(len(content))
+        return [[Content.from_text(text=c.data.decode("utf-8"))] for c in content]
+
+    def sample_input(self) -> Content:
+        return Content.from_text("hello world")
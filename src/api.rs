@@ -4,6 +4,7 @@ use anyhow::Result;
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
 use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator;
@@ -23,6 +24,20 @@ pub struct ExtractorBinding {
     pub filters: HashMap<String, serde_json::Value>,
     pub input_params: Option<serde_json::Value>,
     pub content_source: Option<String>,
+    /// Tasks of bindings with a higher priority are scheduled first
+    #[serde(default)]
+    pub priority: u32,
+    /// Labels of the content copied to the content extracted from it:
+    /// `"all"` (the default), `"none"` or `{"allow": [<label keys>]}`
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub label_inheritance: internal_api::LabelInheritance,
+    /// Labels executors must have to run the tasks of the binding
+    /// (`constraints`) and labels of the executors preferred to run them
+    /// (`preferences`)
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub placement: internal_api::Placement,
 }
 
 impl From<ExtractorBinding> for indexify_coordinator::ExtractorBinding {
@@ -41,6 +56,10 @@ impl From<ExtractorBinding> for indexify_coordinator::ExtractorBinding {
                 .map(|v| v.to_string())
                 .unwrap_or("{}".to_string()),
             content_source: value.content_source.unwrap_or("ingestion".to_string()),
+            priority: value.priority,
+            label_inheritance: serde_json::to_string(&value.label_inheritance).unwrap(),
+            placement_constraints: value.placement.constraints,
+            placement_preferences: value.placement.preferences,
         }
     }
 }
@@ -67,6 +86,14 @@ impl TryFrom<indexify_coordinator::Repository> for DataRepository {
                     .collect(),
                 input_params: Some(serde_json::from_str(&binding.input_params)?),
                 content_source: Some(binding.content_source),
+                priority: binding.priority,
+                label_inheritance: internal_api::LabelInheritance::from_json(
+                    &binding.label_inheritance,
+                )?,
+                placement: internal_api::Placement {
+                    constraints: binding.placement_constraints,
+                    preferences: binding.placement_preferences,
+                },
             });
         }
         Ok(Self {
@@ -157,6 +184,60 @@ pub struct Text {
 pub struct TextAddRequest {
     pub documents: Vec<Text>,
     pub sync: Option<bool>,
+    /// Overrides the priority of the extractor bindings for these documents
+    pub priority: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct UploadFileParams {
+    /// Overrides the priority of the extractor bindings for the uploaded files
+    pub priority: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordFormat {
+    /// An array of objects
+    Json,
+    /// An object per line
+    Jsonl,
+    /// Comma separated values, with a header row
+    Csv,
+}
+
+impl RecordFormat {
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let ext = std::path::Path::new(name).extension()?.to_str()?;
+        match ext.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// How the records of a structured file are turned into content
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RecordMapping {
+    /// Format of the file, guessed from the extension of its name when unset
+    #[serde(default)]
+    pub format: Option<RecordFormat>,
+    /// Field of a record used as the text of its content, the whole record
+    /// is stored as JSON when unset
+    #[serde(default)]
+    pub text_field: Option<String>,
+    /// Fields of a record copied to the labels of its content
+    #[serde(default)]
+    pub label_fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UploadRecordsResponse {
+    /// Id of the content of the uploaded file, the parent of the records
+    pub content_id: String,
+    /// Number of content created from the records
+    pub records: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -180,6 +261,8 @@ pub enum ExtractorOutputSchema {
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ExtractorDescription {
     pub name: String,
+    #[serde(default)]
+    pub version: String,
     pub input_mime_types: Vec<String>,
     pub description: String,
     pub input_params: serde_json::Value,
@@ -195,6 +278,7 @@ impl From<ExtractorDescription> for indexify_coordinator::Extractor {
             .collect();
         Self {
             name: value.name,
+            version: value.version,
             description: value.description,
             input_params: value.input_params.to_string(),
             outputs,
@@ -226,6 +310,7 @@ impl From<ExtractorDescription> for internal_api::ExtractorDescription {
         }
         Self {
             name: extractor.name,
+            version: extractor.version,
             description: extractor.description,
             input_params: extractor.input_params,
             outputs: output_schema,
@@ -245,6 +330,7 @@ impl TryFrom<indexify_coordinator::Extractor> for ExtractorDescription {
         }
         Ok(Self {
             name: value.name,
+            version: value.version,
             description: value.description,
             input_params: serde_json::from_str(&value.input_params).unwrap(),
             outputs,
@@ -351,6 +437,8 @@ pub struct IndexSearchResponse {
 pub struct IndexifyAPIError {
     status_code: StatusCode,
     message: String,
+    /// Errors of individual input params, returned as JSON when set
+    input_params_errors: Option<Vec<api_utils::InputParamsError>>,
 }
 
 impl IndexifyAPIError {
@@ -358,13 +446,48 @@ impl IndexifyAPIError {
         Self {
             status_code,
             message,
+            input_params_errors: None,
         }
     }
+
+    /// Maps invalid input params to a 400 listing every invalid param,
+    /// other errors to `status_code`
+    pub fn from_input_params_error(
+        status_code: StatusCode,
+        context: &str,
+        err: anyhow::Error,
+    ) -> Self {
+        match err.downcast::<api_utils::InvalidInputParams>() {
+            Ok(invalid) => Self {
+                status_code: StatusCode::BAD_REQUEST,
+                message: invalid.to_string(),
+                input_params_errors: Some(invalid.0),
+            },
+            Err(err) => Self::new(status_code, format!("{}: {}", context, err)),
+        }
+    }
+}
+
+/// Body of a 400 response to invalid input params
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct InputParamsErrorResponse {
+    pub message: String,
+    pub errors: Vec<api_utils::InputParamsError>,
 }
 
 impl IntoResponse for IndexifyAPIError {
     fn into_response(self) -> Response {
-        (self.status_code, self.message).into_response()
+        match self.input_params_errors {
+            Some(errors) => (
+                self.status_code,
+                Json(InputParamsErrorResponse {
+                    message: self.message,
+                    errors,
+                }),
+            )
+                .into_response(),
+            None => (self.status_code, self.message).into_response(),
+        }
     }
 }
 
@@ -386,6 +509,168 @@ pub struct ContentMetadata {
     pub source: String,
 }
 
+impl From<internal_api::ContentMetadata> for ContentMetadata {
+    fn from(value: internal_api::ContentMetadata) -> Self {
+        Self {
+            id: value.id,
+            parent_id: value.parent_id,
+            repository: value.repository,
+            name: value.name,
+            content_type: value.content_type,
+            labels: value.labels,
+            storage_url: value.storage_url,
+            created_at: value.created_at,
+            source: value.source,
+        }
+    }
+}
+
+#[derive(Display, EnumString, Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TaskOutcome {
+    Unknown,
+    Success,
+    Failed,
+    Cancelled,
+}
+
+impl From<internal_api::TaskOutcome> for TaskOutcome {
+    fn from(value: internal_api::TaskOutcome) -> Self {
+        match value {
+            internal_api::TaskOutcome::Unknown => TaskOutcome::Unknown,
+            internal_api::TaskOutcome::Success => TaskOutcome::Success,
+            internal_api::TaskOutcome::Failed => TaskOutcome::Failed,
+            internal_api::TaskOutcome::Cancelled => TaskOutcome::Cancelled,
+        }
+    }
+}
+
+impl From<TaskOutcome> for indexify_coordinator::TaskOutcome {
+    fn from(value: TaskOutcome) -> Self {
+        match value {
+            TaskOutcome::Unknown => indexify_coordinator::TaskOutcome::Unknown,
+            TaskOutcome::Success => indexify_coordinator::TaskOutcome::Success,
+            TaskOutcome::Failed => indexify_coordinator::TaskOutcome::Failed,
+            TaskOutcome::Cancelled => indexify_coordinator::TaskOutcome::Cancelled,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Task {
+    pub id: String,
+    pub extractor: String,
+    pub extractor_binding: String,
+    pub repository: String,
+    pub content_metadata: ContentMetadata,
+    pub input_params: serde_json::Value,
+    pub outcome: TaskOutcome,
+    pub executor_id: Option<String>,
+    pub attempts: u32,
+    pub created_at: u64,
+    pub assigned_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub error_message: Option<String>,
+}
+
+impl From<internal_api::Task> for Task {
+    fn from(value: internal_api::Task) -> Self {
+        Self {
+            id: value.id,
+            extractor: value.extractor,
+            extractor_binding: value.extractor_binding,
+            repository: value.repository,
+            content_metadata: value.content_metadata.into(),
+            input_params: value.input_params,
+            outcome: value.outcome.into(),
+            executor_id: value.executor_id,
+            attempts: value.attempts,
+            created_at: value.created_at,
+            assigned_at: value.assigned_at,
+            finished_at: value.finished_at,
+            error_message: value.error_message,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct ListTasksFilters {
+    #[serde(
+        deserialize_with = "api_utils::deserialize_none_to_empty_string",
+        default
+    )]
+    pub extractor_binding: String,
+    #[serde(
+        deserialize_with = "api_utils::deserialize_none_to_empty_string",
+        default
+    )]
+    pub content_id: String,
+    pub outcome: Option<TaskOutcome>,
+    #[serde(
+        deserialize_with = "api_utils::deserialize_none_to_empty_string",
+        default
+    )]
+    pub executor_id: String,
+    #[serde(
+        deserialize_with = "api_utils::deserialize_none_to_empty_string",
+        default
+    )]
+    pub start_id: String,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ListTasksResponse {
+    pub tasks: Vec<Task>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GetTaskResponse {
+    pub task: Task,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, ToSchema)]
+pub struct ExtractorBindingStats {
+    pub repository: String,
+    pub extractor_binding: String,
+    pub content_matched: u64,
+    pub tasks_pending: u64,
+    pub tasks_assigned: u64,
+    pub tasks_succeeded: u64,
+    pub tasks_failed: u64,
+    pub tasks_cancelled: u64,
+    pub percent_complete: f64,
+    pub average_latency_secs: f64,
+    pub last_error: Option<String>,
+}
+
+impl From<indexify_coordinator::ExtractorBindingStats> for ExtractorBindingStats {
+    fn from(value: indexify_coordinator::ExtractorBindingStats) -> Self {
+        let repository = value.repository.clone();
+        let extractor_binding = value.extractor_binding.clone();
+        let stats: internal_api::ExtractorBindingStats = value.into();
+        Self {
+            repository,
+            extractor_binding,
+            content_matched: stats.content_matched,
+            tasks_pending: stats.tasks_pending,
+            tasks_assigned: stats.tasks_assigned,
+            tasks_succeeded: stats.tasks_succeeded,
+            tasks_failed: stats.tasks_failed,
+            tasks_cancelled: stats.tasks_cancelled,
+            percent_complete: stats.percent_complete(),
+            average_latency_secs: stats.average_latency_secs(),
+            last_error: stats.last_error,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ListBindingStatsResponse {
+    pub stats: Vec<ExtractorBindingStats>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, EnumString)]
 pub enum FeatureType {
     #[strum(serialize = "embedding")]
@@ -457,16 +742,57 @@ pub struct ExtractResponse {
     pub content: Vec<Content>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateExtractJobResponse {
+    pub job_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractJobStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractJob {
+    pub id: String,
+    pub status: ExtractJobStatus,
+    pub content: Vec<Content>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetExtractJobParams {
+    /// Seconds to wait for the job to finish before returning it
+    #[serde(default)]
+    pub wait_secs: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WriteExtractedContent {
     pub content_list: Vec<internal_api::Content>,
     pub task_id: String,
     pub repository: String,
     pub index_table_name: Option<String>,
+    /// Indexes the features are written to by feature name, takes precedence
+    /// over `index_table_name`
+    #[serde(default)]
+    pub index_table_names: HashMap<String, String>,
     pub parent_content_id: String,
     pub executor_id: String,
     pub task_outcome: internal_api::TaskOutcome,
     pub extractor_binding: String,
+    #[serde(default)]
+    pub error_message: Option<String>,
+    /// Identifies the part of the task's result written by this request,
+    /// writes of a part that was already written are ignored
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// Output of the extractor while it ran the task
+    #[serde(default)]
+    pub logs: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
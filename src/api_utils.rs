@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 
-use serde::Deserialize;
+use anyhow::anyhow;
+use itertools::Itertools;
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
 
 pub fn validate_label_key(key: &str) -> Result<(), String> {
     let validations = [
@@ -278,9 +283,10 @@ where
     for (key, value) in labels_eq.clone() {
         // if the first part is empty, then it's invalid
         if key.is_empty() {
-            return Err(serde::de::Error::custom(format!(
+            return Err(serde::de::Error::custom(
                 "invalid labels_eq filter - must be in the form 'key:value' or 'key:' or ''"
-            )));
+                    .to_string(),
+            ));
         }
         // if the second part is empty, then it's an empty string value filter
         if value.is_empty() {
@@ -415,3 +421,135 @@ mod test_deserialize_labels_eq_filter {
         }
     }
 }
+
+/// Validation error of an input param, `pointer` is the JSON pointer of the
+/// invalid value within the input params
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InputParamsError {
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid input params: {}",
+    .0.iter().map(|e| format!("{}: {}", e.pointer, e.message)).join(", ")
+)]
+pub struct InvalidInputParams(pub Vec<InputParamsError>);
+
+impl InvalidInputParams {
+    pub fn to_status(&self) -> tonic::Status {
+        tonic::Status::invalid_argument(serde_json::to_string(&self.0).unwrap_or_default())
+    }
+
+    /// Recovers the errors sent by the coordinator with `to_status`
+    pub fn from_status(status: &tonic::Status) -> Option<Self> {
+        if status.code() != tonic::Code::InvalidArgument {
+            return None;
+        }
+        serde_json::from_str(status.message()).ok().map(Self)
+    }
+}
+
+/// Fills in the defaults declared by `schema`, the JSON schema of an
+/// extractor's input params, and validates the result against it. Errors
+/// are returned as `InvalidInputParams`. A null schema accepts any input
+/// params.
+pub fn validate_input_params(
+    schema: &serde_json::Value,
+    mut input_params: serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    if schema.is_null() {
+        return Ok(input_params);
+    }
+    let compiled_schema = JSONSchema::compile(schema)
+        .map_err(|e| anyhow!("unable to compile json schema for input params: {}", e))?;
+    if input_params.is_null() && schema.get("properties").is_some() {
+        input_params = json!({});
+    }
+    fill_defaults(schema, &mut input_params);
+    if let Err(errors) = compiled_schema.validate(&input_params) {
+        let errors = errors
+            .map(|e| InputParamsError {
+                pointer: e.instance_path.to_string(),
+                message: e.to_string(),
+            })
+            .collect();
+        return Err(InvalidInputParams(errors).into());
+    }
+    Ok(input_params)
+}
+
+fn fill_defaults(schema: &serde_json::Value, value: &mut serde_json::Value) {
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return;
+    };
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    for (name, property) in properties {
+        if let Some(default) = property.get("default") {
+            object
+                .entry(name.clone())
+                .or_insert_with(|| default.clone());
+        }
+        if let Some(value) = object.get_mut(name) {
+            fill_defaults(property, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_validate_input_params {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "chunk_size": {"type": "integer", "default": 1000},
+                "splitter": {
+                    "type": "object",
+                    "properties": {"kind": {"type": "string", "default": "new_line"}},
+                },
+                "model": {"type": "string"},
+            },
+            "required": ["model"],
+        })
+    }
+
+    #[test]
+    fn test_defaults() {
+        let params =
+            validate_input_params(&schema(), json!({"model": "m", "splitter": {}})).unwrap();
+        assert_eq!(
+            json!({"model": "m", "chunk_size": 1000, "splitter": {"kind": "new_line"}}),
+            params
+        );
+
+        let params = validate_input_params(&json!(null), json!({"any": 1})).unwrap();
+        assert_eq!(json!({"any": 1}), params);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = validate_input_params(&schema(), json!({"model": "m", "chunk_size": "big"}))
+            .unwrap_err();
+        let errors = err.downcast::<InvalidInputParams>().unwrap().0;
+        assert_eq!(1, errors.len());
+        assert_eq!("/chunk_size", errors[0].pointer);
+
+        // missing input params are validated as an empty object
+        let err = validate_input_params(&schema(), serde_json::Value::Null).unwrap_err();
+        let invalid = err.downcast::<InvalidInputParams>().unwrap();
+        assert_eq!("", invalid.0[0].pointer);
+
+        let status = invalid.to_status();
+        assert_eq!(
+            invalid.0,
+            InvalidInputParams::from_status(&status).unwrap().0
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::Error;
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::{stream::FuturesOrdered, StreamExt};
+use futures::{
+    stream::{BoxStream, FuturesOrdered},
+    StreamExt,
+};
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncWriteExt},
@@ -9,6 +12,8 @@ use tokio::{
 
 use super::{BlobStorageReader, BlobStorageWriter, DiskStorageConfig};
 
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub struct DiskStorage {
     config: DiskStorageConfig,
@@ -33,6 +38,22 @@ impl BlobStorageWriter for DiskStorage {
         Ok(path)
     }
 
+    #[tracing::instrument(skip(self, chunks))]
+    async fn put_stream(
+        &self,
+        key: &str,
+        mut chunks: BoxStream<'static, Result<Bytes, anyhow::Error>>,
+    ) -> Result<String, anyhow::Error> {
+        let path = format!("{}/{}", self.config.path, key);
+        let mut file = File::create(&path).await?;
+        while let Some(chunk) = chunks.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+        let path = format!("file://{}", path);
+        Ok(path)
+    }
+
     #[tracing::instrument(skip(self))]
     async fn delete(&self, key: &str) -> Result<(), anyhow::Error> {
         let path = format!("{}/{}", self.config.path, key);
@@ -64,4 +85,64 @@ impl BlobStorageReader for DiskStorage {
 
         Ok(buffers)
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_stream(
+        &self,
+        key: &str,
+    ) -> Result<BoxStream<'static, Result<Bytes, anyhow::Error>>, anyhow::Error> {
+        let path = key.trim_start_matches("file://");
+        let file = File::open(path).await?;
+        let chunks = futures::stream::try_unfold(file, |mut file| async move {
+            let mut buffer = vec![0; READ_CHUNK_BYTES];
+            let len = file.read(&mut buffer).await?;
+            if len == 0 {
+                return Ok(None);
+            }
+            buffer.truncate(len);
+            Ok(Some((Bytes::from(buffer), file)))
+        });
+        Ok(chunks.boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_put_stream() {
+        let path = std::env::temp_dir().join(format!("blobs_{}", nanoid::nanoid!()));
+        let storage = DiskStorage::new(DiskStorageConfig {
+            path: path.to_str().unwrap().to_string(),
+        })
+        .unwrap();
+        let chunks = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"hello ")),
+            Ok(Bytes::from_static(b"world")),
+        ])
+        .boxed();
+        let url = storage.put_stream("greeting", chunks).await.unwrap();
+        let blobs = storage.get(&[&url]).await.unwrap();
+        assert_eq!(b"hello world".to_vec(), blobs[0]);
+        let chunks: Vec<Bytes> = storage
+            .get_stream(&url)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(b"hello world".to_vec(), chunks.concat());
+
+        // a failing stream fails the write
+        let chunks = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"hello ")),
+            Err(anyhow::anyhow!("stream closed")),
+        ])
+        .boxed();
+        assert!(storage.put_stream("greeting", chunks).await.is_err());
+        std::fs::remove_dir_all(path).unwrap();
+    }
 }
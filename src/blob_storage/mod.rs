@@ -3,6 +3,7 @@ use std::fmt::{self, Debug, Formatter};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::stream::BoxStream;
 use object_store::aws::AmazonS3Builder;
 use serde::{Deserialize, Serialize};
 
@@ -20,7 +21,7 @@ pub struct DiskStorageConfig {
     pub path: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BlobStorageConfig {
     pub s3: Option<S3Config>,
     pub disk: Option<DiskStorageConfig>,
@@ -29,12 +30,21 @@ pub struct BlobStorageConfig {
 #[async_trait]
 pub trait BlobStorageWriter {
     async fn put(&self, key: &str, data: Bytes) -> Result<String, anyhow::Error>;
+    /// Writes the blob as its chunks arrive, without holding all of it in
+    /// memory
+    async fn put_stream(
+        &self,
+        key: &str,
+        chunks: BoxStream<'static, Result<Bytes>>,
+    ) -> Result<String, anyhow::Error>;
     async fn delete(&self, key: &str) -> Result<()>;
 }
 
 #[async_trait]
 pub trait BlobStorageReader {
     async fn get(&self, keys: &[&str]) -> Result<Vec<Vec<u8>>>;
+    /// Reads a blob in chunks, without holding all of it in memory
+    async fn get_stream(&self, key: &str) -> Result<BoxStream<'static, Result<Bytes>>>;
 }
 
 #[derive(Clone)]
@@ -63,6 +73,35 @@ impl BlobStorage {
     pub fn new_with_config(config: BlobStorageConfig) -> Self {
         Self { config }
     }
+
+    fn s3_storage(&self, bucket: &str) -> Result<s3::S3Storage> {
+        Ok(s3::S3Storage::new(
+            bucket,
+            AmazonS3Builder::from_env()
+                .with_region(
+                    self.config
+                        .s3
+                        .as_ref()
+                        .map(|config| config.region.as_str())
+                        .unwrap_or("us-east-1"),
+                )
+                .with_bucket_name(bucket)
+                .build()
+                .context("unable to build S3 builder")?,
+        ))
+    }
+
+    fn disk_storage(&self) -> Result<disk::DiskStorage> {
+        // We need a default implementation for `DiskStorageConfig`
+        disk::DiskStorage::new(
+            self.config
+                .disk
+                .clone()
+                .unwrap_or_else(|| DiskStorageConfig {
+                    path: "blobs".to_string(),
+                }),
+        )
+    }
 }
 
 #[async_trait]
@@ -75,39 +114,24 @@ impl BlobStorageReader for BlobStorage {
         };
 
         if key.starts_with("s3://") {
-            let (bucket, key) = parse_s3_url(key)
+            let (bucket, _) = parse_s3_url(key)
                 .map_err(|err| anyhow::anyhow!("unable to parse s3 url: {}", err))?;
-            return s3::S3Storage::new(
-                key,
-                AmazonS3Builder::from_env()
-                    .with_region(
-                        self.config
-                            .s3
-                            .as_ref()
-                            .map(|config| config.region.as_str())
-                            .unwrap_or("us-east-1"),
-                    )
-                    .with_bucket_name(bucket)
-                    .build()
-                    .context("unable to build S3 builder")?,
-            )
-            .get(keys)
-            .await;
+            return self.s3_storage(bucket)?.get(keys).await;
         }
 
         // If it's not S3, assume it's a file
+        self.disk_storage()?.get(keys).await
+    }
 
-        // We need a default implementation for `DiskStorageConfig`
-        disk::DiskStorage::new(
-            self.config
-                .disk
-                .clone()
-                .unwrap_or_else(|| DiskStorageConfig {
-                    path: "blobs".to_string(),
-                }),
-        )?
-        .get(keys)
-        .await
+    async fn get_stream(&self, key: &str) -> Result<BoxStream<'static, Result<Bytes>>> {
+        if key.starts_with("s3://") {
+            let (bucket, _) = parse_s3_url(key)
+                .map_err(|err| anyhow::anyhow!("unable to parse s3 url: {}", err))?;
+            return self.s3_storage(bucket)?.get_stream(key).await;
+        }
+
+        // If it's not S3, assume it's a file
+        self.disk_storage()?.get_stream(key).await
     }
 }
 
@@ -117,74 +141,37 @@ impl BlobStorageWriter for BlobStorage {
         if key.starts_with("s3://") {
             let (bucket, key) = parse_s3_url(key)
                 .map_err(|err| anyhow::anyhow!("unable to parse s3 url: {}", err))?;
-            return s3::S3Storage::new(
-                key,
-                AmazonS3Builder::from_env()
-                    .with_region(
-                        self.config
-                            .s3
-                            .as_ref()
-                            .map(|config| config.region.as_str())
-                            .unwrap_or("us-east-1"),
-                    )
-                    .with_bucket_name(bucket)
-                    .build()
-                    .context("unable to build S3 builder")?,
-            )
-            .put(key, data)
-            .await;
+            return self.s3_storage(bucket)?.put(key, data).await;
         }
 
         // If it's not S3, assume it's a file
+        self.disk_storage()?.put(key, data).await
+    }
 
-        // We need a default implementation for `DiskStorageConfig`
-        disk::DiskStorage::new(
-            self.config
-                .disk
-                .clone()
-                .unwrap_or_else(|| DiskStorageConfig {
-                    path: "blobs".to_string(),
-                }),
-        )?
-        .put(key, data)
-        .await
+    async fn put_stream(
+        &self,
+        key: &str,
+        chunks: BoxStream<'static, Result<Bytes>>,
+    ) -> Result<String, anyhow::Error> {
+        if key.starts_with("s3://") {
+            let (bucket, key) = parse_s3_url(key)
+                .map_err(|err| anyhow::anyhow!("unable to parse s3 url: {}", err))?;
+            return self.s3_storage(bucket)?.put_stream(key, chunks).await;
+        }
+
+        // If it's not S3, assume it's a file
+        self.disk_storage()?.put_stream(key, chunks).await
     }
 
     async fn delete(&self, key: &str) -> Result<()> {
         if key.starts_with("s3://") {
             let (bucket, key) = parse_s3_url(key)
                 .map_err(|err| anyhow::anyhow!("unable to parse s3 url: {}", err))?;
-            return s3::S3Storage::new(
-                key,
-                AmazonS3Builder::from_env()
-                    .with_region(
-                        self.config
-                            .s3
-                            .as_ref()
-                            .map(|config| config.region.as_str())
-                            .unwrap_or("us-east-1"),
-                    )
-                    .with_bucket_name(bucket)
-                    .build()
-                    .context("unable to build S3 builder")?,
-            )
-            .delete(key)
-            .await;
+            return self.s3_storage(bucket)?.delete(key).await;
         }
 
         // If it's not S3, assume it's a file
-
-        // We need a default implementation for `DiskStorageConfig`
-        disk::DiskStorage::new(
-            self.config
-                .disk
-                .clone()
-                .unwrap_or_else(|| DiskStorageConfig {
-                    path: "blobs".to_string(),
-                }),
-        )?
-        .delete(key)
-        .await
+        self.disk_storage()?.delete(key).await
     }
 }
 
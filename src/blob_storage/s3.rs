@@ -1,8 +1,12 @@
 use anyhow::{anyhow, Error, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
-use futures::{stream::FuturesOrdered, StreamExt};
+use futures::{
+    stream::{BoxStream, FuturesOrdered},
+    StreamExt,
+};
 use object_store::{aws::AmazonS3, ObjectStore};
+use tokio::io::AsyncWriteExt;
 
 use super::{BlobStorageReader, BlobStorageWriter};
 
@@ -27,6 +31,28 @@ impl BlobStorageWriter for S3Storage {
         Ok(format!("s3://{}/{}", self.bucket, key))
     }
 
+    async fn put_stream(
+        &self,
+        key: &str,
+        mut chunks: BoxStream<'static, Result<Bytes>>,
+    ) -> Result<String> {
+        let path = key.into();
+        let (multipart_id, mut writer) = self.client.put_multipart(&path).await?;
+        let result = async {
+            while let Some(chunk) = chunks.next().await {
+                writer.write_all(&chunk?).await?;
+            }
+            writer.shutdown().await?;
+            Ok::<_, Error>(())
+        }
+        .await;
+        if let Err(err) = result {
+            let _ = self.client.abort_multipart(&path, &multipart_id).await;
+            return Err(err);
+        }
+        Ok(format!("s3://{}/{}", self.bucket, key))
+    }
+
     async fn delete(&self, key: &str) -> Result<()> {
         let _ = self
             .client
@@ -65,4 +91,11 @@ impl BlobStorageReader for S3Storage {
 
         Ok(buffers)
     }
+
+    async fn get_stream(&self, key: &str) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let chunks = self.client.get(&key.into()).await?.into_stream();
+        Ok(chunks
+            .map(|chunk| chunk.map_err(|e| anyhow!("Failed to read bytes: {}", e)))
+            .boxed())
+    }
 }
@@ -51,7 +51,7 @@ where
         redis::cmd("SET")
             .arg(key)
             .arg(value)
-            .query_async(&mut conn)
+            .query_async::<_, ()>(&mut conn)
             .await?;
         Ok(())
     }
@@ -59,7 +59,10 @@ where
     async fn invalidate(&mut self, key: &K) -> Result<(), IndexifyCachingError> {
         let mut conn = self.client.get_async_connection().await?;
         let key = key.serialize_to_flexbuffer()?;
-        redis::cmd("DEL").arg(key).query_async(&mut conn).await?;
+        redis::cmd("DEL")
+            .arg(key)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
         Ok(())
     }
 }
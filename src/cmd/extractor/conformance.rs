@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use clap::Args as ClapArgs;
+use indexify_proto::indexify_extractor as proto;
+
+use crate::{cmd::GlobalArgs, extractor::conformance::check_conformance};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    /// address of the extractor service to check
+    #[arg(long)]
+    addr: String,
+
+    /// sample text passed to the extractor
+    #[arg(short, long)]
+    text: Option<String>,
+
+    /// sample file passed to the extractor
+    #[arg(short, long)]
+    file: Option<String>,
+}
+
+impl Args {
+    pub async fn run(self, _: GlobalArgs) {
+        let Self { addr, text, file } = self;
+
+        let sample = match (text, file) {
+            (None, Some(file_path)) => proto::Content {
+                mime: mime_guess::from_path(&file_path)
+                    .first_or_octet_stream()
+                    .to_string(),
+                data: std::fs::read(&file_path)
+                    .unwrap_or_else(|e| panic!("unable to read file: {}, error: {}", file_path, e)),
+                features: vec![],
+                labels: HashMap::new(),
+            },
+            (text, None) => proto::Content {
+                mime: mime::TEXT_PLAIN.to_string(),
+                data: text.unwrap_or("hello world".to_string()).into_bytes(),
+                features: vec![],
+                labels: HashMap::new(),
+            },
+            _ => panic!("either text or file path can be provided"),
+        };
+        let checks = check_conformance(&addr, sample)
+            .await
+            .expect("unable to run conformance checks");
+        let mut failed = 0;
+        for check in &checks {
+            match &check.result {
+                Ok(()) => println!("PASS {}", check.name),
+                Err(err) => {
+                    failed += 1;
+                    println!("FAIL {}: {}", check.name, err);
+                }
+            }
+        }
+        println!("{} passed, {} failed", checks.len() - failed, failed);
+        if failed > 0 {
+            std::process::exit(1);
+        }
+    }
+}
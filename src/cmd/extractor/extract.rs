@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
 use anyhow::anyhow;
 use clap::Args as ClapArgs;
@@ -9,7 +9,8 @@ use tracing_unwrap::ResultExt;
 use crate::{
     cmd::GlobalArgs,
     coordinator_filters::matches_mime_type,
-    extractor::{py_extractors::PythonExtractor, python_path, ExtractorTS},
+    extractor::load_extractor,
+    server_config::WasmConfig,
 };
 
 #[derive(Debug, ClapArgs)]
@@ -49,10 +50,7 @@ impl Args {
                 .await
                 .unwrap_err_or_log();
         } else if let Some(extractor_path) = extractor_path {
-            python_path::set_python_path(&extractor_path).unwrap();
-            let extractor =
-                PythonExtractor::new_from_extractor_path(&extractor_path).unwrap_or_log();
-            let extractor: ExtractorTS = Arc::new(extractor);
+            let extractor = load_extractor(&extractor_path, &WasmConfig::default()).unwrap_or_log();
             let content = match (text, file) {
                 (Some(text), None) => Ok(internal_api::Content {
                     mime: "text/plain".to_string(),
@@ -1,11 +1,9 @@
-use std::sync::Arc;
-
 use clap::Args as ClapArgs;
 
 use crate::{
     cmd::GlobalArgs,
-    extractor::{extractor_runner, py_extractors::PythonExtractor, python_path},
-    server_config::ExtractorConfig,
+    extractor::{extractor_runner, load_extractor},
+    server_config::{ExtractorConfig, WasmConfig},
 };
 
 #[derive(Debug, ClapArgs)]
@@ -37,9 +35,8 @@ impl Args {
             }
         };
 
-        python_path::set_python_path(&extractor_path).unwrap();
-        let extractor = PythonExtractor::new_from_extractor_path(&extractor_path).unwrap();
-        let extractor_runner = extractor_runner::ExtractorRunner::new(Arc::new(extractor));
+        let extractor = load_extractor(&extractor_path, &WasmConfig::default()).unwrap();
+        let extractor_runner = extractor_runner::ExtractorRunner::new(extractor);
         let info = extractor_runner.info().unwrap();
         println!("{}", serde_json::to_string_pretty(&info).unwrap());
     }
@@ -2,10 +2,12 @@ use clap::{Args as ClapArgs, Subcommand};
 
 use super::GlobalArgs;
 
+mod conformance;
 mod extract;
 mod info;
 mod new;
 mod package;
+mod serve;
 mod start;
 
 #[derive(Debug, ClapArgs)]
@@ -24,6 +26,8 @@ impl Args {
             Command::Package(args) => args.run(global_args).await,
             Command::Start(args) => args.run(global_args).await,
             Command::Info(args) => args.run(global_args).await,
+            Command::Serve(args) => args.run(global_args).await,
+            Command::Conformance(args) => args.run(global_args).await,
         }
     }
 }
@@ -35,5 +39,9 @@ pub enum Command {
     Package(package::Args),
     Info(info::Args),
     /// join the extractor to indexify
-    Start(start::Args),
+    Start(Box<start::Args>),
+    /// serve the extractor over the gRPC extractor protocol
+    Serve(serve::Args),
+    /// check that an extractor service implements the gRPC extractor protocol
+    Conformance(conformance::Args),
 }
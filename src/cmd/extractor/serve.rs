@@ -0,0 +1,45 @@
+use clap::Args as ClapArgs;
+use indexify_proto::indexify_extractor::extractor_service_server::ExtractorServiceServer;
+
+use crate::{
+    cmd::GlobalArgs,
+    extractor::{grpc_extractor::ExtractorGrpcService, load_extractor},
+    prelude::*,
+    server_config::{ExtractorConfig, WasmConfig},
+};
+
+#[derive(Debug, ClapArgs)]
+pub struct Args {
+    #[arg(long)]
+    extractor_path: Option<String>,
+
+    /// address to serve the extractor service on
+    #[arg(long, default_value = "0.0.0.0:9500")]
+    listen_addr: String,
+}
+
+impl Args {
+    pub async fn run(self, _: GlobalArgs) {
+        let Self {
+            extractor_path,
+            listen_addr,
+        } = self;
+
+        let extractor_path = match extractor_path {
+            Some(path) => path,
+            None => {
+                ExtractorConfig::from_path("indexify.yaml")
+                    .unwrap_or_else(|_| panic!("unable to load extractor config from indexify.yaml, and extractor path is not provided explicitly via --extractor-path"))
+                    .path
+            }
+        };
+        let extractor = load_extractor(&extractor_path, &WasmConfig::default()).unwrap();
+        let service = ExtractorGrpcService::new(extractor);
+        info!("serving extractor service on: {}", listen_addr);
+        tonic::transport::Server::builder()
+            .add_service(ExtractorServiceServer::new(service))
+            .serve(listen_addr.parse().expect("invalid listen address"))
+            .await
+            .expect("failed to run extractor service");
+    }
+}
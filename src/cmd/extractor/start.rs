@@ -22,8 +22,76 @@ pub struct Args {
     #[arg(long)]
     ingestion_addr: String,
 
+    /// path of an extractor to host, repeat to host several extractors
     #[arg(long)]
-    extractor_path: Option<String>,
+    extractor_path: Vec<String>,
+
+    /// address of an extractor serving the gRPC extractor protocol, e.g. a
+    /// sidecar, used instead of the extractor path
+    #[arg(long)]
+    extractor_addr: Option<String>,
+
+    /// host an extractor built into the binary, e.g. text_chunker, repeat to
+    /// host several extractors
+    #[arg(long)]
+    builtin_extractor: Vec<String>,
+
+    /// maximum number of tasks passed to the extractor at once
+    #[arg(long)]
+    batch_size: Option<u32>,
+
+    /// number of extractor calls running concurrently
+    #[arg(long)]
+    workers: Option<usize>,
+
+    /// seconds after which a running task is marked as failed, python
+    /// extractors are interrupted and python workers killed
+    #[arg(long)]
+    task_timeout_secs: Option<u64>,
+
+    /// write the output of streaming extractors to the ingestion server in
+    /// batches of this many content
+    #[arg(long)]
+    stream_flush_size: Option<usize>,
+
+    /// bytes of extractor output kept per task
+    #[arg(long)]
+    max_task_log_bytes: Option<usize>,
+
+    /// seconds running tasks are given to finish when the executor is
+    /// drained
+    #[arg(long)]
+    drain_timeout_secs: Option<u64>,
+
+    /// label of the executor as key=value, matched against the placement of
+    /// bindings, repeat to set several labels
+    #[arg(long, value_parser = parse_label)]
+    label: Vec<(String, String)>,
+
+    /// run the extractor in this many python worker processes
+    #[arg(long)]
+    python_workers: Option<usize>,
+
+    /// restart python workers after this many extractions
+    #[arg(long)]
+    max_tasks_per_worker: Option<usize>,
+
+    /// stream results to the gRPC ingestion endpoint at this address
+    #[arg(long)]
+    ingestion_grpc_addr: Option<String>,
+
+    /// token presented to the gRPC ingestion endpoint
+    #[arg(long)]
+    ingestion_grpc_token: Option<String>,
+
+    /// directory of the blob store content is read from, when it isn't read
+    /// through the gRPC ingestion endpoint
+    #[arg(long)]
+    blob_storage_path: Option<String>,
+
+    /// journal received tasks and unreported results in this directory
+    #[arg(long)]
+    journal_dir: Option<String>,
 }
 
 impl Args {
@@ -33,24 +101,53 @@ impl Args {
             coordinator_addr,
             ingestion_addr,
             extractor_path,
+            extractor_addr,
+            builtin_extractor,
+            batch_size,
+            workers,
+            task_timeout_secs,
+            stream_flush_size,
+            max_task_log_bytes,
+            drain_timeout_secs,
+            label,
+            python_workers,
+            max_tasks_per_worker,
+            ingestion_grpc_addr,
+            ingestion_grpc_token,
+            blob_storage_path,
+            journal_dir,
         } = self;
 
         info!("starting indexify executor, version: {}", crate::VERSION);
-        let extractor_path = match extractor_path {
-            Some(path) => path,
-            None => {
+        let mut extractor_paths = extractor_path;
+        if extractor_paths.is_empty() && extractor_addr.is_none() && builtin_extractor.is_empty() {
+            extractor_paths.push(
                 ExtractorConfig::from_path("indexify.yaml")
                     .unwrap_or_else(|_| panic!("unable to load extractor config from indexify.yaml, and extractor path is not provided explicitly via --extractor-path"))
-                    .path
-            }
-        };
+                    .path,
+            );
+        }
         let executor_config = Arc::new(
             ExecutorConfig::default()
                 .with_advertise_addr(advertise_addr)
                 .expect("unable to use the provided advertise address")
                 .with_coordinator_addr(coordinator_addr)
                 .with_ingestion_addr(ingestion_addr)
-                .with_extractor_path(extractor_path),
+                .with_extractor_paths(extractor_paths)
+                .with_extractor_addr(extractor_addr)
+                .with_builtin_extractors(builtin_extractor)
+                .with_batch_size(batch_size)
+                .with_workers(workers)
+                .with_task_timeout_secs(task_timeout_secs)
+                .with_stream_flush_size(stream_flush_size)
+                .with_max_task_log_bytes(max_task_log_bytes)
+                .with_drain_timeout_secs(drain_timeout_secs)
+                .with_labels(label)
+                .with_python_workers(python_workers, max_tasks_per_worker)
+                .with_ingestion_grpc_addr(ingestion_grpc_addr)
+                .with_ingestion_grpc_token(ingestion_grpc_token)
+                .with_blob_storage_path(blob_storage_path)
+                .with_journal_dir(journal_dir),
         );
         ExecutorServer::new(executor_config)
             .await
@@ -60,3 +157,10 @@ impl Args {
             .expect("failed to run executor server");
     }
 }
+
+fn parse_label(label: &str) -> Result<(String, String), String> {
+    label
+        .split_once('=')
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .ok_or_else(|| format!("invalid label {}, expected key=value", label))
+}
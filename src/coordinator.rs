@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet, VecDeque},
     hash::{Hash, Hasher},
     sync::Arc,
 };
@@ -7,23 +7,31 @@ use std::{
 use anyhow::{anyhow, Ok, Result};
 use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator;
-use jsonschema::JSONSchema;
+use itertools::Itertools;
 use tokio::sync::watch::Receiver;
 use tracing::info;
 
 use crate::{
+    api_utils::validate_input_params,
     coordinator_filters::*,
     state::{store::StateChange, SharedState},
     utils::timestamp_secs,
 };
 
+const DEFAULT_LIST_TASKS_LIMIT: usize = 100;
+
 pub struct Coordinator {
     shared_state: SharedState,
+    /// Maximum number of unfinished tasks assigned to a single executor
+    max_tasks_per_executor: usize,
 }
 
 impl Coordinator {
-    pub fn new(shared_state: SharedState) -> Arc<Self> {
-        Arc::new(Self { shared_state })
+    pub fn new(shared_state: SharedState, max_tasks_per_executor: usize) -> Arc<Self> {
+        Arc::new(Self {
+            shared_state,
+            max_tasks_per_executor,
+        })
     }
 
     #[tracing::instrument(skip(self))]
@@ -42,7 +50,9 @@ impl Coordinator {
                         .shared_state
                         .content_matching_binding(&repository, &binding)
                         .await?;
-                    let tasks_for_binding = self.create_task(&binding, content_list).await?;
+                    let tasks_for_binding = self
+                        .create_task(&binding, content_list, event.priority)
+                        .await?;
                     tasks.extend(tasks_for_binding);
                 }
                 internal_api::ExtractionEventPayload::CreateContent { content } => {
@@ -51,8 +61,9 @@ impl Coordinator {
                         .filter_extractor_binding_for_content(&content)
                         .await?;
                     for binding in bindings {
-                        let task_for_binding =
-                            self.create_task(&binding, vec![content.clone()]).await?;
+                        let task_for_binding = self
+                            .create_task(&binding, vec![content.clone()], event.priority)
+                            .await?;
                         tasks.extend(task_for_binding);
                     }
                 }
@@ -69,20 +80,47 @@ impl Coordinator {
     #[tracing::instrument(skip(self))]
     pub async fn distribute_work(&self) -> Result<HashMap<String, String>, anyhow::Error> {
         let unallocated_tasks = self.shared_state.unassigned_tasks().await?;
+        let assigned_tasks = self.shared_state.assigned_tasks().await?;
 
-        // work_id -> executor_id
-        let mut task_assignments = HashMap::new();
-        for task in unallocated_tasks {
+        let mut executors_by_extractor = HashMap::new();
+        let mut batch_sizes = HashMap::new();
+        let mut executor_labels = HashMap::new();
+        let mut placements = HashMap::new();
+        for task in &unallocated_tasks {
+            let binding = (task.repository.clone(), task.extractor_binding.clone());
+            if !placements.contains_key(&binding) {
+                for extractor_binding in self.shared_state.list_bindings(&task.repository).await? {
+                    placements.insert(
+                        (task.repository.clone(), extractor_binding.name),
+                        extractor_binding.placement,
+                    );
+                }
+            }
+            if executors_by_extractor.contains_key(&task.extractor) {
+                continue;
+            }
             let executors = self
                 .shared_state
                 .get_executors_for_extractor(&task.extractor)
                 .await?;
-            if !executors.is_empty() {
-                let rand_index = rand::random::<usize>() % executors.len();
-                let executor_meta = executors[rand_index].clone();
-                task_assignments.insert(task.id.clone(), executor_meta.id.clone());
+            for executor in &executors {
+                batch_sizes.insert(executor.id.clone(), executor.batch_size as usize);
+                executor_labels.insert(executor.id.clone(), executor.labels.clone());
             }
+            let executors = executors.into_iter().map(|e| e.id).sorted().collect_vec();
+            executors_by_extractor.insert(task.extractor.clone(), executors);
         }
+
+        // work_id -> executor_id
+        let task_assignments = schedule_tasks(
+            unallocated_tasks,
+            &assigned_tasks,
+            &executors_by_extractor,
+            &batch_sizes,
+            &executor_labels,
+            &placements,
+            self.max_tasks_per_executor,
+        );
         info!("finishing work assignment: {:?}", task_assignments);
         Ok(task_assignments)
     }
@@ -91,6 +129,7 @@ impl Coordinator {
         &self,
         extractor_binding: &internal_api::ExtractorBinding,
         content_list: Vec<internal_api::ContentMetadata>,
+        priority: Option<u32>,
     ) -> Result<Vec<internal_api::Task>> {
         let extractor = self
             .shared_state
@@ -124,6 +163,14 @@ impl Coordinator {
                 content_metadata: content.clone(),
                 input_params: extractor_binding.input_params.clone(),
                 outcome: internal_api::TaskOutcome::Unknown,
+                priority: priority.unwrap_or(extractor_binding.priority),
+                executor_id: None,
+                attempts: 0,
+                created_at: timestamp_secs(),
+                assigned_at: None,
+                finished_at: None,
+                error_message: None,
+                logs_url: None,
             };
             info!("created task: {:?}", task);
             tasks.push(task);
@@ -144,7 +191,7 @@ impl Coordinator {
             .await?
             .into_iter();
         list_content_filter(content, source, parent_id, labels_eq)
-            .map(|c| Ok(c))
+            .map(Ok)
             .collect::<Result<Vec<internal_api::ContentMetadata>>>()
     }
 
@@ -161,15 +208,33 @@ impl Coordinator {
         executor_id: &str,
         outcome: internal_api::TaskOutcome,
         content_list: Vec<indexify_coordinator::ContentMetadata>,
+        error_message: Option<String>,
+        logs_url: Option<String>,
     ) -> Result<()> {
         info!(
             "updating task: {}, executor_id: {}, outcome: {:?}",
             task_id, executor_id, outcome
         );
         let mut task = self.shared_state.task_with_id(task_id).await?;
+        if task.outcome == internal_api::TaskOutcome::Cancelled {
+            info!("ignoring update for cancelled task: {}", task_id);
+            return Ok(());
+        }
+        let content_list = self.label_derived_content(&task, content_list).await?;
         let (content_meta_list, extraction_events) =
-            content_request_to_content_metadata(content_list)?;
-        task.outcome = outcome;
+            content_request_to_content_metadata(content_list, None)?;
+        // the parts of a streamed result written after the task finished
+        // don't reopen it
+        if outcome != internal_api::TaskOutcome::Unknown || !task.is_terminal() {
+            task.outcome = outcome;
+            if task.is_terminal() {
+                task.finished_at = Some(timestamp_secs());
+            }
+            task.error_message = error_message;
+        }
+        if logs_url.is_some() {
+            task.logs_url = logs_url;
+        }
         self.shared_state
             .update_task(
                 task,
@@ -181,6 +246,60 @@ impl Coordinator {
         Ok(())
     }
 
+    /// Sets the labels of the content extracted by a task: the labels of its
+    /// input allowed by the binding, the labels set by the extractor and the
+    /// provenance labels
+    async fn label_derived_content(
+        &self,
+        task: &internal_api::Task,
+        content_list: Vec<indexify_coordinator::ContentMetadata>,
+    ) -> Result<Vec<indexify_coordinator::ContentMetadata>> {
+        if content_list.is_empty() {
+            return Ok(content_list);
+        }
+        let label_inheritance = self
+            .shared_state
+            .list_bindings(&task.repository)
+            .await?
+            .into_iter()
+            .find(|binding| binding.name == task.extractor_binding)
+            .map(|binding| binding.label_inheritance)
+            .unwrap_or_default();
+        // the extractor may have been removed since the task was created
+        let extractor_version = self
+            .shared_state
+            .extractor_with_name(&task.extractor)
+            .await
+            .map(|extractor| extractor.version)
+            .unwrap_or_default();
+        let root_content_id = self.root_content_id(&task.content_metadata).await?;
+        let provenance = provenance_labels(task, &root_content_id, &extractor_version);
+        let inherited = label_inheritance.inherited_labels(&task.content_metadata.labels);
+        Ok(content_list
+            .into_iter()
+            .map(|mut content| {
+                content.labels = derived_labels(&inherited, content.labels, &provenance);
+                content
+            })
+            .collect())
+    }
+
+    /// Id of the ingested content a content was derived from
+    async fn root_content_id(&self, content: &internal_api::ContentMetadata) -> Result<String> {
+        if let Some(root_content_id) = content.labels.get(internal_api::ROOT_CONTENT_ID_LABEL) {
+            return Ok(root_content_id.clone());
+        }
+        // content derived before provenance labels existed
+        let mut content = content.clone();
+        while !content.parent_id.is_empty() {
+            content = self
+                .shared_state
+                .get_conent_metadata(&content.parent_id)
+                .await?;
+        }
+        Ok(content.id)
+    }
+
     pub async fn create_repository(&self, repository: &str) -> Result<()> {
         self.shared_state.create_repository(repository).await?;
         Ok(())
@@ -198,11 +317,114 @@ impl Coordinator {
         self.shared_state.list_extractors().await
     }
 
-    pub async fn heartbeat(&self, executor_id: &str) -> Result<Vec<internal_api::Task>> {
-        let tasks = self.shared_state.tasks_for_executor(executor_id).await?;
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_tasks(
+        &self,
+        repository: &str,
+        extractor_binding: &str,
+        content_id: &str,
+        outcome: Option<internal_api::TaskOutcome>,
+        executor_id: &str,
+        start_id: &str,
+        limit: usize,
+    ) -> Result<Vec<internal_api::Task>> {
+        let tasks = self.shared_state.list_tasks(repository).await?;
+        let limit = if limit == 0 {
+            DEFAULT_LIST_TASKS_LIMIT
+        } else {
+            limit
+        };
+        let tasks = list_tasks_filter(tasks, extractor_binding, content_id, outcome, executor_id)
+            .filter(|t| start_id.is_empty() || t.id.as_str() > start_id)
+            .sorted_by(|a, b| a.id.cmp(&b.id))
+            .take(limit)
+            .collect();
         Ok(tasks)
     }
 
+    pub async fn list_binding_stats(
+        &self,
+        repository: &str,
+    ) -> Result<Vec<indexify_coordinator::ExtractorBindingStats>> {
+        let stats = self
+            .shared_state
+            .list_binding_stats(repository)
+            .await?
+            .into_iter()
+            .map(|(repository, binding, stats)| stats.to_proto(&repository, &binding))
+            .sorted_by(|a, b| {
+                (&a.repository, &a.extractor_binding).cmp(&(&b.repository, &b.extractor_binding))
+            })
+            .collect();
+        Ok(stats)
+    }
+
+    pub async fn get_task(&self, task_id: &str) -> Option<internal_api::Task> {
+        self.shared_state.get_task(task_id).await
+    }
+
+    pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
+        let task = self.shared_state.task_with_id(task_id).await?;
+        if task.is_terminal() {
+            return Err(anyhow!(
+                "task {} has already finished with outcome {:?}",
+                task_id,
+                task.outcome
+            ));
+        }
+        info!("cancelling task: {}", task_id);
+        self.shared_state.cancel_task(task_id).await
+    }
+
+    pub async fn heartbeat(
+        &self,
+        executor_id: &str,
+    ) -> Result<(Vec<internal_api::Task>, Vec<String>)> {
+        let tasks = self.shared_state.tasks_for_executor(executor_id).await?;
+        let cancelled_task_ids = self
+            .shared_state
+            .cancelled_tasks_for_executor(executor_id)
+            .await?;
+        Ok((tasks, cancelled_task_ids))
+    }
+
+    /// Returns the tasks assigned to and cancelled for the executor that
+    /// weren't delivered to it yet, None if there are none
+    pub async fn task_delta(
+        &self,
+        executor_id: &str,
+        delivery: &mut TaskDelivery,
+    ) -> Result<Option<TaskDelta>> {
+        let (tasks, cancelled_task_ids) = self.heartbeat(executor_id).await?;
+        Ok(delivery.delta(tasks, cancelled_task_ids))
+    }
+
+    pub async fn acknowledge_cancelled_tasks(
+        &self,
+        executor_id: &str,
+        task_ids: Vec<String>,
+    ) -> Result<()> {
+        self.shared_state
+            .acknowledge_cancelled_tasks(executor_id, task_ids)
+            .await
+    }
+
+    /// Stops assigning tasks to the executor and hands the tasks it didn't
+    /// start to other executors
+    pub async fn drain_executor(
+        &self,
+        executor_id: &str,
+        unstarted_task_ids: Vec<String>,
+    ) -> Result<()> {
+        info!(
+            "draining executor: {}, returned tasks: {:?}",
+            executor_id, unstarted_task_ids
+        );
+        self.shared_state
+            .drain_executor(executor_id, unstarted_task_ids)
+            .await
+    }
+
     pub async fn remove_executor(&self, executor_id: &str) -> Result<()> {
         info!("removing executor: {}", executor_id);
         self.shared_state.remove_executor(executor_id).await?;
@@ -242,10 +464,12 @@ impl Coordinator {
         &self,
         addr: &str,
         executor_id: &str,
-        extractor: internal_api::ExtractorDescription,
+        extractors: Vec<internal_api::ExtractorDescription>,
+        batch_size: u32,
+        labels: HashMap<String, String>,
     ) -> Result<()> {
         self.shared_state
-            .register_executor(addr, executor_id, extractor)
+            .register_executor(addr, executor_id, extractors, batch_size.max(1), labels)
             .await
     }
 
@@ -267,29 +491,11 @@ impl Coordinator {
 
     pub async fn create_binding(
         &self,
-        binding: internal_api::ExtractorBinding,
+        mut binding: internal_api::ExtractorBinding,
         extractor: internal_api::ExtractorDescription,
     ) -> Result<()> {
-        let input_params_schema = JSONSchema::compile(&extractor.input_params).map_err(|e| {
-            anyhow!(
-                "unable to compile json schema for input params: {:?}, error: {:?}",
-                &extractor.input_params,
-                e
-            )
-        })?;
-        let extractor_params_schema = binding.input_params.clone();
-        let validation_result = input_params_schema.validate(&extractor_params_schema);
-        if let Err(errors) = validation_result {
-            let errors = errors
-                .into_iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<String>>();
-            return Err(anyhow!(
-                "unable to validate input params for extractor binding: {}, errors: {}",
-                &binding.name,
-                errors.join(",")
-            ));
-        }
+        binding.input_params =
+            validate_input_params(&extractor.input_params, binding.input_params)?;
         let extraction_event = internal_api::ExtractionEvent {
             id: nanoid::nanoid!(),
             repository: binding.repository.clone(),
@@ -299,6 +505,7 @@ impl Coordinator {
             },
             created_at: timestamp_secs(),
             processed_at: None,
+            priority: None,
         };
         self.shared_state
             .create_binding(binding, extraction_event)
@@ -320,12 +527,18 @@ impl Coordinator {
         self.shared_state.get_state_change_watcher()
     }
 
+    /// Changes whenever tasks are assigned to or taken from executors
+    pub fn get_task_assignments_watcher(&self) -> Receiver<u64> {
+        self.shared_state.get_task_assignments_watcher()
+    }
+
     pub async fn create_content_metadata(
         &self,
         content_list: Vec<indexify_coordinator::ContentMetadata>,
+        priority: Option<u32>,
     ) -> Result<()> {
         let (content_meta_list, extraction_events) =
-            content_request_to_content_metadata(content_list)?;
+            content_request_to_content_metadata(content_list, priority)?;
         self.shared_state
             .create_content_batch(content_meta_list, extraction_events)
             .await?;
@@ -339,6 +552,7 @@ impl Coordinator {
 
 fn content_request_to_content_metadata(
     content_list: Vec<indexify_coordinator::ContentMetadata>,
+    priority: Option<u32>,
 ) -> Result<(
     Vec<internal_api::ContentMetadata>,
     Vec<internal_api::ExtractionEvent>,
@@ -355,22 +569,274 @@ fn content_request_to_content_metadata(
             payload: internal_api::ExtractionEventPayload::CreateContent { content: c },
             created_at: timestamp_secs(),
             processed_at: None,
+            priority,
         };
         extraction_events.push(extraction_event);
     }
     Ok((content_meta_list, extraction_events))
 }
 
+fn provenance_labels(
+    task: &internal_api::Task,
+    root_content_id: &str,
+    extractor_version: &str,
+) -> HashMap<String, String> {
+    HashMap::from([
+        (
+            internal_api::ROOT_CONTENT_ID_LABEL.to_string(),
+            root_content_id.to_string(),
+        ),
+        (
+            internal_api::BINDING_LABEL.to_string(),
+            task.extractor_binding.clone(),
+        ),
+        (
+            internal_api::EXTRACTOR_LABEL.to_string(),
+            task.extractor.clone(),
+        ),
+        (
+            internal_api::EXTRACTOR_VERSION_LABEL.to_string(),
+            extractor_version.to_string(),
+        ),
+    ])
+}
+
+/// Labels of a derived content, the labels set by the extractor override the
+/// inherited ones and can't override the provenance labels
+fn derived_labels(
+    inherited: &HashMap<String, String>,
+    extracted: HashMap<String, String>,
+    provenance: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut labels = inherited.clone();
+    labels.extend(extracted);
+    labels.extend(provenance.clone());
+    labels
+}
+
+/// Scheduling cost of a task. Higher priority tasks cost less, which gives
+/// their repository a bigger share of the executors.
+fn task_cost(task: &internal_api::Task) -> f64 {
+    1.0 / (task.priority as f64 + 1.0)
+}
+
+/// Assigns tasks to executors with weighted fair queuing across
+/// repositories. Every repository is a queue ordered by priority and then
+/// age, and the next task is always taken from the repository with the
+/// smallest virtual finish time, so a large backfill in one repository
+/// doesn't starve the others. Tasks of a repository with the same extractor,
+/// input params and priority are handed to an executor in batches of up to
+/// its preferred batch size. An executor is never given more than
+/// `max_tasks_per_executor` unfinished tasks.
+fn schedule_tasks(
+    unassigned_tasks: Vec<internal_api::Task>,
+    assigned_tasks: &HashMap<String, Vec<internal_api::Task>>,
+    executors_by_extractor: &HashMap<String, Vec<String>>,
+    batch_sizes: &HashMap<String, usize>,
+    executor_labels: &HashMap<String, HashMap<String, String>>,
+    placements: &HashMap<(String, String), internal_api::Placement>,
+    max_tasks_per_executor: usize,
+) -> HashMap<String, String> {
+    let no_labels = HashMap::new();
+    let no_placement = internal_api::Placement::default();
+    let placement_of = |task: &internal_api::Task| {
+        placements
+            .get(&(task.repository.clone(), task.extractor_binding.clone()))
+            .unwrap_or(&no_placement)
+    };
+    let mut capacity: HashMap<String, usize> = HashMap::new();
+    let mut virtual_time: HashMap<String, f64> = HashMap::new();
+    for (executor_id, tasks) in assigned_tasks {
+        capacity.insert(
+            executor_id.clone(),
+            max_tasks_per_executor.saturating_sub(tasks.len()),
+        );
+        for task in tasks {
+            *virtual_time.entry(task.repository.clone()).or_default() += task_cost(task);
+        }
+    }
+
+    let mut queues: BTreeMap<String, VecDeque<internal_api::Task>> = BTreeMap::new();
+    let unassigned_tasks = unassigned_tasks.into_iter().sorted_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.created_at.cmp(&b.created_at))
+            .then(a.id.cmp(&b.id))
+    });
+    for task in unassigned_tasks {
+        queues
+            .entry(task.repository.clone())
+            .or_default()
+            .push_back(task);
+    }
+
+    let mut task_assignments = HashMap::new();
+    loop {
+        let next_repository = queues
+            .iter()
+            .filter_map(|(repository, queue)| {
+                let finish_time = virtual_time.get(repository).copied().unwrap_or_default() +
+                    task_cost(queue.front()?);
+                Some((repository, finish_time))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(repository, _)| repository.clone());
+        let Some(repository) = next_repository else {
+            break;
+        };
+        let Some(task) = queues.get_mut(&repository).and_then(|q| q.pop_front()) else {
+            break;
+        };
+        // pick the executor allowed by the placement of the binding which
+        // matches most of its preferences, then the least loaded one which
+        // still has capacity
+        let placement = placement_of(&task);
+        let executor_id = executors_by_extractor
+            .get(&task.extractor)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| {
+                let labels = executor_labels.get(id).unwrap_or(&no_labels);
+                if !placement.allows(labels) {
+                    return None;
+                }
+                let free = *capacity.entry(id.clone()).or_insert(max_tasks_per_executor);
+                Some((id, placement.score(labels), free))
+            })
+            .filter(|(_, _, free)| *free > 0)
+            .max_by_key(|(_, score, free)| (*score, *free))
+            .map(|(id, ..)| id.clone());
+        let Some(executor_id) = executor_id else {
+            continue;
+        };
+        let free = capacity.get_mut(&executor_id).unwrap();
+        let batch_size = batch_sizes
+            .get(&executor_id)
+            .copied()
+            .unwrap_or(1)
+            .clamp(1, *free);
+        let mut batch = vec![task];
+        let queue = queues.get_mut(&repository).unwrap();
+        let mut i = 0;
+        while batch.len() < batch_size && i < queue.len() {
+            let candidate = &queue[i];
+            if candidate.extractor == batch[0].extractor &&
+                candidate.input_params == batch[0].input_params &&
+                candidate.priority == batch[0].priority &&
+                placement_of(candidate) == placement
+            {
+                batch.extend(queue.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        *free -= batch.len();
+        for task in batch {
+            *virtual_time.entry(repository.clone()).or_default() += task_cost(&task);
+            task_assignments.insert(task.id, executor_id.clone());
+        }
+    }
+    task_assignments
+}
+
+/// Tasks sent to an executor in the responses to its heartbeats
+#[derive(Debug, PartialEq)]
+pub struct TaskDelta {
+    pub version: u64,
+    /// Set on the first response of a heartbeat stream, which carries all
+    /// the tasks assigned to the executor
+    pub full: bool,
+    pub tasks: Vec<internal_api::Task>,
+    pub cancelled_task_ids: Vec<String>,
+}
+
+/// Tasks delivered to an executor over its heartbeat stream, so that every
+/// response only carries the tasks assigned or cancelled since the previous
+/// one. Cancellations are acknowledged once the executor acknowledges the
+/// version of the response which carried them.
+#[derive(Debug, Default)]
+pub struct TaskDelivery {
+    version: u64,
+    sent_tasks: HashSet<String>,
+    sent_cancellations: HashSet<String>,
+    unacked_cancellations: Vec<(u64, Vec<String>)>,
+}
+
+impl TaskDelivery {
+    /// Records the version acknowledged by the executor and returns the
+    /// cancellations it received. An executor acknowledging a version that
+    /// wasn't sent on this stream gets all its tasks again.
+    pub fn acknowledge(&mut self, acked_version: u64) -> Vec<String> {
+        if acked_version > self.version {
+            *self = Self::default();
+            return vec![];
+        }
+        let (acked, unacked) = std::mem::take(&mut self.unacked_cancellations)
+            .into_iter()
+            .partition(|(version, _)| *version <= acked_version);
+        self.unacked_cancellations = unacked;
+        acked
+            .into_iter()
+            .flat_map(|(_, task_ids): (u64, Vec<String>)| task_ids)
+            .collect()
+    }
+
+    /// Returns what changed since the previous delta given the tasks
+    /// currently assigned to and cancelled for the executor
+    pub fn delta(
+        &mut self,
+        assigned: Vec<internal_api::Task>,
+        cancelled_task_ids: Vec<String>,
+    ) -> Option<TaskDelta> {
+        let full = self.version == 0;
+        // forget the tasks which aren't assigned anymore
+        let assigned_ids: HashSet<&str> = assigned.iter().map(|t| t.id.as_str()).collect();
+        self.sent_tasks
+            .retain(|task_id| assigned_ids.contains(task_id.as_str()));
+        self.sent_cancellations
+            .retain(|task_id| cancelled_task_ids.contains(task_id));
+
+        let tasks = assigned
+            .into_iter()
+            .filter(|task| !self.sent_tasks.contains(&task.id))
+            .collect_vec();
+        let cancelled_task_ids = cancelled_task_ids
+            .into_iter()
+            .filter(|task_id| !self.sent_cancellations.contains(task_id))
+            .collect_vec();
+        if !full && tasks.is_empty() && cancelled_task_ids.is_empty() {
+            return None;
+        }
+        self.version += 1;
+        self.sent_tasks
+            .extend(tasks.iter().map(|task| task.id.clone()));
+        self.sent_cancellations
+            .extend(cancelled_task_ids.iter().cloned());
+        if !cancelled_task_ids.is_empty() {
+            self.unacked_cancellations
+                .push((self.version, cancelled_task_ids.clone()));
+        }
+        Some(TaskDelta {
+            version: self.version,
+            full,
+            tasks,
+            cancelled_task_ids,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::HashMap, sync::Arc, time::Duration};
 
     use indexify_internal_api as internal_api;
     use indexify_proto::indexify_coordinator;
+    use itertools::Itertools;
 
+    use super::{derived_labels, provenance_labels, schedule_tasks, Coordinator, TaskDelivery};
     use crate::{
         server_config::{ServerConfig, ServerPeer, SledConfig},
-        state::App,
+        state::{store::SledStorableTestFactory, App},
         test_util::db_utils::{mock_extractor, DEFAULT_TEST_EXTRACTOR, DEFAULT_TEST_REPOSITORY},
     };
 
@@ -378,9 +844,12 @@ mod tests {
     #[tracing_test::traced_test]
     async fn test_create_extraction_events() -> Result<(), anyhow::Error> {
         let config = Arc::new(ServerConfig::default());
-        let shared_state = App::new(config).await.unwrap();
+        let shared_state = App::new(config.clone()).await.unwrap();
         shared_state.initialize_raft().await.unwrap();
-        let coordinator = crate::coordinator::Coordinator::new(shared_state.clone());
+        let coordinator = crate::coordinator::Coordinator::new(
+            shared_state.clone(),
+            config.max_tasks_per_executor,
+        );
 
         // Add a repository
         coordinator
@@ -389,17 +858,20 @@ mod tests {
 
         // Add content and ensure that we are creating a extraction event
         coordinator
-            .create_content_metadata(vec![indexify_coordinator::ContentMetadata {
-                id: "test".to_string(),
-                repository: DEFAULT_TEST_REPOSITORY.to_string(),
-                parent_id: "".to_string(),
-                file_name: "test".to_string(),
-                mime: "text/plain".to_string(),
-                created_at: 0,
-                storage_url: "test".to_string(),
-                labels: HashMap::new(),
-                source: "ingestion".to_string(),
-            }])
+            .create_content_metadata(
+                vec![indexify_coordinator::ContentMetadata {
+                    id: "test".to_string(),
+                    repository: DEFAULT_TEST_REPOSITORY.to_string(),
+                    parent_id: "".to_string(),
+                    file_name: "test".to_string(),
+                    mime: "text/plain".to_string(),
+                    created_at: 0,
+                    storage_url: "test".to_string(),
+                    labels: HashMap::new(),
+                    source: "ingestion".to_string(),
+                }],
+                None,
+            )
             .await?;
 
         let events = shared_state.unprocessed_extraction_events().await?;
@@ -415,7 +887,13 @@ mod tests {
 
         // Add extractors and extractor bindings and ensure that we are creating tasks
         coordinator
-            .register_executor("localhost:8956", "test_executor_id", mock_extractor())
+            .register_executor(
+                "localhost:8956",
+                "test_executor_id",
+                vec![mock_extractor()],
+                1,
+                HashMap::new(),
+            )
             .await?;
         coordinator
             .create_binding(
@@ -435,6 +913,9 @@ mod tests {
                         "test_repository.test.test_output".to_string(),
                     )]),
                     content_source: "ingestion".to_string(),
+                    priority: 0,
+                    label_inheritance: internal_api::LabelInheritance::All,
+                    placement: internal_api::Placement::default(),
                 },
                 mock_extractor(),
             )
@@ -453,17 +934,20 @@ mod tests {
 
         // Add a content with a different source and ensure we don't create a task
         coordinator
-            .create_content_metadata(vec![indexify_coordinator::ContentMetadata {
-                id: "test2".to_string(),
-                repository: DEFAULT_TEST_REPOSITORY.to_string(),
-                parent_id: "test".to_string(),
-                file_name: "test2".to_string(),
-                mime: "text/plain".to_string(),
-                created_at: 0,
-                storage_url: "test2".to_string(),
-                labels: HashMap::new(),
-                source: "some_extractor_produced_this".to_string(),
-            }])
+            .create_content_metadata(
+                vec![indexify_coordinator::ContentMetadata {
+                    id: "test2".to_string(),
+                    repository: DEFAULT_TEST_REPOSITORY.to_string(),
+                    parent_id: "test".to_string(),
+                    file_name: "test2".to_string(),
+                    mime: "text/plain".to_string(),
+                    created_at: 0,
+                    storage_url: "test2".to_string(),
+                    labels: HashMap::new(),
+                    source: "some_extractor_produced_this".to_string(),
+                }],
+                None,
+            )
             .await?;
         coordinator.process_and_distribute_work().await?;
         assert_eq!(0, shared_state.unprocessed_extraction_events().await?.len());
@@ -478,6 +962,527 @@ mod tests {
         Ok(())
     }
 
+    fn test_task(id: &str, repository: &str, priority: u32) -> internal_api::Task {
+        internal_api::Task {
+            id: id.to_string(),
+            extractor: DEFAULT_TEST_EXTRACTOR.to_string(),
+            repository: repository.to_string(),
+            priority,
+            created_at: 0,
+            ..internal_api::Task::spawn_instance_for_store_test()
+        }
+    }
+
+    #[test]
+    fn test_derived_labels() {
+        let task = test_task("task", DEFAULT_TEST_REPOSITORY, 0);
+        let provenance = provenance_labels(&task, "root", "0.1.0");
+        let parent_labels = HashMap::from([
+            ("topic".to_string(), "news".to_string()),
+            ("secret".to_string(), "yes".to_string()),
+        ]);
+        let inherited = internal_api::LabelInheritance::Allow(vec!["topic".to_string()])
+            .inherited_labels(&parent_labels);
+        let labels = derived_labels(
+            &inherited,
+            HashMap::from([
+                ("page".to_string(), "1".to_string()),
+                (
+                    internal_api::ROOT_CONTENT_ID_LABEL.to_string(),
+                    "forged".to_string(),
+                ),
+            ]),
+            &provenance,
+        );
+        assert_eq!(labels.get("topic").unwrap(), "news");
+        assert_eq!(labels.get("page").unwrap(), "1");
+        assert!(!labels.contains_key("secret"));
+        assert_eq!(
+            labels.get(internal_api::ROOT_CONTENT_ID_LABEL).unwrap(),
+            "root"
+        );
+        assert_eq!(
+            labels.get(internal_api::BINDING_LABEL).unwrap(),
+            &task.extractor_binding
+        );
+        assert_eq!(
+            labels.get(internal_api::EXTRACTOR_LABEL).unwrap(),
+            DEFAULT_TEST_EXTRACTOR
+        );
+        assert_eq!(
+            labels.get(internal_api::EXTRACTOR_VERSION_LABEL).unwrap(),
+            "0.1.0"
+        );
+
+        let inherited = internal_api::LabelInheritance::None.inherited_labels(&parent_labels);
+        let labels = derived_labels(&inherited, HashMap::new(), &provenance);
+        assert_eq!(labels, provenance);
+    }
+
+    #[test]
+    fn test_task_delivery() {
+        let mut delivery = TaskDelivery::default();
+        let a = test_task("a", DEFAULT_TEST_REPOSITORY, 0);
+        let b = test_task("b", DEFAULT_TEST_REPOSITORY, 0);
+
+        // the first delta is full, even without tasks
+        let delta = delivery.delta(vec![], vec![]).unwrap();
+        assert!(delta.full);
+        assert_eq!(1, delta.version);
+        assert!(delivery.delta(vec![], vec![]).is_none());
+
+        // only new tasks are sent
+        let delta = delivery.delta(vec![a.clone()], vec![]).unwrap();
+        assert_eq!((2, false), (delta.version, delta.full));
+        assert_eq!(vec![a.clone()], delta.tasks);
+        let delta = delivery.delta(vec![a.clone(), b.clone()], vec![]).unwrap();
+        assert_eq!(vec![b.clone()], delta.tasks);
+
+        // cancellations are acknowledged with the version carrying them
+        let delta = delivery
+            .delta(vec![b.clone()], vec!["a".to_string()])
+            .unwrap();
+        assert_eq!(4, delta.version);
+        assert!(delta.tasks.is_empty());
+        assert_eq!(vec!["a".to_string()], delta.cancelled_task_ids);
+        assert!(delivery
+            .delta(vec![b.clone()], vec!["a".to_string()])
+            .is_none());
+        assert!(delivery.acknowledge(3).is_empty());
+        assert_eq!(vec!["a".to_string()], delivery.acknowledge(4));
+        assert!(delivery.acknowledge(4).is_empty());
+
+        // a version unknown to the stream resends everything
+        assert!(delivery.acknowledge(10).is_empty());
+        let delta = delivery.delta(vec![b.clone()], vec![]).unwrap();
+        assert!(delta.full);
+        assert_eq!(vec![b], delta.tasks);
+    }
+
+    #[test]
+    fn test_schedule_tasks() {
+        let executors_by_extractor = HashMap::from([(
+            DEFAULT_TEST_EXTRACTOR.to_string(),
+            vec!["executor1".to_string(), "executor2".to_string()],
+        )]);
+
+        // a backfill in one repository doesn't starve another repository
+        let mut unassigned = (0..10)
+            .map(|i| test_task(&format!("backfill{:02}", i), "repo1", 0))
+            .collect_vec();
+        unassigned.push(test_task("interactive", "repo2", 0));
+        let assignments = schedule_tasks(
+            unassigned,
+            &HashMap::new(),
+            &executors_by_extractor,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            2,
+        );
+        assert_eq!(4, assignments.len());
+        assert!(assignments.contains_key("interactive"));
+        for executor in ["executor1", "executor2"] {
+            assert_eq!(
+                2,
+                assignments
+                    .values()
+                    .filter(|e| e.as_str() == executor)
+                    .count()
+            );
+        }
+
+        // higher priority tasks are scheduled first within a repository
+        let assignments = schedule_tasks(
+            vec![test_task("low", "repo1", 0), test_task("high", "repo1", 5)],
+            &HashMap::from([(
+                "executor1".to_string(),
+                vec![test_task("running", "repo1", 0)],
+            )]),
+            &executors_by_extractor,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            1,
+        );
+        assert_eq!(
+            HashMap::from([("high".to_string(), "executor2".to_string())]),
+            assignments
+        );
+
+        // executors without capacity don't get any tasks
+        let full = HashMap::from([
+            ("executor1".to_string(), vec![test_task("a", "repo1", 0)]),
+            ("executor2".to_string(), vec![test_task("b", "repo1", 0)]),
+        ]);
+        let assignments = schedule_tasks(
+            vec![test_task("c", "repo2", 10)],
+            &full,
+            &executors_by_extractor,
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashMap::new(),
+            1,
+        );
+        assert!(assignments.is_empty());
+
+        // tasks with the same input params are handed out in batches
+        let mut unassigned = (1..=4)
+            .map(|i| test_task(&format!("a{}", i), "repo1", 0))
+            .collect_vec();
+        unassigned.push(internal_api::Task {
+            input_params: serde_json::json!({"other": true}),
+            ..test_task("b1", "repo1", 0)
+        });
+        let batch_sizes =
+            HashMap::from([("executor1".to_string(), 4), ("executor2".to_string(), 4)]);
+        let assignments = schedule_tasks(
+            unassigned,
+            &HashMap::new(),
+            &executors_by_extractor,
+            &batch_sizes,
+            &HashMap::new(),
+            &HashMap::new(),
+            10,
+        );
+        assert_eq!(5, assignments.len());
+        for id in ["a2", "a3", "a4"] {
+            assert_eq!(assignments["a1"], assignments[id]);
+        }
+        assert_ne!(assignments["a1"], assignments["b1"]);
+    }
+
+    #[test]
+    fn test_schedule_tasks_placement() {
+        let executors_by_extractor = HashMap::from([(
+            DEFAULT_TEST_EXTRACTOR.to_string(),
+            vec![
+                "executor1".to_string(),
+                "executor2".to_string(),
+                "executor3".to_string(),
+            ],
+        )]);
+        let labels = |labels: &[(&str, &str)]| {
+            labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let executor_labels = HashMap::from([
+            ("executor1".to_string(), labels(&[("zone", "eu")])),
+            (
+                "executor2".to_string(),
+                labels(&[("zone", "us"), ("gpu", "true")]),
+            ),
+            (
+                "executor3".to_string(),
+                labels(&[("zone", "eu"), ("gpu", "true")]),
+            ),
+        ]);
+        let placements = HashMap::from([
+            (
+                ("repo1".to_string(), "eu_only".to_string()),
+                internal_api::Placement {
+                    constraints: labels(&[("zone", "eu")]),
+                    preferences: labels(&[("gpu", "true")]),
+                },
+            ),
+            (
+                ("repo1".to_string(), "asia_only".to_string()),
+                internal_api::Placement {
+                    constraints: labels(&[("zone", "asia")]),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let binding_task = |id: &str, binding: &str| internal_api::Task {
+            extractor_binding: binding.to_string(),
+            ..test_task(id, "repo1", 0)
+        };
+
+        // constrained tasks only run on the executors allowed by the
+        // placement, the preferred executor first
+        let assignments = schedule_tasks(
+            vec![
+                binding_task("eu1", "eu_only"),
+                binding_task("eu2", "eu_only"),
+                binding_task("eu3", "eu_only"),
+                binding_task("asia", "asia_only"),
+            ],
+            &HashMap::new(),
+            &executors_by_extractor,
+            &HashMap::new(),
+            &executor_labels,
+            &placements,
+            2,
+        );
+        assert_eq!(
+            HashMap::from([
+                ("eu1".to_string(), "executor3".to_string()),
+                ("eu2".to_string(), "executor3".to_string()),
+                ("eu3".to_string(), "executor1".to_string()),
+            ]),
+            assignments
+        );
+
+        // tasks of bindings without a placement run anywhere
+        let assignments = schedule_tasks(
+            vec![binding_task("any", "other")],
+            &HashMap::from([
+                ("executor1".to_string(), vec![test_task("a", "repo1", 0)]),
+                ("executor3".to_string(), vec![test_task("b", "repo1", 0)]),
+            ]),
+            &executors_by_extractor,
+            &HashMap::new(),
+            &executor_labels,
+            &placements,
+            1,
+        );
+        assert_eq!(
+            HashMap::from([("any".to_string(), "executor2".to_string())]),
+            assignments
+        );
+    }
+
+    /// Binds the test extractor to the test repository and adds the
+    /// content, a task is created for every content
+    async fn create_test_tasks(
+        coordinator: &Coordinator,
+        content_ids: &[&str],
+    ) -> Result<(), anyhow::Error> {
+        coordinator
+            .create_binding(
+                internal_api::ExtractorBinding {
+                    id: "test-binding-id".to_string(),
+                    name: "test".to_string(),
+                    extractor: DEFAULT_TEST_EXTRACTOR.to_string(),
+                    repository: DEFAULT_TEST_REPOSITORY.to_string(),
+                    input_params: serde_json::json!({}),
+                    filters: HashMap::new(),
+                    output_index_name_mapping: HashMap::from([(
+                        "test_output".to_string(),
+                        "test.test_output".to_string(),
+                    )]),
+                    index_name_table_mapping: HashMap::from([(
+                        "test.test_output".to_string(),
+                        "test_repository.test.test_output".to_string(),
+                    )]),
+                    content_source: "ingestion".to_string(),
+                    priority: 0,
+                    label_inheritance: internal_api::LabelInheritance::All,
+                    placement: internal_api::Placement::default(),
+                },
+                mock_extractor(),
+            )
+            .await?;
+        for id in content_ids {
+            coordinator
+                .create_content_metadata(
+                    vec![indexify_coordinator::ContentMetadata {
+                        id: id.to_string(),
+                        repository: DEFAULT_TEST_REPOSITORY.to_string(),
+                        parent_id: "".to_string(),
+                        file_name: id.to_string(),
+                        mime: "text/plain".to_string(),
+                        created_at: 0,
+                        storage_url: id.to_string(),
+                        labels: HashMap::new(),
+                        source: "ingestion".to_string(),
+                    }],
+                    None,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_list_and_cancel_tasks() -> Result<(), anyhow::Error> {
+        let config = Arc::new(ServerConfig {
+            coordinator_port: 18900,
+            coordinator_addr: "localhost:18900".to_string(),
+            raft_port: 18901,
+            peers: vec![ServerPeer {
+                node_id: 0,
+                addr: "localhost:18901".to_string(),
+            }],
+            sled: SledConfig {
+                path: Some(format!("/tmp/indexify-test/tasks/{}", nanoid::nanoid!())),
+            },
+            ..Default::default()
+        });
+        let shared_state = App::new(config.clone()).await.unwrap();
+        shared_state.initialize_raft().await.unwrap();
+        let coordinator = crate::coordinator::Coordinator::new(
+            shared_state.clone(),
+            config.max_tasks_per_executor,
+        );
+
+        coordinator
+            .create_repository(DEFAULT_TEST_REPOSITORY)
+            .await?;
+        coordinator
+            .register_executor(
+                "localhost:8956",
+                "test_executor_id",
+                vec![mock_extractor()],
+                1,
+                HashMap::new(),
+            )
+            .await?;
+        create_test_tasks(&coordinator, &["content1", "content2"]).await?;
+        coordinator.process_and_distribute_work().await?;
+
+        let tasks = coordinator
+            .list_tasks(DEFAULT_TEST_REPOSITORY, "test", "", None, "", "", 0)
+            .await?;
+        assert_eq!(2, tasks.len());
+        assert!(tasks[0].id < tasks[1].id);
+        assert_eq!(Some("test_executor_id".to_string()), tasks[0].executor_id);
+        assert_eq!(1, tasks[0].attempts);
+
+        // pagination
+        let page = coordinator
+            .list_tasks(DEFAULT_TEST_REPOSITORY, "", "", None, "", &tasks[0].id, 0)
+            .await?;
+        assert_eq!(1, page.len());
+        assert_eq!(tasks[1].id, page[0].id);
+
+        // cancel a task and make sure the executor is told about it once
+        coordinator.cancel_task(&tasks[0].id).await?;
+        let task = coordinator.get_task(&tasks[0].id).await.unwrap();
+        assert_eq!(internal_api::TaskOutcome::Cancelled, task.outcome);
+        assert!(task.finished_at.is_some());
+        assert!(coordinator.cancel_task(&tasks[0].id).await.is_err());
+
+        let (assigned, cancelled) = coordinator.heartbeat("test_executor_id").await?;
+        assert_eq!(1, assigned.len());
+        assert_eq!(vec![tasks[0].id.clone()], cancelled);
+        coordinator
+            .acknowledge_cancelled_tasks("test_executor_id", cancelled)
+            .await?;
+        let (_, cancelled) = coordinator.heartbeat("test_executor_id").await?;
+        assert!(cancelled.is_empty());
+
+        let cancelled_tasks = coordinator
+            .list_tasks(
+                DEFAULT_TEST_REPOSITORY,
+                "",
+                "",
+                Some(internal_api::TaskOutcome::Cancelled),
+                "",
+                "",
+                0,
+            )
+            .await?;
+        assert_eq!(1, cancelled_tasks.len());
+
+        // finish the remaining task and check the binding stats
+        coordinator
+            .update_task(
+                &tasks[1].id,
+                "test_executor_id",
+                internal_api::TaskOutcome::Failed,
+                vec![],
+                Some("extractor crashed".to_string()),
+                Some("blobs/task.log".to_string()),
+            )
+            .await?;
+        let task = coordinator.get_task(&tasks[1].id).await.unwrap();
+        assert_eq!(Some("blobs/task.log".to_string()), task.logs_url);
+        let stats = coordinator
+            .list_binding_stats(DEFAULT_TEST_REPOSITORY)
+            .await?;
+        assert_eq!(1, stats.len());
+        let stats: internal_api::ExtractorBindingStats = stats[0].clone().into();
+        assert_eq!(2, stats.content_matched);
+        assert_eq!(0, stats.tasks_pending);
+        assert_eq!(0, stats.tasks_assigned);
+        assert_eq!(1, stats.tasks_failed);
+        assert_eq!(1, stats.tasks_cancelled);
+        assert_eq!(Some("extractor crashed".to_string()), stats.last_error);
+        assert_eq!(100.0, stats.percent_complete());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_drain_executor() -> Result<(), anyhow::Error> {
+        let config = Arc::new(ServerConfig {
+            coordinator_port: 18910,
+            coordinator_addr: "localhost:18910".to_string(),
+            raft_port: 18911,
+            peers: vec![ServerPeer {
+                node_id: 0,
+                addr: "localhost:18911".to_string(),
+            }],
+            sled: SledConfig {
+                path: Some(format!("/tmp/indexify-test/drain/{}", nanoid::nanoid!())),
+            },
+            ..Default::default()
+        });
+        let shared_state = App::new(config.clone()).await.unwrap();
+        shared_state.initialize_raft().await.unwrap();
+        let coordinator = Coordinator::new(shared_state.clone(), config.max_tasks_per_executor);
+        coordinator
+            .create_repository(DEFAULT_TEST_REPOSITORY)
+            .await?;
+        coordinator
+            .register_executor(
+                "localhost:8956",
+                "executor_a",
+                vec![mock_extractor()],
+                1,
+                HashMap::new(),
+            )
+            .await?;
+        create_test_tasks(&coordinator, &["content1", "content2"]).await?;
+        coordinator.process_and_distribute_work().await?;
+        let (tasks, _) = coordinator.heartbeat("executor_a").await?;
+        assert_eq!(2, tasks.len());
+        coordinator
+            .register_executor(
+                "localhost:8957",
+                "executor_b",
+                vec![mock_extractor()],
+                1,
+                HashMap::new(),
+            )
+            .await?;
+
+        // the unstarted task goes to the other executor, the draining
+        // executor keeps its running task
+        coordinator
+            .drain_executor("executor_a", vec![tasks[0].id.clone()])
+            .await?;
+        coordinator.process_and_distribute_work().await?;
+        let (drained_tasks, _) = coordinator.heartbeat("executor_a").await?;
+        assert_eq!(vec![tasks[1].id.clone()], ids(drained_tasks));
+        let (other_tasks, _) = coordinator.heartbeat("executor_b").await?;
+        assert_eq!(vec![tasks[0].id.clone()], ids(other_tasks));
+
+        // the tasks still running when the executor is removed are reassigned
+        coordinator.remove_executor("executor_a").await?;
+        coordinator.process_and_distribute_work().await?;
+        let (other_tasks, _) = coordinator.heartbeat("executor_b").await?;
+        assert_eq!(2, other_tasks.len());
+        let task = coordinator.get_task(&tasks[1].id).await.unwrap();
+        assert_eq!(Some("executor_b".to_string()), task.executor_id);
+
+        let stats = coordinator
+            .list_binding_stats(DEFAULT_TEST_REPOSITORY)
+            .await?;
+        let stats: internal_api::ExtractorBindingStats = stats[0].clone().into();
+        assert_eq!(0, stats.tasks_pending);
+        assert_eq!(2, stats.tasks_assigned);
+        Ok(())
+    }
+
+    fn ids(tasks: Vec<internal_api::Task>) -> Vec<String> {
+        tasks.into_iter().map(|task| task.id).collect()
+    }
+
     fn create_test_raft_configs(
         node_count: usize,
     ) -> Result<Vec<Arc<ServerConfig>>, anyhow::Error> {
@@ -81,9 +81,7 @@ mod test_list_content_filter {
 
         // no filters
         let filtered_content =
-            list_content_filter(content.clone().into_iter(), "", "", &no_labels_filter)
-                .into_iter()
-                .collect::<Vec<_>>();
+            list_content_filter(content.clone(), "", "", &no_labels_filter).collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 4);
         assert_eq!(filtered_content[0].id, "1");
         assert_eq!(filtered_content[1].id, "2");
@@ -91,48 +89,31 @@ mod test_list_content_filter {
         assert_eq!(filtered_content[3].id, "4");
 
         // source filter
-        let filtered_content = list_content_filter(
-            content.clone().into_iter(),
-            "source1",
-            "",
-            &no_labels_filter,
-        )
-        .into_iter()
-        .collect::<Vec<_>>();
+        let filtered_content =
+            list_content_filter(content.clone(), "source1", "", &no_labels_filter)
+                .collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 2);
         assert_eq!(filtered_content[0].id, "1");
         assert_eq!(filtered_content[1].id, "3");
 
         // parent_id and source filter
-        let filtered_content = list_content_filter(
-            content.clone().into_iter(),
-            "source1",
-            "parent2",
-            &no_labels_filter,
-        )
-        .into_iter()
-        .collect::<Vec<_>>();
+        let filtered_content =
+            list_content_filter(content.clone(), "source1", "parent2", &no_labels_filter)
+                .collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 1);
         assert_eq!(filtered_content[0].id, "3");
 
         // parent_id filter
-        let filtered_content = list_content_filter(
-            content.clone().into_iter(),
-            "",
-            "parent2",
-            &no_labels_filter,
-        )
-        .into_iter()
-        .collect::<Vec<_>>();
+        let filtered_content =
+            list_content_filter(content.clone(), "", "parent2", &no_labels_filter)
+                .collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 2);
         assert_eq!(filtered_content[0].id, "2");
         assert_eq!(filtered_content[1].id, "3");
 
         // labels filter - empty - skips the labels filter
         let filtered_content =
-            list_content_filter(content.clone().into_iter(), "", "", &no_labels_filter)
-                .into_iter()
-                .collect::<Vec<_>>();
+            list_content_filter(content.clone(), "", "", &no_labels_filter).collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 4);
 
         // labels filter - exact match
@@ -141,9 +122,8 @@ mod test_list_content_filter {
             labels.insert("key1".to_string(), "value1".to_string());
             labels
         };
-        let filtered_content = list_content_filter(content.clone().into_iter(), "", "", &labels_eq)
-            .into_iter()
-            .collect::<Vec<_>>();
+        let filtered_content =
+            list_content_filter(content.clone(), "", "", &labels_eq).collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 1);
         assert_eq!(filtered_content[0].id, "1");
 
@@ -154,9 +134,8 @@ mod test_list_content_filter {
             labels.insert("key2".to_string(), "value2".to_string());
             labels
         };
-        let filtered_content = list_content_filter(content.clone().into_iter(), "", "", &labels_eq)
-            .into_iter()
-            .collect::<Vec<_>>();
+        let filtered_content =
+            list_content_filter(content.clone(), "", "", &labels_eq).collect::<Vec<_>>();
         assert_eq!(filtered_content.len(), 1);
         assert_eq!(filtered_content[0].id, "3");
     }
@@ -254,18 +233,102 @@ mod test_extractor_mimetype_filter {
         mimetype_matcher(
             TestExtractor::TextPlain,
             vec![
-                (&mime::TEXT_PLAIN.to_string(), true),
-                (&mime::IMAGE_PNG.to_string(), false),
-                (&mime::APPLICATION_PDF.to_string(), false),
+                (mime::TEXT_PLAIN.as_ref(), true),
+                (mime::IMAGE_PNG.as_ref(), false),
+                (mime::APPLICATION_PDF.as_ref(), false),
             ],
         );
         mimetype_matcher(
             TestExtractor::Wildcard,
             vec![
-                (&mime::TEXT_PLAIN.to_string(), true),
-                (&mime::IMAGE_PNG.to_string(), true),
-                (&mime::APPLICATION_PDF.to_string(), true),
+                (mime::TEXT_PLAIN.as_ref(), true),
+                (mime::IMAGE_PNG.as_ref(), true),
+                (mime::APPLICATION_PDF.as_ref(), true),
             ],
         );
     }
 }
+
+/// filter for tasks
+pub fn list_tasks_filter<'a>(
+    tasks: impl IntoIterator<Item = internal_api::Task> + 'a,
+    extractor_binding: &'a str,
+    content_id: &'a str,
+    outcome: Option<internal_api::TaskOutcome>,
+    executor_id: &'a str,
+) -> impl Iterator<Item = internal_api::Task> + 'a {
+    tasks
+        .into_iter()
+        .filter(move |t| extractor_binding.is_empty() || t.extractor_binding == extractor_binding)
+        .filter(move |t| content_id.is_empty() || t.content_metadata.id == content_id)
+        .filter(move |t| outcome.as_ref().map_or(true, |o| t.outcome == *o))
+        .filter(move |t| executor_id.is_empty() || t.executor_id.as_deref() == Some(executor_id))
+}
+
+#[cfg(test)]
+mod test_list_tasks_filter {
+    use super::*;
+    use crate::state::store::SledStorableTestFactory;
+
+    #[test]
+    fn test_list_tasks_filter() {
+        let default = internal_api::Task::spawn_instance_for_store_test();
+        let tasks = vec![
+            internal_api::Task {
+                id: "1".to_string(),
+                extractor_binding: "binding1".to_string(),
+                outcome: internal_api::TaskOutcome::Unknown,
+                executor_id: None,
+                ..default.clone()
+            },
+            internal_api::Task {
+                id: "2".to_string(),
+                extractor_binding: "binding1".to_string(),
+                outcome: internal_api::TaskOutcome::Failed,
+                executor_id: Some("executor1".to_string()),
+                ..default.clone()
+            },
+            internal_api::Task {
+                id: "3".to_string(),
+                extractor_binding: "binding2".to_string(),
+                outcome: internal_api::TaskOutcome::Success,
+                executor_id: Some("executor2".to_string()),
+                ..default.clone()
+            },
+        ];
+
+        // no filters
+        let filtered = list_tasks_filter(tasks.clone(), "", "", None, "").collect::<Vec<_>>();
+        assert_eq!(filtered.len(), 3);
+
+        // binding filter
+        let filtered =
+            list_tasks_filter(tasks.clone(), "binding1", "", None, "").collect::<Vec<_>>();
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].id, "1");
+        assert_eq!(filtered[1].id, "2");
+
+        // outcome filter
+        let filtered = list_tasks_filter(
+            tasks.clone(),
+            "",
+            "",
+            Some(internal_api::TaskOutcome::Failed),
+            "",
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "2");
+
+        // executor filter
+        let filtered =
+            list_tasks_filter(tasks.clone(), "", "", None, "executor2").collect::<Vec<_>>();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "3");
+
+        // content id filter
+        let filtered =
+            list_tasks_filter(tasks.clone(), "", "unknown_content", None, "").collect::<Vec<_>>();
+        assert_eq!(filtered.len(), 0);
+    }
+}
@@ -14,12 +14,16 @@ use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator::{
     self,
     coordinator_service_server::CoordinatorService,
+    CancelTaskRequest,
+    CancelTaskResponse,
     CreateContentRequest,
     CreateContentResponse,
     CreateIndexRequest,
     CreateIndexResponse,
     CreateRepositoryRequest,
     CreateRepositoryResponse,
+    DrainExecutorRequest,
+    DrainExecutorResponse,
     ExtractorBindRequest,
     ExtractorBindResponse,
     GetContentMetadataRequest,
@@ -28,8 +32,12 @@ use indexify_proto::indexify_coordinator::{
     GetIndexResponse,
     GetRepositoryRequest,
     GetRepositoryResponse,
+    GetTaskRequest,
+    GetTaskResponse,
     HeartbeatRequest,
     HeartbeatResponse,
+    ListBindingStatsRequest,
+    ListBindingStatsResponse,
     ListBindingsRequest,
     ListBindingsResponse,
     ListContentRequest,
@@ -40,8 +48,12 @@ use indexify_proto::indexify_coordinator::{
     ListIndexesResponse,
     ListRepositoriesRequest,
     ListRepositoriesResponse,
+    ListTasksRequest,
+    ListTasksResponse,
     RegisterExecutorRequest,
     RegisterExecutorResponse,
+    RemoveExecutorRequest,
+    RemoveExecutorResponse,
     UpdateTaskRequest,
     UpdateTaskResponse,
 };
@@ -56,10 +68,11 @@ use tokio::{
 };
 use tokio_stream::{Stream, StreamExt};
 use tonic::{Request, Response, Status, Streaming};
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{
-    coordinator::Coordinator,
+    api_utils::InvalidInputParams,
+    coordinator::{Coordinator, TaskDelivery},
     server_config::ServerConfig,
     state::{self, store::StateChange},
     tonic_streamer::DropReceiver,
@@ -81,15 +94,20 @@ impl CoordinatorService for CoordinatorServiceServer {
         &self,
         request: tonic::Request<CreateContentRequest>,
     ) -> Result<tonic::Response<CreateContentResponse>, tonic::Status> {
-        let content_meta = request
-            .into_inner()
+        let request = request.into_inner();
+        let content_list = request
             .content
-            .ok_or(tonic::Status::aborted("content is missing"))?;
-        let id = content_meta.id.clone();
-        let content_list = vec![content_meta];
+            .into_iter()
+            .chain(request.content_list)
+            .collect_vec();
+        let id = content_list
+            .first()
+            .ok_or(tonic::Status::aborted("content is missing"))?
+            .id
+            .clone();
         let _ = self
             .coordinator
-            .create_content_metadata(content_list)
+            .create_content_metadata(content_list, request.priority)
             .await
             .map_err(|e| tonic::Status::aborted(e.to_string()))?;
         Ok(tonic::Response::new(CreateContentResponse { id }))
@@ -160,12 +178,24 @@ impl CoordinatorService for CoordinatorServiceServer {
             output_index_name_mapping: output_index_name_mapping.clone(),
             index_name_table_mapping: index_name_table_mapping.clone(),
             content_source: extractor_binding.content_source,
+            priority: extractor_binding.priority,
+            label_inheritance: internal_api::LabelInheritance::from_json(
+                &extractor_binding.label_inheritance,
+            )
+            .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?,
+            placement: internal_api::Placement {
+                constraints: extractor_binding.placement_constraints,
+                preferences: extractor_binding.placement_preferences,
+            },
         };
         let _ = self
             .coordinator
             .create_binding(extractor_binding, extractor.clone())
             .await
-            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+            .map_err(|e| match e.downcast_ref::<InvalidInputParams>() {
+                Some(invalid) => invalid.to_status(),
+                None => tonic::Status::aborted(e.to_string()),
+            })?;
         Ok(tonic::Response::new(ExtractorBindResponse {
             created_at: timestamp_secs() as i64,
             extractor: Some(extractor.into()),
@@ -262,12 +292,22 @@ impl CoordinatorService for CoordinatorServiceServer {
         request: tonic::Request<RegisterExecutorRequest>,
     ) -> Result<tonic::Response<RegisterExecutorResponse>, tonic::Status> {
         let request = request.into_inner();
-        let extractor = request
-            .extractor
-            .ok_or(tonic::Status::aborted("missing extractor"))?;
+        // executors registering a single extractor only set the deprecated
+        // extractor field
+        let extractors = match (request.extractors.is_empty(), request.extractor) {
+            (false, _) => request.extractors,
+            (true, Some(extractor)) => vec![extractor],
+            (true, None) => return Err(tonic::Status::aborted("missing extractor")),
+        };
         let _resp = self
             .coordinator
-            .register_executor(&request.addr, &request.executor_id, extractor.into())
+            .register_executor(
+                &request.addr,
+                &request.executor_id,
+                extractors.into_iter().map(|e| e.into()).collect(),
+                request.batch_size,
+                request.labels,
+            )
             .await
             .map_err(|e| tonic::Status::aborted(e.to_string()))?;
         Ok(tonic::Response::new(RegisterExecutorResponse {
@@ -286,12 +326,16 @@ impl CoordinatorService for CoordinatorServiceServer {
         let mut shutdown_rx = self.shutdown_rx.clone();
         tokio::spawn(async move {
             let mut executor_id = String::new();
+            let mut delivery = TaskDelivery::default();
+            let mut task_assignments_rx = coordinator.get_task_assignments_watcher();
             loop {
                 select! {
                     _ = shutdown_rx.changed() => {
                         info!("shutting down server, stopping heartbeats from executor: {}", executor_id);
                         return;
                     }
+                    // push new tasks right away instead of waiting for the next heartbeat
+                    Ok(()) = task_assignments_rx.changed(), if !executor_id.is_empty() => {}
                     frame = in_stream.next() => {
                         // Ensure the frame has something
                         if frame.as_ref().is_none() {
@@ -304,44 +348,80 @@ impl CoordinatorService for CoordinatorServiceServer {
                             }
                             break;
                         }
-                        executor_id = frame.unwrap().unwrap().executor_id.clone();
-                        let tasks = coordinator.heartbeat(&executor_id).await;
-                            if let Err(err) = &tasks {
-                                if let Err(err) =
-                                    tx.send(Err(tonic::Status::internal(err.to_string()))).await
-                                {
-                                    info!("heartbeats stopped, removing executor: {}", executor_id);
-                                    if let Err(err) = coordinator.remove_executor(&executor_id).await {
-                                        error!("error removing executor: {}", err);
-                                    }
-                                    error!(
-                                        "error sending error message in heartbeat response: {}",
-                                        err
-                                    );
-                                    return;
-                                }
-                                continue;
+                        let request = frame.unwrap().unwrap();
+                        executor_id = request.executor_id.clone();
+                        debug!(
+                            "heartbeat from executor: {}, running tasks: {}, pending tasks: {}",
+                            executor_id, request.running_tasks, request.pending_tasks
+                        );
+                        let cancelled_task_ids = delivery.acknowledge(request.acked_version);
+                        if !cancelled_task_ids.is_empty() {
+                            if let Err(err) = coordinator
+                                .acknowledge_cancelled_tasks(&executor_id, cancelled_task_ids)
+                                .await
+                            {
+                                error!("error acknowledging cancelled tasks: {}", err);
                             }
-                            let tasks = tasks
-                                .unwrap()
-                                .into_iter()
-                                .map(|t| t.into())
-                                .collect::<Vec<indexify_coordinator::Task>>();
-                            let resp = HeartbeatResponse {
-                                executor_id: executor_id.clone(),
-                                tasks,
-                            };
-                            if let Err(err) = tx.send(Ok(resp)).await {
-                                error!("error sending heartbeat response: {:?}", err);
-                                return;
+                        }
+                    }
+                }
+                let delta = match coordinator.task_delta(&executor_id, &mut delivery).await {
+                    Ok(Some(delta)) => delta,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        if let Err(err) =
+                            tx.send(Err(tonic::Status::internal(err.to_string()))).await
+                        {
+                            info!("heartbeats stopped, removing executor: {}", executor_id);
+                            if let Err(err) = coordinator.remove_executor(&executor_id).await {
+                                error!("error removing executor: {}", err);
                             }
+                            error!("error sending error message in heartbeat response: {}", err);
+                            return;
+                        }
+                        continue;
                     }
+                };
+                let resp = HeartbeatResponse {
+                    executor_id: executor_id.clone(),
+                    tasks: delta.tasks.into_iter().map(|t| t.into()).collect(),
+                    cancelled_task_ids: delta.cancelled_task_ids,
+                    version: delta.version,
+                    full: delta.full,
+                };
+                if let Err(err) = tx.send(Ok(resp)).await {
+                    error!("error sending heartbeat response: {:?}", err);
+                    return;
                 }
             }
         });
         Ok(tonic::Response::new(Box::pin(rx) as HBResponseStream))
     }
 
+    async fn drain_executor(
+        &self,
+        request: tonic::Request<DrainExecutorRequest>,
+    ) -> Result<tonic::Response<DrainExecutorResponse>, tonic::Status> {
+        let request = request.into_inner();
+        self.coordinator
+            .drain_executor(&request.executor_id, request.unstarted_task_ids)
+            .await
+            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        Ok(tonic::Response::new(DrainExecutorResponse {}))
+    }
+
+    async fn remove_executor(
+        &self,
+        request: tonic::Request<RemoveExecutorRequest>,
+    ) -> Result<tonic::Response<RemoveExecutorResponse>, tonic::Status> {
+        let request = request.into_inner();
+        self.coordinator
+            .remove_executor(&request.executor_id)
+            .await
+            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        Ok(tonic::Response::new(RemoveExecutorResponse {}))
+    }
+
     async fn update_task(
         &self,
         request: tonic::Request<UpdateTaskRequest>,
@@ -355,12 +435,85 @@ impl CoordinatorService for CoordinatorServiceServer {
                 &request.executor_id,
                 outcome,
                 request.content_list,
+                Some(request.error_message).filter(|e| !e.is_empty()),
+                Some(request.logs_url).filter(|url| !url.is_empty()),
             )
             .await
             .map_err(|e| tonic::Status::aborted(e.to_string()))?;
         Ok(tonic::Response::new(UpdateTaskResponse {}))
     }
 
+    async fn list_tasks(
+        &self,
+        request: Request<ListTasksRequest>,
+    ) -> Result<Response<ListTasksResponse>, Status> {
+        let req = request.into_inner();
+        let outcome = req
+            .outcome
+            .map(indexify_coordinator::TaskOutcome::try_from)
+            .transpose()
+            .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?
+            .map(internal_api::TaskOutcome::from);
+        let tasks = self
+            .coordinator
+            .list_tasks(
+                &req.repository,
+                &req.extractor_binding,
+                &req.content_id,
+                outcome,
+                &req.executor_id,
+                &req.start_id,
+                req.limit as usize,
+            )
+            .await
+            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        let tasks = tasks
+            .into_iter()
+            .map(|t| t.into())
+            .collect::<Vec<indexify_coordinator::Task>>();
+        Ok(Response::new(ListTasksResponse { tasks }))
+    }
+
+    async fn list_binding_stats(
+        &self,
+        request: Request<ListBindingStatsRequest>,
+    ) -> Result<Response<ListBindingStatsResponse>, Status> {
+        let req = request.into_inner();
+        let stats = self
+            .coordinator
+            .list_binding_stats(&req.repository)
+            .await
+            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        Ok(Response::new(ListBindingStatsResponse { stats }))
+    }
+
+    async fn get_task(
+        &self,
+        request: Request<GetTaskRequest>,
+    ) -> Result<Response<GetTaskResponse>, Status> {
+        let req = request.into_inner();
+        let task = self
+            .coordinator
+            .get_task(&req.task_id)
+            .await
+            .ok_or_else(|| tonic::Status::not_found(format!("task {} not found", req.task_id)))?;
+        Ok(Response::new(GetTaskResponse {
+            task: Some(task.into()),
+        }))
+    }
+
+    async fn cancel_task(
+        &self,
+        request: Request<CancelTaskRequest>,
+    ) -> Result<Response<CancelTaskResponse>, Status> {
+        let req = request.into_inner();
+        self.coordinator
+            .cancel_task(&req.task_id)
+            .await
+            .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        Ok(Response::new(CancelTaskResponse {}))
+    }
+
     async fn list_indexes(
         &self,
         request: Request<ListIndexesRequest>,
@@ -417,9 +570,11 @@ impl CoordinatorService for CoordinatorServiceServer {
             .get_extractor_coordinates(&req.extractor)
             .await
             .map_err(|e| tonic::Status::aborted(e.to_string()))?;
+        let extractor = self.coordinator.get_extractor(&req.extractor).await.ok();
         Ok(Response::new(
             indexify_coordinator::GetExtractorCoordinatesResponse {
                 addrs: extractor_coordinates,
+                extractor: extractor.map(|e| e.into()),
             },
         ))
     }
@@ -457,7 +612,7 @@ impl CoordinatorServer {
         let addr: SocketAddr = config.coordinator_lis_addr_sock()?;
         let shared_state = state::App::new(config.clone()).await?;
 
-        let coordinator = Coordinator::new(shared_state.clone());
+        let coordinator = Coordinator::new(shared_state.clone(), config.max_tasks_per_executor);
         info!("coordinator listening on: {}", addr.to_string());
         Ok(Self {
             addr,
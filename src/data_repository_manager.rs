@@ -3,12 +3,13 @@ use std::{
     fmt,
     hash::{Hash, Hasher},
     path::Path,
-    sync::Arc,
-    time::SystemTime,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Result};
 use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator::{
     self,
@@ -20,11 +21,13 @@ use indexify_proto::indexify_coordinator::{
     ListIndexesRequest,
     UpdateTaskRequest,
 };
+use itertools::Itertools;
 use nanoid::nanoid;
-use tracing::{error, info};
+use tracing::info;
 
 use crate::{
     api::{self, Content, EmbeddingSchema},
+    api_utils::InvalidInputParams,
     blob_storage::{BlobStorage, BlobStorageConfig, BlobStorageReader, BlobStorageWriter},
     coordinator_client::CoordinatorClient,
     extractor::ExtractedEmbeddings,
@@ -33,11 +36,30 @@ use crate::{
     vector_index::{ScoredText, VectorIndexManager},
 };
 
+/// Records of an uploaded file written to the blob store at once
+const RECORD_WRITE_CONCURRENCY: usize = 16;
+
+/// Content created by a single coordinator request
+const CREATE_CONTENT_BATCH_SIZE: usize = 1000;
+
+/// Id of the `position`-th content of the task result written with
+/// `idempotency_key`. The id is also the content's blob name, a result
+/// written again overwrites its content instead of duplicating it.
+pub fn result_content_id(idempotency_key: &str, position: usize) -> String {
+    let mut s = DefaultHasher::new();
+    idempotency_key.hash(&mut s);
+    position.hash(&mut s);
+    format!("{:x}", s.finish())
+}
+
 pub struct DataRepositoryManager {
     vector_index_manager: Arc<VectorIndexManager>,
     metadata_index_manager: Arc<MetadataIndexManager>,
     blob_storage: BlobStorage,
     coordinator_client: Arc<CoordinatorClient>,
+    /// Idempotency keys of recently written task results, results sent again
+    /// after they expire overwrite their content, see `result_content_id`
+    written_results: moka::future::Cache<String, ()>,
 }
 
 impl fmt::Debug for DataRepositoryManager {
@@ -59,6 +81,10 @@ impl DataRepositoryManager {
             metadata_index_manager: attribute_index_manager,
             blob_storage,
             coordinator_client,
+            written_results: moka::future::Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(Duration::from_secs(60 * 60))
+                .build(),
         })
     }
 
@@ -141,7 +167,11 @@ impl DataRepositoryManager {
             .get()
             .await?
             .create_binding(req)
-            .await?
+            .await
+            .map_err(|status| match InvalidInputParams::from_status(&status) {
+                Some(invalid) => anyhow::Error::from(invalid),
+                None => status.into(),
+            })?
             .into_inner();
         let mut index_names = Vec::new();
         let extractor = response.extractor.ok_or(anyhow!(
@@ -174,7 +204,7 @@ impl DataRepositoryManager {
                         .metadata_index_manager
                         .create_index(
                             repository,
-                            &index_name,
+                            index_name,
                             table_name,
                             &extractor.name,
                             &extractor_binding.name,
@@ -256,25 +286,115 @@ impl DataRepositoryManager {
     }
 
     #[tracing::instrument]
-    pub async fn add_texts(&self, repo_name: &str, content_list: Vec<api::Content>) -> Result<()> {
+    pub async fn list_tasks(
+        &self,
+        repository: &str,
+        filters: &api::ListTasksFilters,
+    ) -> Result<Vec<api::Task>> {
+        let outcome: Option<indexify_coordinator::TaskOutcome> =
+            filters.outcome.clone().map(|o| o.into());
+        let req = indexify_coordinator::ListTasksRequest {
+            repository: repository.to_string(),
+            extractor_binding: filters.extractor_binding.clone(),
+            content_id: filters.content_id.clone(),
+            outcome: outcome.map(|o| o as i32),
+            executor_id: filters.executor_id.clone(),
+            start_id: filters.start_id.clone(),
+            limit: filters.limit.unwrap_or_default(),
+        };
+        let response = self.coordinator_client.get().await?.list_tasks(req).await?;
+        response
+            .into_inner()
+            .tasks
+            .into_iter()
+            .map(|t| Ok(internal_api::Task::try_from(t)?.into()))
+            .collect()
+    }
+
+    #[tracing::instrument]
+    pub async fn list_binding_stats(
+        &self,
+        repository: &str,
+    ) -> Result<Vec<api::ExtractorBindingStats>> {
+        let req = indexify_coordinator::ListBindingStatsRequest {
+            repository: repository.to_string(),
+        };
+        let response = self
+            .coordinator_client
+            .get()
+            .await?
+            .list_binding_stats(req)
+            .await?;
+        Ok(response
+            .into_inner()
+            .stats
+            .into_iter()
+            .map(|s| s.into())
+            .collect())
+    }
+
+    /// Returns the task, `None` when it doesn't exist
+    #[tracing::instrument]
+    pub async fn get_task(&self, task_id: &str) -> Result<Option<api::Task>> {
+        let Some(task) = self.coordinator_task(task_id).await? else {
+            return Ok(None);
+        };
+        Ok(Some(internal_api::Task::try_from(task)?.into()))
+    }
+
+    async fn coordinator_task(&self, task_id: &str) -> Result<Option<indexify_coordinator::Task>> {
+        let req = indexify_coordinator::GetTaskRequest {
+            task_id: task_id.to_string(),
+        };
+        match self.coordinator_client.get().await?.get_task(req).await {
+            Ok(response) => Ok(response.into_inner().task),
+            Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+            Err(status) => Err(anyhow!("unable to get task: {}", status.message())),
+        }
+    }
+
+    /// Returns the logs captured while the task ran, `None` when the task
+    /// doesn't exist or didn't log anything
+    #[tracing::instrument]
+    pub async fn task_logs(&self, task_id: &str) -> Result<Option<Vec<u8>>> {
+        let task = self.coordinator_task(task_id).await?;
+        let Some(task) = task.filter(|task| !task.logs_url.is_empty()) else {
+            return Ok(None);
+        };
+        let logs = self.blob_storage.get(&[&task.logs_url]).await?;
+        logs.into_iter()
+            .next()
+            .map(Some)
+            .ok_or_else(|| anyhow!("logs of task {} not found in blob store", task_id))
+    }
+
+    #[tracing::instrument]
+    pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
+        let req = indexify_coordinator::CancelTaskRequest {
+            task_id: task_id.to_string(),
+        };
+        self.coordinator_client
+            .get()
+            .await?
+            .cancel_task(req)
+            .await
+            .map_err(|e| anyhow!("unable to cancel task: {}", e.message()))?;
+        Ok(())
+    }
+
+    #[tracing::instrument]
+    pub async fn add_texts(
+        &self,
+        repo_name: &str,
+        content_list: Vec<api::Content>,
+        priority: Option<u32>,
+    ) -> Result<()> {
         for text in content_list {
             let content_metadata = self
                 .write_content(repo_name, text, None, None, "ingestion")
                 .await?;
-            let req = CreateContentRequest {
-                content: Some(content_metadata),
-            };
-            self.coordinator_client
-                .get()
-                .await?
-                .create_content(GrpcHelper::into_req(req))
-                .await
-                .map_err(|e| {
-                    anyhow!(
-                        "unable to write content metadata to coordinator {}",
-                        e.to_string()
-                    )
-                })?;
+            self.create_content(vec![content_metadata], priority)
+                .await?;
         }
         Ok(())
     }
@@ -315,8 +435,38 @@ impl DataRepositoryManager {
         Ok(content_list)
     }
 
+    /// Streams the data of a content in chunks
+    pub async fn read_content_stream(
+        &self,
+        content_id: &str,
+    ) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let req = GetContentMetadataRequest {
+            content_list: vec![content_id.to_string()],
+        };
+        let content_metadata = self
+            .coordinator_client
+            .get()
+            .await?
+            .get_content_metadata(req)
+            .await?
+            .into_inner()
+            .content_list
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("content {} not found", content_id))?;
+        self.blob_storage
+            .get_stream(&content_metadata.storage_url)
+            .await
+    }
+
     #[tracing::instrument(skip(self, data))]
-    pub async fn upload_file(&self, repository: &str, data: Bytes, name: &str) -> Result<()> {
+    pub async fn upload_file(
+        &self,
+        repository: &str,
+        data: Bytes,
+        name: &str,
+        priority: Option<u32>,
+    ) -> Result<()> {
         let ext = Path::new(name)
             .extension()
             .unwrap_or_default()
@@ -333,8 +483,76 @@ impl DataRepositoryManager {
             .write_content(repository, content, Some(name), None, "ingestion")
             .await
             .map_err(|e| anyhow!("unable to write content to blob store: {}", e))?;
+        self.create_content(vec![content_metadata], priority).await
+    }
+
+    /// Uploads a structured file and the content of its records, whose
+    /// parent is the file. The content is written to the blob store first,
+    /// then created in batches, a failure can leave the first batches
+    /// created.
+    #[tracing::instrument(skip(self, data, records))]
+    pub async fn upload_records(
+        &self,
+        repository: &str,
+        data: Bytes,
+        name: &str,
+        records: Vec<api::Content>,
+        priority: Option<u32>,
+    ) -> Result<api::UploadRecordsResponse> {
+        let content_mime = mime_guess::from_path(name).first_or_octet_stream();
+        let content = api::Content {
+            content_type: content_mime.to_string(),
+            bytes: data.to_vec(),
+            labels: HashMap::new(),
+            features: vec![],
+        };
+        let file_metadata = self
+            .write_content(repository, content, Some(name), None, "ingestion")
+            .await
+            .map_err(|e| anyhow!("unable to write content to blob store: {}", e))?;
+        let content_id = file_metadata.id.clone();
+        let record_count = records.len();
+        let records_metadata: Vec<ContentMetadata> =
+            futures::stream::iter(records.into_iter().enumerate())
+                .map(|(i, record)| {
+                    let content_id = content_id.clone();
+                    async move {
+                        self.write_content(
+                            repository,
+                            record,
+                            Some(&format!("{}.{}", name, i)),
+                            Some(content_id),
+                            "ingestion",
+                        )
+                        .await
+                    }
+                })
+                .buffered(RECORD_WRITE_CONCURRENCY)
+                .try_collect()
+                .await
+                .map_err(|e| anyhow!("unable to write records to blob store: {}", e))?;
+        let content_list = std::iter::once(file_metadata)
+            .chain(records_metadata)
+            .collect_vec();
+        for batch in content_list.chunks(CREATE_CONTENT_BATCH_SIZE) {
+            self.create_content(batch.to_vec(), priority).await?;
+        }
+        Ok(api::UploadRecordsResponse {
+            content_id,
+            records: record_count,
+        })
+    }
+
+    /// Creates the content in a single write
+    async fn create_content(
+        &self,
+        content_list: Vec<ContentMetadata>,
+        priority: Option<u32>,
+    ) -> Result<()> {
         let req = CreateContentRequest {
-            content: Some(content_metadata),
+            content: None,
+            priority,
+            content_list,
         };
         self.coordinator_client
             .get()
@@ -358,9 +576,6 @@ impl DataRepositoryManager {
         parent_id: Option<String>,
         source: &str,
     ) -> Result<ContentMetadata> {
-        let current_ts_secs = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)?
-            .as_secs();
         let mut s = DefaultHasher::new();
         repository.hash(&mut s);
         content.bytes.hash(&mut s);
@@ -372,6 +587,22 @@ impl DataRepositoryManager {
             parent_id.hash(&mut s);
         }
         let id = format!("{:x}", s.finish());
+        self.write_content_with_id(repository, content, id, file_name, parent_id, source)
+            .await
+    }
+
+    async fn write_content_with_id(
+        &self,
+        repository: &str,
+        content: api::Content,
+        id: String,
+        file_name: String,
+        parent_id: Option<String>,
+        source: &str,
+    ) -> Result<ContentMetadata> {
+        let current_ts_secs = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
         let storage_url = self
             .write_to_blob_store(repository, &file_name, Bytes::from(content.bytes.clone()))
             .await
@@ -399,76 +630,220 @@ impl DataRepositoryManager {
         &self,
         extracted_content: api::WriteExtractedContent,
     ) -> Result<()> {
+        let idempotency_key = extracted_content.idempotency_key.clone();
+        if let Some(key) = &idempotency_key {
+            if self.is_result_written(key) {
+                info!("ignoring already written result {}", key);
+                return Ok(());
+            }
+        }
         let index_table_name = extracted_content.index_table_name.clone();
         let mut new_content_metadata = Vec::new();
-        for content in extracted_content.content_list {
+        for (position, content) in extracted_content.content_list.into_iter().enumerate() {
             let content: api::Content = content.into();
-            let content_metadata = self
-                .write_content(
+            let parent_id = Some(extracted_content.parent_content_id.to_string());
+            let mut content_metadata = match &idempotency_key {
+                Some(key) => {
+                    let id = result_content_id(key, position);
+                    self.write_content_with_id(
+                        &extracted_content.repository,
+                        content.clone(),
+                        id.clone(),
+                        id,
+                        parent_id,
+                        &extracted_content.extractor_binding,
+                    )
+                    .await?
+                }
+                None => {
+                    self.write_content(
+                        &extracted_content.repository,
+                        content.clone(),
+                        None,
+                        parent_id,
+                        &extracted_content.extractor_binding,
+                    )
+                    .await?
+                }
+            };
+            if let Some(file_name) = content.labels.get(internal_api::FILE_NAME_LABEL) {
+                content_metadata.file_name = file_name.clone();
+            }
+            new_content_metadata.push(content_metadata.clone());
+            for feature in content.features {
+                let index_table_name = extracted_content
+                    .index_table_names
+                    .get(&feature.name)
+                    .or(index_table_name.as_ref());
+                let Some(index_table_name) = index_table_name else {
+                    continue;
+                };
+                self.add_extracted_feature(
                     &extracted_content.repository,
-                    content.clone(),
-                    None,
-                    Some(extracted_content.parent_content_id.to_string()),
-                    &extracted_content.extractor_binding,
+                    index_table_name,
+                    &content_metadata,
+                    feature,
                 )
                 .await?;
-            new_content_metadata.push(content_metadata.clone());
-            if index_table_name.as_ref().is_none() {
-                continue;
             }
-            let index_table_name = index_table_name.as_ref().unwrap();
-            for feature in content.features {
-                match feature.feature_type {
-                    api::FeatureType::Embedding => {
-                        let embedding_payload: internal_api::Embedding =
-                            serde_json::from_value(feature.data).map_err(|e| {
-                                anyhow!("unable to get embedding from extracted data {}", e)
-                            })?;
-                        let embeddings = ExtractedEmbeddings {
-                            content_id: content_metadata.id.to_string(),
-                            embedding: embedding_payload.values,
-                        };
-                        self.vector_index_manager
-                            .add_embedding(&index_table_name.clone(), vec![embeddings])
-                            .await
-                            .map_err(|e| {
-                                anyhow!("unable to add embedding to vector index {}", e)
-                            })?;
-                    }
-                    api::FeatureType::Metadata => {
-                        let extracted_attributes = ExtractedMetadata::new(
-                            &content_metadata.id,
-                            &content_metadata.parent_id,
-                            feature.data.clone(),
-                            "extractor_name",
-                            &extracted_content.repository,
-                        );
-                        info!("adding metadata to index {}", feature.data.to_string());
-                        self.metadata_index_manager
-                            .add_metadata(
-                                &extracted_content.repository,
-                                &index_table_name.clone(),
-                                extracted_attributes,
-                            )
-                            .await?;
+        }
+
+        self.update_task(
+            &extracted_content.executor_id,
+            &extracted_content.task_id,
+            extracted_content.task_outcome,
+            new_content_metadata,
+            extracted_content.error_message,
+            extracted_content.logs,
+        )
+        .await?;
+        if let Some(key) = idempotency_key {
+            self.mark_result_written(key).await;
+        }
+        Ok(())
+    }
+
+    pub fn is_result_written(&self, idempotency_key: &str) -> bool {
+        self.written_results.contains_key(idempotency_key)
+    }
+
+    pub async fn mark_result_written(&self, idempotency_key: String) {
+        self.written_results.insert(idempotency_key, ()).await;
+    }
+
+    /// Writes content whose data arrives in `chunks` to the blob store as
+    /// the chunks are received. Content with an `id` is stored under it,
+    /// otherwise the id is derived from the data.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn write_content_stream(
+        &self,
+        repository: &str,
+        id: Option<String>,
+        mime: String,
+        labels: HashMap<String, String>,
+        parent_id: &str,
+        source: &str,
+        chunks: BoxStream<'static, Result<Bytes>>,
+    ) -> Result<ContentMetadata> {
+        let current_ts_secs = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_secs();
+        let mut s = DefaultHasher::new();
+        repository.hash(&mut s);
+        parent_id.hash(&mut s);
+        let hasher = Arc::new(Mutex::new(s));
+        let chunks = {
+            let hasher = hasher.clone();
+            chunks
+                .inspect(move |chunk| {
+                    if let Ok(chunk) = chunk {
+                        hasher.lock().unwrap().write(chunk);
                     }
-                    _ => {}
-                }
+                })
+                .boxed()
+        };
+        let file_name = id.clone().unwrap_or(nanoid!());
+        let storage_url = self
+            .blob_storage
+            .put_stream(&file_name, chunks)
+            .await
+            .map_err(|e| anyhow!("unable to write content to blob store: {}", e))?;
+        let id = id.unwrap_or_else(|| format!("{:x}", hasher.lock().unwrap().finish()));
+        // the name set by the extractor isn't used as the blob key, it may be
+        // any path
+        let file_name = labels
+            .get(internal_api::FILE_NAME_LABEL)
+            .cloned()
+            .unwrap_or(file_name);
+        Ok(ContentMetadata {
+            id,
+            file_name,
+            storage_url,
+            parent_id: parent_id.to_string(),
+            created_at: current_ts_secs as i64,
+            mime,
+            repository: repository.to_string(),
+            labels,
+            source: source.to_string(),
+        })
+    }
+
+    /// Adds a feature of extracted content to the index it is written to
+    pub async fn add_extracted_feature(
+        &self,
+        repository: &str,
+        index_table_name: &str,
+        content_metadata: &ContentMetadata,
+        feature: api::Feature,
+    ) -> Result<()> {
+        match feature.feature_type {
+            api::FeatureType::Embedding => {
+                let embedding_payload: internal_api::Embedding =
+                    serde_json::from_value(feature.data).map_err(|e| {
+                        anyhow!("unable to get embedding from extracted data {}", e)
+                    })?;
+                let embeddings = ExtractedEmbeddings {
+                    content_id: content_metadata.id.to_string(),
+                    embedding: embedding_payload.values,
+                };
+                self.vector_index_manager
+                    .add_embedding(index_table_name, vec![embeddings])
+                    .await
+                    .map_err(|e| anyhow!("unable to add embedding to vector index {}", e))?;
             }
+            api::FeatureType::Metadata => {
+                let extracted_attributes = ExtractedMetadata::new(
+                    &content_metadata.id,
+                    &content_metadata.parent_id,
+                    feature.data.clone(),
+                    "extractor_name",
+                    repository,
+                );
+                info!("adding metadata to index {}", feature.data.to_string());
+                self.metadata_index_manager
+                    .add_metadata(repository, index_table_name, extracted_attributes)
+                    .await?;
+            }
+            _ => {}
         }
+        Ok(())
+    }
 
-        let outcome: indexify_coordinator::TaskOutcome = extracted_content.task_outcome.into();
-
+    /// Records the outcome of a task and the content it extracted
+    pub async fn update_task(
+        &self,
+        executor_id: &str,
+        task_id: &str,
+        task_outcome: internal_api::TaskOutcome,
+        content_list: Vec<ContentMetadata>,
+        error_message: Option<String>,
+        logs: Option<String>,
+    ) -> Result<()> {
+        let outcome: indexify_coordinator::TaskOutcome = task_outcome.into();
+        let logs_url = match logs.filter(|logs| !logs.is_empty()) {
+            Some(logs) => self
+                .blob_storage
+                .put(&format!("task-{}.log", task_id), Bytes::from(logs))
+                .await
+                .map_err(|e| anyhow!("unable to write logs of task {}: {}", task_id, e))?,
+            None => String::new(),
+        };
         let req = UpdateTaskRequest {
-            executor_id: extracted_content.executor_id,
-            task_id: extracted_content.task_id,
+            executor_id: executor_id.to_string(),
+            task_id: task_id.to_string(),
             outcome: outcome as i32,
-            content_list: new_content_metadata,
+            content_list,
+            error_message: error_message.unwrap_or_default(),
+            logs_url,
         };
-        let res = self.coordinator_client.get().await?.update_task(req).await;
-        if let Err(err) = res {
-            error!("unable to update task: {}", err.to_string());
-        }
+        // fail the write so that the executor retries it, rewriting content
+        // is idempotent
+        self.coordinator_client
+            .get()
+            .await?
+            .update_task(req)
+            .await
+            .map_err(|e| anyhow!("unable to update task: {}", e.message()))?;
         Ok(())
     }
 
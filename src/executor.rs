@@ -1,18 +1,32 @@
-use std::{collections::HashMap, fmt, sync::Arc, time::SystemTime};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
 use anyhow::{anyhow, Context, Result};
+use futures::StreamExt;
 use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator::{HeartbeatRequest, RegisterExecutorRequest};
+use itertools::Itertools;
+use jsonschema::JSONSchema;
 use nanoid::nanoid;
 use serde_json::json;
-use tokio::sync::watch;
-use tokio_stream::{wrappers::WatchStream, StreamExt};
-use tracing::{error, info};
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::WatchStream;
+use tracing::{error, info, warn};
 
 use crate::{
     blob_storage::{BlobStorage, BlobStorageReader},
     coordinator_client::CoordinatorClient,
-    extractor::extractor_runner::ExtractorRunner,
+    extractor::{extractor_runner::ExtractorRunner, task_deadline, task_logs::TaskLogs},
+    ingestion_client::IngestionClient,
+    result_uploader::ResultUploader,
     server_config::ExecutorConfig,
     task_store::TaskStore,
 };
@@ -25,12 +39,147 @@ fn create_executor_id() -> String {
     format!("{}_{}", nanoid!(), host_name)
 }
 
+struct HostedExtractor {
+    runner: Arc<ExtractorRunner>,
+    description: internal_api::ExtractorDescription,
+    /// Compiled schemas of the attributes outputs
+    attributes_schemas: HashMap<String, JSONSchema>,
+}
+
+impl HostedExtractor {
+    fn new(runner: ExtractorRunner) -> Result<Self> {
+        let description: internal_api::ExtractorDescription = runner.info()?.into();
+        let mut attributes_schemas = HashMap::new();
+        for (name, output) in &description.outputs {
+            let internal_api::OutputSchema::Attributes(schema) = output else {
+                continue;
+            };
+            match JSONSchema::compile(schema) {
+                Ok(schema) => {
+                    attributes_schemas.insert(name.clone(), schema);
+                }
+                Err(err) => warn!(
+                    "not validating output {} of extractor {}, invalid schema: {}",
+                    name, description.name, err
+                ),
+            }
+        }
+        Ok(Self {
+            runner: Arc::new(runner),
+            description,
+            attributes_schemas,
+        })
+    }
+
+    /// Checks that every feature of the extracted content is a declared
+    /// output of the extractor and matches its schema
+    fn validate_features(&self, content_list: &[internal_api::Content]) -> Result<()> {
+        let features = content_list.iter().flat_map(|content| &content.features);
+        for feature in features {
+            let output = self.description.outputs.get(&feature.name).ok_or(anyhow!(
+                "feature {} is not a declared output of extractor {}",
+                feature.name,
+                self.description.name
+            ))?;
+            match (&feature.feature_type, output) {
+                (
+                    internal_api::FeatureType::Embedding,
+                    internal_api::OutputSchema::Embedding(schema),
+                ) => {
+                    let embedding: internal_api::Embedding =
+                        serde_json::from_value(feature.data.clone())
+                            .map_err(|e| anyhow!("embedding {} is invalid: {}", feature.name, e))?;
+                    if embedding.values.len() != schema.dim {
+                        return Err(anyhow!(
+                            "embedding {} has {} dimensions, expected {}",
+                            feature.name,
+                            embedding.values.len(),
+                            schema.dim
+                        ));
+                    }
+                }
+                (
+                    internal_api::FeatureType::Metadata,
+                    internal_api::OutputSchema::Attributes(_),
+                ) => {
+                    let Some(schema) = self.attributes_schemas.get(&feature.name) else {
+                        continue;
+                    };
+                    if let Err(errors) = schema.validate(&feature.data) {
+                        let errors = errors
+                            .map(|e| format!("{}: {}", e.instance_path, e))
+                            .join(", ");
+                        return Err(anyhow!(
+                            "metadata {} doesn't match its schema: {}",
+                            feature.name,
+                            errors
+                        ));
+                    }
+                }
+                (feature_type, _) => {
+                    return Err(anyhow!(
+                        "feature {} of type {:?} doesn't match the declared output type",
+                        feature.name,
+                        feature_type
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the executor reads the content of its tasks from
+enum ContentReader {
+    /// Straight from the blob store of the server
+    BlobStorage(BlobStorage),
+    /// Through the gRPC ingestion endpoint of the server
+    Ingestion(IngestionClient),
+}
+
+impl ContentReader {
+    fn new(executor_config: &ExecutorConfig) -> Result<Self> {
+        match &executor_config.ingestion_grpc_addr {
+            Some(addr) => Ok(Self::Ingestion(IngestionClient::new(
+                addr,
+                executor_config.ingestion_grpc_token.as_deref(),
+            )?)),
+            None => Ok(Self::BlobStorage(BlobStorage::new_with_config(
+                executor_config.blob_storage.clone(),
+            ))),
+        }
+    }
+
+    async fn get_content(
+        &self,
+        content_metadata: internal_api::ContentMetadata,
+    ) -> Result<internal_api::Content> {
+        let data = match self {
+            Self::BlobStorage(blob_storage) => blob_storage
+                .get(&[&content_metadata.storage_url])
+                .await?
+                .into_iter()
+                .next()
+                .context("no data returned. Invalid error")?,
+            Self::Ingestion(client) => client.read_content(&content_metadata.id).await?,
+        };
+        let extracted_content = internal_api::Content {
+            mime: content_metadata.content_type,
+            bytes: data,
+            features: vec![],
+            labels: content_metadata.labels,
+        };
+        Ok(extracted_content)
+    }
+}
+
 pub struct ExtractorExecutor {
     executor_config: Arc<ExecutorConfig>,
     pub executor_id: String,
-    extractor_runner: Arc<ExtractorRunner>,
-    extractor_description: internal_api::ExtractorDescription,
+    /// Extractors hosted by the executor by name
+    extractors: BTreeMap<String, HostedExtractor>,
     listen_addr: String,
+    content_reader: ContentReader,
 
     task_store: Arc<TaskStore>,
 }
@@ -48,19 +197,29 @@ impl ExtractorExecutor {
     #[tracing::instrument]
     pub async fn new(
         executor_config: Arc<ExecutorConfig>,
-        extractor_runner: ExtractorRunner,
+        extractor_runners: Vec<ExtractorRunner>,
         listen_addr: String,
         task_store: Arc<TaskStore>,
     ) -> Result<Self> {
-        let executor_id = create_executor_id();
-        let extractor_description = extractor_runner.info()?.into();
-        let extractor_runner = Arc::new(extractor_runner);
+        let executor_id = task_store.executor_id(create_executor_id())?;
+        let mut extractors = BTreeMap::new();
+        for extractor_runner in extractor_runners {
+            let hosted = HostedExtractor::new(extractor_runner)?;
+            let name = hosted.description.name.clone();
+            if extractors.insert(name.clone(), hosted).is_some() {
+                return Err(anyhow!("extractor {} is hosted more than once", name));
+            }
+        }
+        if extractors.is_empty() {
+            return Err(anyhow!("executor doesn't host any extractor"));
+        }
+        let content_reader = ContentReader::new(&executor_config)?;
         let extractor_executor = Self {
             executor_config,
             executor_id,
-            extractor_runner,
-            extractor_description,
+            extractors,
             listen_addr,
+            content_reader,
             task_store,
         };
         Ok(extractor_executor)
@@ -75,70 +234,334 @@ impl ExtractorExecutor {
                 .unwrap()
                 .as_secs(),
             addr: self.executor_config.listen_if.clone().into(),
-            extractor: self.extractor_runner.info().unwrap().into(),
+            extractors: self.extractor_descriptions(),
         }
     }
 
+    fn extractor_descriptions(&self) -> Vec<internal_api::ExtractorDescription> {
+        self.extractors
+            .values()
+            .map(|extractor| extractor.description.clone())
+            .collect()
+    }
+
+    /// Returns the extractor with the given name. The name can be omitted if
+    /// the executor hosts a single extractor.
+    fn hosted_extractor(&self, extractor_name: Option<&str>) -> Result<&HostedExtractor> {
+        let extractor = match extractor_name {
+            Some(name) => self
+                .extractors
+                .get(name)
+                .ok_or(anyhow!("extractor {} is not hosted by this executor", name))?,
+            None if self.extractors.len() == 1 => self.extractors.values().next().unwrap(),
+            None => {
+                return Err(anyhow!(
+                    "executor hosts {} extractors, the extractor name is required",
+                    self.extractors.len()
+                ))
+            }
+        };
+        Ok(extractor)
+    }
+
     #[tracing::instrument]
     pub async fn extract(
         &self,
+        extractor_name: Option<&str>,
         content: internal_api::Content,
         input_params: Option<serde_json::Value>,
     ) -> Result<Vec<internal_api::Content>, anyhow::Error> {
+        let extractor = self.hosted_extractor(extractor_name)?;
         let extracted_content = self
-            .extractor_runner
-            .extract(vec![content], input_params.unwrap_or(json!({})))?;
+            .run_extractor(
+                extractor.runner.clone(),
+                vec![content],
+                input_params.unwrap_or(json!({})),
+                None,
+            )
+            .await?;
         let content = extracted_content
             .first()
             .ok_or(anyhow!("no content was extracted"))?
             .to_owned();
+        extractor.validate_features(&content)?;
         Ok(content)
     }
 
-    #[tracing::instrument(skip(self))]
-    pub async fn execute_pending_tasks(&self) -> Result<(), anyhow::Error> {
-        let tasks = self.task_store.pending_tasks();
+    #[tracing::instrument(skip(self, uploader))]
+    pub async fn execute_pending_tasks(
+        &self,
+        uploader: &ResultUploader,
+    ) -> Result<(), anyhow::Error> {
+        let batch_size = self.executor_config.batch_size.max(1) as usize;
+        let workers = self.executor_config.workers.max(1);
+        let results =
+            futures::stream::iter(batch_tasks(self.task_store.pending_tasks(), batch_size))
+                .map(|batch| self.execute_batch(batch, uploader))
+                .buffer_unordered(workers)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+        self.task_store.update(results);
+        Ok(())
+    }
+
+    /// Runs a batch of tasks sharing the same extractor and input params
+    /// with a single call to the extractor and splits the output back per
+    /// task. The tasks of streaming extractors are run one by one.
+    async fn execute_batch(
+        &self,
+        tasks: Vec<internal_api::Task>,
+        uploader: &ResultUploader,
+    ) -> Vec<internal_api::TaskResult> {
+        let tasks = self.task_store.start(tasks);
+        let Some(extractor) = tasks.first().map(|t| t.extractor.clone()) else {
+            return vec![];
+        };
+        let extractor = match self.hosted_extractor(Some(&extractor)) {
+            Ok(extractor) => extractor,
+            Err(err) => {
+                return tasks
+                    .iter()
+                    .map(|task| internal_api::TaskResult::failed(&task.id, Some(err.to_string())))
+                    .collect()
+            }
+        };
         let mut results = Vec::new();
+        let mut batch_tasks = Vec::new();
+        let mut batch_content = Vec::new();
         for task in tasks {
             info!("performing task: {}", &task.id);
-            let content = get_content(task.content_metadata).await;
-            if let Err(err) = &content {
-                info!("failed to get content: {}", err);
-                results.push(internal_api::TaskResult::failed(
-                    &task.id,
-                    Some(err.to_string()),
-                ));
-                continue;
+            match self
+                .content_reader
+                .get_content(task.content_metadata.clone())
+                .await
+            {
+                Ok(content) => {
+                    batch_tasks.push(task);
+                    batch_content.push(content);
+                }
+                Err(err) => {
+                    info!("failed to get content: {}", err);
+                    results.push(internal_api::TaskResult::failed(
+                        &task.id,
+                        Some(err.to_string()),
+                    ));
+                }
+            }
+        }
+        if extractor.runner.streams() {
+            for (task, content) in batch_tasks.iter().zip(batch_content) {
+                results.push(
+                    self.execute_streaming_task(extractor, task, content, uploader)
+                        .await,
+                );
+            }
+            return results;
+        }
+        let Some(input_params) = batch_tasks.first().map(|t| t.input_params.clone()) else {
+            return results;
+        };
+        // the tasks of a batch share the logs of the extractor call
+        let logs = TaskLogs::new(self.executor_config.max_task_log_bytes);
+        let extracted_content_batch = self
+            .run_extractor(
+                extractor.runner.clone(),
+                batch_content,
+                input_params,
+                Some(logs.clone()),
+            )
+            .await
+            .and_then(|batch| {
+                if batch.len() != batch_tasks.len() {
+                    return Err(anyhow!(
+                        "extractor returned {} results for {} inputs",
+                        batch.len(),
+                        batch_tasks.len()
+                    ));
+                }
+                Ok(batch)
+            });
+        match extracted_content_batch {
+            Ok(batch) => {
+                for (task, extracted_content_list) in batch_tasks.iter().zip(batch) {
+                    let result = match extractor.validate_features(&extracted_content_list) {
+                        Ok(()) => {
+                            internal_api::TaskResult::success(&task.id, extracted_content_list)
+                        }
+                        Err(err) => {
+                            info!("invalid output of task {}: {}", task.id, err);
+                            internal_api::TaskResult::failed(&task.id, Some(err.to_string()))
+                        }
+                    };
+                    results.push(result.with_logs(logs.contents()));
+                }
             }
-            let content = content.unwrap();
-            let extracted_content_batch = self
-                .extractor_runner
-                .extract(vec![content], task.input_params.clone());
-            if let Err(err) = &extracted_content_batch {
+            Err(err) => {
                 info!("failed to extract content: {}", err);
-                results.push(internal_api::TaskResult::failed(
-                    &task.id,
-                    Some(err.to_string()),
-                ));
-                continue;
+                for task in &batch_tasks {
+                    results.push(
+                        internal_api::TaskResult::failed(&task.id, Some(err.to_string()))
+                            .with_logs(logs.contents()),
+                    );
+                }
             }
+        }
+        results
+    }
 
-            for extracted_content_list in extracted_content_batch.unwrap() {
-                results.push(internal_api::TaskResult::success(
-                    &task.id,
-                    extracted_content_list,
-                ));
+    /// Runs a task of a streaming extractor, its output is written to the
+    /// ingestion server in batches as it's produced and only the content
+    /// produced after the last batch is part of the task result
+    async fn execute_streaming_task(
+        &self,
+        extractor: &HostedExtractor,
+        task: &internal_api::Task,
+        content: internal_api::Content,
+        uploader: &ResultUploader,
+    ) -> internal_api::TaskResult {
+        let logs = TaskLogs::new(self.executor_config.max_task_log_bytes);
+        let mut part = 0;
+        let result = self
+            .run_extractor_stream(
+                extractor.runner.clone(),
+                content,
+                task.input_params.clone(),
+                logs.clone(),
+                |content_list| {
+                    part += 1;
+                    let part = part - 1;
+                    async move {
+                        extractor.validate_features(&content_list)?;
+                        uploader
+                            .upload_partial(&self.executor_id, task, part, content_list)
+                            .await
+                    }
+                },
+            )
+            .await
+            .and_then(|content_list| {
+                extractor.validate_features(&content_list)?;
+                Ok(content_list)
+            });
+        let result = match result {
+            Ok(content_list) => internal_api::TaskResult::success(&task.id, content_list),
+            Err(err) => {
+                info!("failed to extract content of task {}: {}", task.id, err);
+                internal_api::TaskResult::failed(&task.id, Some(err.to_string()))
+            }
+        };
+        result.with_logs(logs.contents())
+    }
+
+    /// Runs a streaming extractor on a blocking thread, passing every
+    /// `stream_flush_size` content it produces to `flush`, and returns the
+    /// content produced after the last flush. An extraction exceeding the
+    /// task timeout is abandoned, the extractor stops at its next output or
+    /// when it is interrupted at the deadline.
+    async fn run_extractor_stream<F, Fut>(
+        &self,
+        extractor_runner: Arc<ExtractorRunner>,
+        content: internal_api::Content,
+        input_params: serde_json::Value,
+        logs: TaskLogs,
+        mut flush: F,
+    ) -> Result<Vec<internal_api::Content>>
+    where
+        F: FnMut(Vec<internal_api::Content>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let flush_size = self.executor_config.stream_flush_size.max(1);
+        // bounded so that a fast extractor waits for its output to be written
+        let (tx, mut rx) = mpsc::channel(flush_size);
+        let timeout = Duration::from_secs(self.executor_config.task_timeout_secs);
+        let task_deadline = Instant::now() + timeout;
+        let extraction = tokio::task::spawn_blocking(move || {
+            task_deadline::with_deadline(task_deadline, || {
+                logs.capture(|| -> Result<()> {
+                    for content in extractor_runner.extract_stream(content, input_params)? {
+                        if tx.blocking_send(content).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(())
+                })
+            })
+        });
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(deadline);
+        let mut content_list = Vec::new();
+        loop {
+            let content = tokio::select! {
+                content = rx.recv() => content,
+                _ = &mut deadline => {
+                    return Err(anyhow!(
+                        "extraction timed out after {} seconds",
+                        timeout.as_secs()
+                    ))
+                }
+            };
+            let Some(content) = content else {
+                break;
+            };
+            content_list.push(content?);
+            if content_list.len() >= flush_size {
+                flush(std::mem::take(&mut content_list)).await?;
             }
         }
-        self.task_store.update(results);
-        Ok(())
+        match extraction.await {
+            Ok(result) => result.map(|()| content_list),
+            Err(err) => Err(anyhow!("extractor panicked: {}", err)),
+        }
+    }
+
+    /// Runs the extractor on a blocking thread so that slow extractors don't
+    /// stall the runtime, capturing its output in `logs` when set. A call
+    /// exceeding the task timeout is abandoned, extractors which can be
+    /// interrupted stop at the deadline, others finish in the background and
+    /// their output is discarded.
+    async fn run_extractor(
+        &self,
+        extractor_runner: Arc<ExtractorRunner>,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+        logs: Option<TaskLogs>,
+    ) -> Result<Vec<Vec<internal_api::Content>>> {
+        let timeout = Duration::from_secs(self.executor_config.task_timeout_secs);
+        let deadline = Instant::now() + timeout;
+        let extraction = tokio::task::spawn_blocking(move || {
+            let extract = || extractor_runner.extract(content, input_params);
+            task_deadline::with_deadline(deadline, || match logs {
+                Some(logs) => logs.capture(extract),
+                None => extract(),
+            })
+        });
+        match tokio::time::timeout(timeout, extraction).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => Err(anyhow!("extractor panicked: {}", err)),
+            Err(_) => Err(anyhow!(
+                "extraction timed out after {} seconds",
+                timeout.as_secs()
+            )),
+        }
     }
 
     pub async fn register(&self, coordinator_client: Arc<CoordinatorClient>) -> Result<()> {
+        let extractors: Vec<_> = self
+            .extractor_descriptions()
+            .into_iter()
+            .map(|extractor| extractor.into())
+            .collect();
         let req = RegisterExecutorRequest {
             executor_id: self.executor_id.clone(),
             addr: self.listen_addr.clone(),
-            extractor: Some(self.extractor_description.clone().into()),
+            // still set for coordinators that only read a single extractor
+            extractor: extractors.first().cloned(),
+            batch_size: self.executor_config.batch_size,
+            extractors,
+            labels: self.executor_config.labels.clone(),
         };
         let _resp = coordinator_client
             .get()
@@ -150,30 +573,59 @@ impl ExtractorExecutor {
     }
 }
 
-async fn get_content(
-    content_metadata: internal_api::ContentMetadata,
-) -> Result<internal_api::Content> {
-    let data = BlobStorage::new()
-        .get(&[&content_metadata.storage_url])
-        .await?
+/// Groups tasks with the same extractor and input params into batches of at
+/// most `batch_size` tasks. Higher priority and older tasks come first.
+fn batch_tasks(tasks: Vec<internal_api::Task>, batch_size: usize) -> Vec<Vec<internal_api::Task>> {
+    let tasks = tasks.into_iter().sorted_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.created_at.cmp(&b.created_at))
+            .then(a.id.cmp(&b.id))
+    });
+    let mut groups: Vec<((String, serde_json::Value), Vec<internal_api::Task>)> = Vec::new();
+    for task in tasks {
+        match groups.iter_mut().find(|((extractor, params), _)| {
+            *extractor == task.extractor && *params == task.input_params
+        }) {
+            Some((_, group)) => group.push(task),
+            None => groups.push((
+                (task.extractor.clone(), task.input_params.clone()),
+                vec![task],
+            )),
+        }
+    }
+    groups
         .into_iter()
-        .next()
-        .context("no data returned. Invalid error")?;
-    let extracted_content = internal_api::Content {
-        mime: content_metadata.content_type,
-        bytes: data,
-        features: vec![],
-        labels: HashMap::new(),
-    };
-    Ok(extracted_content)
+        .flat_map(|(_, group)| {
+            group
+                .into_iter()
+                .chunks(batch_size)
+                .into_iter()
+                .map(|chunk| chunk.collect_vec())
+                .collect_vec()
+        })
+        .collect()
 }
 
+/// Receives the tasks assigned to the executor over the heartbeat stream.
+/// The coordinator sends the tasks assigned since its previous response,
+/// every heartbeat acknowledges the last response received.
 pub async fn heartbeat(
     task_store: Arc<TaskStore>,
     coordinator_client: Arc<CoordinatorClient>,
     heartbeat_rx: watch::Receiver<HeartbeatRequest>,
 ) -> Result<()> {
-    let req_stream = WatchStream::new(heartbeat_rx);
+    let acked_version = Arc::new(AtomicU64::new(0));
+    let req_stream = WatchStream::new(heartbeat_rx).map({
+        let task_store = task_store.clone();
+        let acked_version = acked_version.clone();
+        move |req| HeartbeatRequest {
+            acked_version: acked_version.load(Ordering::SeqCst),
+            running_tasks: task_store.running() as u32,
+            pending_tasks: task_store.pending() as u32,
+            ..req
+        }
+    });
     let response = coordinator_client
         .get()
         .await?
@@ -187,6 +639,18 @@ pub async fn heartbeat(
             break;
         }
         let hb_resp = received.map_err(|e| anyhow!("error receiving heartbeat: {:?}", e))?;
+        if hb_resp.full {
+            // drop the tasks handed to other executors while this one was
+            // disconnected
+            let assigned: HashSet<&str> = hb_resp.tasks.iter().map(|t| t.id.as_str()).collect();
+            let unassigned = task_store
+                .pending_tasks()
+                .into_iter()
+                .map(|task| task.id)
+                .filter(|task_id| !assigned.contains(task_id.as_str()))
+                .collect();
+            task_store.cancel(unassigned);
+        }
         let mut tasks = Vec::new();
         for task in hb_resp.tasks {
             if task_store.has_finished(&task.id) {
@@ -202,6 +666,392 @@ pub async fn heartbeat(
         if !tasks.is_empty() {
             task_store.add(tasks);
         }
+        task_store.cancel(hb_resp.cancelled_task_ids);
+        acked_version.store(hb_resp.version, Ordering::SeqCst);
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    use indexify_internal_api as internal_api;
+    use serde_json::json;
+
+    use super::{batch_tasks, ExtractorExecutor, HostedExtractor};
+    use crate::{
+        coordinator_client::CoordinatorClient,
+        extractor::{
+            extractor_runner::ExtractorRunner,
+            task_logs::{self, TaskLogs},
+            ContentStream,
+            EmbeddingSchema,
+            Extractor,
+            ExtractorSchema,
+        },
+        result_uploader::ResultUploader,
+        server_config::{ExecutorConfig, UploadConfig},
+        state::store::SledStorableTestFactory,
+        task_store::TaskStore,
+    };
+
+    #[derive(Debug)]
+    struct SlowExtractor;
+
+    impl Extractor for SlowExtractor {
+        fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+            Ok(ExtractorSchema::default())
+        }
+
+        fn extract(
+            &self,
+            content: Vec<internal_api::Content>,
+            _input_params: serde_json::Value,
+        ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+            std::thread::sleep(Duration::from_secs(2));
+            Ok(content.into_iter().map(|c| vec![c]).collect())
+        }
+    }
+
+    #[derive(Debug)]
+    struct EchoExtractor(&'static str);
+
+    impl Extractor for EchoExtractor {
+        fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+            Ok(ExtractorSchema {
+                name: self.0.to_string(),
+                ..Default::default()
+            })
+        }
+
+        fn extract(
+            &self,
+            content: Vec<internal_api::Content>,
+            _input_params: serde_json::Value,
+        ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+            Ok(content.into_iter().map(|c| vec![c]).collect())
+        }
+    }
+
+    fn test_uploader() -> ResultUploader {
+        ResultUploader::new(
+            UploadConfig::default(),
+            "localhost:0",
+            None,
+            Arc::new(CoordinatorClient::new("localhost:0")),
+        )
+    }
+
+    /// Streams a copy of its input per line of it
+    #[derive(Debug)]
+    struct LinesExtractor;
+
+    impl Extractor for LinesExtractor {
+        fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+            Ok(ExtractorSchema::default())
+        }
+
+        fn extract(
+            &self,
+            _content: Vec<internal_api::Content>,
+            _input_params: serde_json::Value,
+        ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+            Err(anyhow::anyhow!("LinesExtractor only streams"))
+        }
+
+        fn streams(&self) -> bool {
+            true
+        }
+
+        fn extract_stream(
+            &self,
+            content: internal_api::Content,
+            _input_params: serde_json::Value,
+        ) -> Result<ContentStream, anyhow::Error> {
+            let text = String::from_utf8(content.bytes)?;
+            task_logs::write(&format!("{} lines\n", text.lines().count()));
+            let lines = text
+                .lines()
+                .map(|line| match line {
+                    "error" => Err(anyhow::anyhow!("invalid line")),
+                    line => Ok(internal_api::Content {
+                        mime: "text/plain".to_string(),
+                        bytes: line.as_bytes().to_vec(),
+                        features: vec![],
+                        labels: HashMap::new(),
+                    }),
+                })
+                .collect::<Vec<_>>();
+            Ok(Box::new(lines.into_iter()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extractor_stream() {
+        let executor_config = Arc::new(ExecutorConfig {
+            stream_flush_size: 2,
+            ..Default::default()
+        });
+        let executor = ExtractorExecutor::new(
+            executor_config,
+            vec![ExtractorRunner::new(Arc::new(LinesExtractor))],
+            "localhost:0".to_string(),
+            Arc::new(TaskStore::new()),
+        )
+        .await
+        .unwrap();
+        let extractor_runner = executor.hosted_extractor(None).unwrap().runner.clone();
+        let content = |text: &str| internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: text.as_bytes().to_vec(),
+            features: vec![],
+            labels: HashMap::new(),
+        };
+
+        let mut flushed = Vec::new();
+        let logs = TaskLogs::new(1024);
+        let remaining = executor
+            .run_extractor_stream(
+                extractor_runner.clone(),
+                content("a\nb\nc\nd\ne"),
+                json!({}),
+                logs.clone(),
+                |content_list| {
+                    flushed.push(content_list.len());
+                    async { Ok(()) }
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(vec![2, 2], flushed);
+        assert_eq!(b"e".to_vec(), remaining[0].bytes);
+        assert_eq!("5 lines\n", logs.contents());
+
+        // errors of the extractor and of the flush fail the extraction
+        let result = executor
+            .run_extractor_stream(
+                extractor_runner.clone(),
+                content("a\nerror"),
+                json!({}),
+                TaskLogs::default(),
+                |_| async { Ok(()) },
+            )
+            .await;
+        assert!(result.is_err());
+        let result = executor
+            .run_extractor_stream(
+                extractor_runner,
+                content("a\nb"),
+                json!({}),
+                TaskLogs::default(),
+                |_| async { Err(anyhow::anyhow!("unable to write")) },
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multiple_extractors() {
+        let executor = ExtractorExecutor::new(
+            Arc::new(ExecutorConfig::default()),
+            vec![
+                ExtractorRunner::new(Arc::new(EchoExtractor("a"))),
+                ExtractorRunner::new(Arc::new(EchoExtractor("b"))),
+            ],
+            "localhost:0".to_string(),
+            Arc::new(TaskStore::new()),
+        )
+        .await
+        .unwrap();
+        let names: Vec<String> = executor
+            .extractor_descriptions()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(vec!["a".to_string(), "b".to_string()], names);
+
+        let content = internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: b"hello".to_vec(),
+            features: vec![],
+            labels: Default::default(),
+        };
+        let extracted = executor
+            .extract(Some("b"), content.clone(), None)
+            .await
+            .unwrap();
+        assert_eq!(content.bytes, extracted[0].bytes);
+        assert!(executor
+            .extract(Some("c"), content.clone(), None)
+            .await
+            .is_err());
+        // the extractor has to be named if there are several
+        assert!(executor.extract(None, content, None).await.is_err());
+
+        // tasks of extractors the executor doesn't host fail
+        let task = internal_api::Task {
+            extractor: "c".to_string(),
+            ..test_task("1", json!({}), 0)
+        };
+        executor.task_store.add(vec![task.clone()]);
+        let results = executor.execute_batch(vec![task], &test_uploader()).await;
+        assert_eq!(internal_api::TaskOutcome::Failed, results[0].outcome);
+
+        let duplicate = ExtractorExecutor::new(
+            Arc::new(ExecutorConfig::default()),
+            vec![
+                ExtractorRunner::new(Arc::new(EchoExtractor("a"))),
+                ExtractorRunner::new(Arc::new(EchoExtractor("a"))),
+            ],
+            "localhost:0".to_string(),
+            Arc::new(TaskStore::new()),
+        )
+        .await;
+        assert!(duplicate.is_err());
+    }
+
+    #[derive(Debug)]
+    struct FeatureExtractor;
+
+    impl Extractor for FeatureExtractor {
+        fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+            Ok(ExtractorSchema {
+                name: "features".to_string(),
+                embedding_schemas: HashMap::from([(
+                    "embedding".to_string(),
+                    EmbeddingSchema {
+                        distance: "cosine".to_string(),
+                        dim: 2,
+                    },
+                )]),
+                metadata_schemas: HashMap::from([(
+                    "metadata".to_string(),
+                    json!({"type": "object", "required": ["title"]}),
+                )]),
+                ..Default::default()
+            })
+        }
+
+        fn extract(
+            &self,
+            _content: Vec<internal_api::Content>,
+            _input_params: serde_json::Value,
+        ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+            Ok(vec![])
+        }
+    }
+
+    fn content_with_feature(
+        feature_type: internal_api::FeatureType,
+        name: &str,
+        data: serde_json::Value,
+    ) -> Vec<internal_api::Content> {
+        vec![internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: vec![],
+            features: vec![internal_api::Feature {
+                feature_type,
+                name: name.to_string(),
+                data,
+            }],
+            labels: HashMap::new(),
+        }]
+    }
+
+    #[test]
+    fn test_validate_features() {
+        use internal_api::FeatureType::{Embedding, Metadata};
+
+        let extractor =
+            HostedExtractor::new(ExtractorRunner::new(Arc::new(FeatureExtractor))).unwrap();
+        let valid = [
+            content_with_feature(
+                Embedding,
+                "embedding",
+                json!({"values": [0.1, 0.2], "distance": "cosine"}),
+            ),
+            content_with_feature(Metadata, "metadata", json!({"title": "a"})),
+            vec![],
+        ];
+        for content in valid {
+            assert!(extractor.validate_features(&content).is_ok());
+        }
+        let invalid = [
+            // wrong dimensions
+            content_with_feature(
+                Embedding,
+                "embedding",
+                json!({"values": [0.1], "distance": "cosine"}),
+            ),
+            // metadata not matching the schema
+            content_with_feature(Metadata, "metadata", json!({"name": "a"})),
+            // undeclared output
+            content_with_feature(Metadata, "other", json!({"title": "a"})),
+            // output of another type
+            content_with_feature(Metadata, "embedding", json!({"title": "a"})),
+        ];
+        for content in invalid {
+            assert!(extractor.validate_features(&content).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extractor_timeout() {
+        let executor_config = Arc::new(ExecutorConfig {
+            task_timeout_secs: 1,
+            ..Default::default()
+        });
+        let executor = ExtractorExecutor::new(
+            executor_config,
+            vec![ExtractorRunner::new(Arc::new(SlowExtractor))],
+            "localhost:0".to_string(),
+            Arc::new(TaskStore::new()),
+        )
+        .await
+        .unwrap();
+        let extractor_runner = executor.hosted_extractor(None).unwrap().runner.clone();
+        let err = executor
+            .run_extractor(extractor_runner, vec![], json!({}), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    fn test_task(id: &str, input_params: serde_json::Value, priority: u32) -> internal_api::Task {
+        internal_api::Task {
+            id: id.to_string(),
+            input_params,
+            priority,
+            ..internal_api::Task::spawn_instance_for_store_test()
+        }
+    }
+
+    #[test]
+    fn test_batch_tasks() {
+        let tasks = vec![
+            test_task("1", json!({"a": 1}), 0),
+            test_task("2", json!({"a": 2}), 0),
+            test_task("3", json!({"a": 1}), 0),
+            test_task("4", json!({"a": 1}), 5),
+            test_task("5", json!({"a": 2}), 0),
+            internal_api::Task {
+                extractor: "other".to_string(),
+                ..test_task("6", json!({"a": 1}), 0)
+            },
+        ];
+        let batches = batch_tasks(tasks, 2)
+            .into_iter()
+            .map(|batch| batch.into_iter().map(|t| t.id).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                vec!["4".to_string(), "1".to_string()],
+                vec!["3".to_string()],
+                vec!["2".to_string(), "5".to_string()],
+                vec!["6".to_string()],
+            ],
+            batches
+        );
+    }
+}
@@ -1,11 +1,10 @@
 use std::{
-    collections::HashMap,
     net::SocketAddr,
     sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use axum::{
     extract::State,
     routing::{get, post},
@@ -15,19 +14,34 @@ use axum::{
 use axum_otel_metrics::HttpMetricsLayerBuilder;
 use axum_tracing_opentelemetry::middleware::OtelAxumLayer;
 use indexify_internal_api as internal_api;
-use indexify_proto::indexify_coordinator::HeartbeatRequest;
+use indexify_proto::indexify_coordinator::{
+    DrainExecutorRequest,
+    HeartbeatRequest,
+    RemoveExecutorRequest,
+};
 use tokio::{
     signal,
-    sync::{watch, watch::Receiver},
-    time::interval,
+    sync::{watch, watch::Receiver, Notify, OnceCell},
+    time::{interval, Instant},
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::{
-    api::{IndexifyAPIError, WriteExtractedContent},
+    api::IndexifyAPIError,
     coordinator_client::CoordinatorClient,
     executor::{heartbeat, ExtractorExecutor},
-    extractor::{extractor_runner, py_extractors, python_path},
+    extractor::{
+        builtin_extractor,
+        extractor_runner,
+        grpc_extractor::GrpcExtractor,
+        load_extractor,
+        py_worker_pool::PythonWorkerPool,
+        python_path,
+        wasm_extractor::is_wasm_extractor_path,
+        ExtractorTS,
+    },
+    ingestion_client::IngestionClient,
+    result_uploader::ResultUploader,
     server_config::ExecutorConfig,
     task_store::TaskStore,
 };
@@ -41,6 +55,87 @@ pub struct ExecutorServer {
 pub struct ApiEndpointState {
     executor: Arc<ExtractorExecutor>,
     coordinator_client: Arc<CoordinatorClient>,
+    drain: Arc<Drain>,
+}
+
+/// Hands the work of the executor over before it stops. The executor stops
+/// accepting tasks, the tasks it didn't start go back to the coordinator and
+/// the running ones are given the drain timeout to finish before the
+/// executor deregisters, the coordinator reassigns the ones still running.
+#[derive(Debug)]
+struct Drain {
+    executor_id: String,
+    task_store: Arc<TaskStore>,
+    coordinator_client: Arc<CoordinatorClient>,
+    timeout: Duration,
+    requested: Notify,
+    response: OnceCell<internal_api::DrainResponse>,
+}
+
+impl Drain {
+    /// Drains the executor, calls made while it's draining wait for the
+    /// same drain to finish
+    async fn run(&self) -> internal_api::DrainResponse {
+        self.response.get_or_init(|| self.drain()).await.clone()
+    }
+
+    async fn drain(&self) -> internal_api::DrainResponse {
+        let unstarted_task_ids = self.task_store.drain();
+        let returned_tasks = unstarted_task_ids.len();
+        info!(
+            "draining executor {}, returning {} unstarted tasks",
+            self.executor_id, returned_tasks
+        );
+        if let Err(err) = self.drain_executor(unstarted_task_ids).await {
+            // the tasks are reassigned when the executor is removed
+            error!("unable to drain executor: {}", err);
+        }
+        let deadline = Instant::now() + self.timeout;
+        while !self.task_store.is_idle() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        let unfinished_tasks = self.task_store.running();
+        if unfinished_tasks > 0 {
+            warn!(
+                "{} tasks still running after {:?}, handing them to other executors",
+                unfinished_tasks, self.timeout
+            );
+        }
+        if let Err(err) = self.remove_executor().await {
+            error!("unable to remove executor: {}", err);
+        }
+        internal_api::DrainResponse {
+            returned_tasks,
+            unfinished_tasks,
+        }
+    }
+
+    async fn drain_executor(&self, unstarted_task_ids: Vec<String>) -> Result<()> {
+        let req = DrainExecutorRequest {
+            executor_id: self.executor_id.clone(),
+            unstarted_task_ids,
+        };
+        self.coordinator_client
+            .get()
+            .await?
+            .drain_executor(req)
+            .await
+            .map_err(|e| anyhow!("{}", e.message()))?;
+        Ok(())
+    }
+
+    async fn remove_executor(&self) -> Result<()> {
+        let req = RemoveExecutorRequest {
+            executor_id: self.executor_id.clone(),
+        };
+        self.coordinator_client
+            .get()
+            .await?
+            .remove_executor(req)
+            .await
+            .map_err(|e| anyhow!("{}", e.message()))?;
+        Ok(())
+    }
 }
 
 impl ExecutorServer {
@@ -54,30 +149,72 @@ impl ExecutorServer {
         })
     }
 
+    /// Loads every extractor the executor is configured to host
+    async fn load_extractors(&self) -> Result<Vec<ExtractorTS>> {
+        let config = &self.executor_config;
+        let mut extractors: Vec<ExtractorTS> = Vec::new();
+        if let Some(addr) = &config.extractor_addr {
+            extractors.push(Arc::new(GrpcExtractor::connect(addr).await?));
+        }
+        for name in &config.builtin_extractors {
+            extractors.push(builtin_extractor(name)?);
+        }
+        for path in config.all_extractor_paths() {
+            let extractor: ExtractorTS = match &config.python_workers {
+                Some(workers) if !is_wasm_extractor_path(&path) => {
+                    python_path::set_python_path(&path)?;
+                    Arc::new(PythonWorkerPool::new_from_extractor_path(
+                        &path,
+                        workers.clone(),
+                    )?)
+                }
+                _ => load_extractor(&path, &config.wasm)?,
+            };
+            extractors.push(extractor);
+        }
+        if extractors.is_empty() {
+            return Err(anyhow!("no extractor configured"));
+        }
+        Ok(extractors)
+    }
+
     pub async fn run(&self) -> Result<(), anyhow::Error> {
         let addr: SocketAddr = self.executor_config.listen_addr_sock()?;
         let listener = tokio::net::TcpListener::bind(addr).await?;
         let listen_addr = listener.local_addr()?.to_string();
         let listen_port = listener.local_addr()?.port();
         let advertise_addr = format!("{}:{}", self.executor_config.advertise_if, listen_port);
-        python_path::set_python_path(&self.executor_config.extractor_path)?;
-        let extractor = py_extractors::PythonExtractor::new_from_extractor_path(
-            &self.executor_config.extractor_path,
-        )?;
-        let extractor_runner = extractor_runner::ExtractorRunner::new(Arc::new(extractor));
-        let task_store = Arc::new(TaskStore::new());
+        let extractor_runners = self
+            .load_extractors()
+            .await?
+            .into_iter()
+            .map(extractor_runner::ExtractorRunner::new)
+            .collect();
+        let task_store = match &self.executor_config.journal_dir {
+            Some(journal_dir) => Arc::new(TaskStore::open(journal_dir)?),
+            None => Arc::new(TaskStore::new()),
+        };
         let executor = Arc::new(
             ExtractorExecutor::new(
                 self.executor_config.clone(),
-                extractor_runner,
+                extractor_runners,
                 advertise_addr.clone(),
                 task_store.clone(),
             )
             .await?,
         );
+        let drain = Arc::new(Drain {
+            executor_id: executor.executor_id.clone(),
+            task_store: task_store.clone(),
+            coordinator_client: self.coordinator_client.clone(),
+            timeout: Duration::from_secs(self.executor_config.drain_timeout_secs),
+            requested: Notify::new(),
+            response: OnceCell::new(),
+        });
         let endpoint_state = Arc::new(ApiEndpointState {
             executor: executor.clone(),
             coordinator_client: self.coordinator_client.clone(),
+            drain: drain.clone(),
         });
         let metrics = HttpMetricsLayerBuilder::new().build();
         let app = Router::new()
@@ -88,6 +225,10 @@ impl ExecutorServer {
                 post(sync_worker).with_state(endpoint_state.clone()),
             )
             .route("/extract", post(extract).with_state(endpoint_state.clone()))
+            .route(
+                "/drain",
+                post(drain_executor).with_state(endpoint_state.clone()),
+            )
             //start OpenTelemetry trace on incoming request
             .layer(OtelAxumLayer::default())
             .layer(metrics);
@@ -101,11 +242,25 @@ impl ExecutorServer {
         let (shutdown_tx, shutdown_rx) = watch::channel::<()>(());
         let (heartbeat_tx, heartbeat_rx) = watch::channel::<HeartbeatRequest>(HeartbeatRequest {
             executor_id: executor.executor_id.clone(),
+            ..Default::default()
         });
+        let ingestion_client = self
+            .executor_config
+            .ingestion_grpc_addr
+            .as_deref()
+            .map(|addr| {
+                IngestionClient::new(addr, self.executor_config.ingestion_grpc_token.as_deref())
+            })
+            .transpose()?;
         run_extractors(
             task_store.clone(),
             executor.clone(),
-            self.executor_config.ingestion_api_addr.clone(),
+            ResultUploader::new(
+                self.executor_config.upload.clone(),
+                &self.executor_config.ingestion_api_addr,
+                ingestion_client,
+                self.coordinator_client.clone(),
+            ),
             shutdown_rx.clone(),
         );
         let coordinator_client = self.coordinator_client.clone();
@@ -122,7 +277,9 @@ impl ExecutorServer {
                         break;
                     },
                     _ = int.tick() => {
-                        if !has_registered.load(std::sync::atomic::Ordering::Relaxed) {
+                        // registering again would make a drained executor
+                        // eligible for tasks
+                        if !has_registered.load(std::sync::atomic::Ordering::Relaxed) && !task_store.is_draining() {
                             info!("registering executor with coordinator at address {}", coordinator_addr);
                             if let Err(err) = executor.register(coordinator_client.clone()).await {
                                 error!("unable to register : {}", err.to_string());
@@ -140,7 +297,7 @@ impl ExecutorServer {
                                 has_registered.store(false, std::sync::atomic::Ordering::SeqCst);
                             });
                         }
-                        if heartbeat_tx.send(HeartbeatRequest{executor_id: executor.executor_id.clone()}).is_err(){
+                        if heartbeat_tx.send(HeartbeatRequest{executor_id: executor.executor_id.clone(), ..Default::default()}).is_err(){
                             error!("heartbeat channel closed, so we will try registering again");
                             has_registered.store(false, std::sync::atomic::Ordering::SeqCst);
                             }
@@ -150,7 +307,11 @@ impl ExecutorServer {
         });
         axum::serve(listener, app.into_make_service())
             .with_graceful_shutdown(async move {
-                let _ = shutdown_signal().await;
+                tokio::select! {
+                    _ = shutdown_signal() => {},
+                    _ = drain.requested.notified() => {},
+                }
+                drain.run().await;
                 shutdown_tx.send(()).unwrap()
             })
             .await?;
@@ -171,7 +332,11 @@ async fn extract(
 ) -> Result<Json<internal_api::ExtractResponse>, IndexifyAPIError> {
     let content = endpoint_state
         .executor
-        .extract(query.content, query.input_params)
+        .extract(
+            query.extractor_name.as_deref(),
+            query.content,
+            query.input_params,
+        )
         .await;
 
     match content {
@@ -189,11 +354,10 @@ async fn extract(
 fn run_extractors(
     task_store: Arc<TaskStore>,
     executor: Arc<ExtractorExecutor>,
-    ingestion_addr: String,
+    uploader: ResultUploader,
     mut shutdown_rx: Receiver<()>,
 ) {
     let mut watch_rx = task_store.get_watcher().clone();
-    let ingestion_api = format!("http://{}/write_content", ingestion_addr);
     tokio::spawn(async move {
         loop {
             tokio::select! {
@@ -203,7 +367,7 @@ fn run_extractors(
                 },
                 _ = watch_rx.changed() => {
                     info!("pulling work from task store");
-                    if let Err(err) = executor.execute_pending_tasks().await {
+                    if let Err(err) = executor.execute_pending_tasks(&uploader).await {
                         error!("unable to execute pending tasks: {}", err.to_string());
                     }
                     let task_results = task_store.finished_tasks();
@@ -211,40 +375,14 @@ fn run_extractors(
                         continue;
                     }
 
-                    // write extracted data
                     for task_result in task_results {
-                        let task = task_store.get_task(&task_result.task_id);
-                        if task.is_none() {
+                        let Some(task) = task_store.get_task(&task_result.task_id) else {
                             error!("unable to find task: {}", task_result.task_id);
                             continue;
-                        }
-                        let task = task.unwrap();
-                        let content_by_index = split_content_list_by_index_names(task_result.extracted_content.clone(), task.output_index_table_mapping.clone());
-                        for (index_name, content_list) in content_by_index {
-                            let req = WriteExtractedContent{
-                                parent_content_id: task.content_metadata.id.clone(),
-                                task_id: task.id.clone(),
-                                repository: task.repository.clone(),
-                                content_list: content_list.clone(),
-                                index_table_name: Some(index_name.clone()),
-                                executor_id: executor.executor_id.clone(),
-                                task_outcome: task_result.outcome.clone(),
-                                extractor_binding: task.extractor_binding.clone(),
-                            };
-                            let write_result = reqwest::Client::new()
-                            .post(&ingestion_api)
-                            .json(&req)
-                            .send()
-                            .await;
-                            if let Err(err) = write_result {
-                                error!("unable to write extracted content: {}", err.to_string());
-                                continue;
-                            }
-                            if let Err(err) = write_result {
-                                error!("unable to write extracted content: {}", err.to_string());
-                                continue;
-                            }
-                            task_store.clear_completed_task(&task.id);
+                        };
+                        match uploader.upload(&executor.executor_id, &task, task_result).await {
+                            Ok(()) => task_store.clear_completed_task(&task.id),
+                            Err(err) => error!("unable to report result of task {}, keeping it for later: {}", task.id, err),
                         }
                     }
                 }
@@ -253,6 +391,16 @@ fn run_extractors(
     });
 }
 
+/// Drains the executor and stops it, responding once it's drained
+#[tracing::instrument]
+#[axum::debug_handler]
+async fn drain_executor(
+    endpoint_state: State<Arc<ApiEndpointState>>,
+) -> Json<internal_api::DrainResponse> {
+    endpoint_state.drain.requested.notify_one();
+    Json(endpoint_state.drain.run().await)
+}
+
 #[axum::debug_handler]
 async fn sync_worker(endpoint_state: State<Arc<ApiEndpointState>>) -> Result<(), IndexifyAPIError> {
     endpoint_state
@@ -291,27 +439,3 @@ async fn shutdown_signal() {
     }
     info!("signal received, shutting down server gracefully");
 }
-
-fn split_content_list_by_index_names(
-    content_list: Vec<internal_api::Content>,
-    index_mapping: HashMap<String, String>,
-) -> HashMap<String, Vec<internal_api::Content>> {
-    let mut content_map: HashMap<String, Vec<internal_api::Content>> = HashMap::new();
-    for content in &content_list {
-        if content.features.is_empty() {
-            content_map
-                .entry("".to_string())
-                .or_default()
-                .push(content.clone());
-            continue;
-        }
-        for feature in &content.features {
-            let index_name = index_mapping.get(&feature.name).unwrap();
-            content_map
-                .entry(index_name.clone())
-                .or_default()
-                .push(content.clone());
-        }
-    }
-    content_map
-}
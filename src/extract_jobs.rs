@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use tokio::sync::watch;
+use tracing::error;
+
+use crate::{
+    api::{Content, ExtractJob, ExtractJobStatus, ExtractRequest},
+    extractor_router::ExtractorRouter,
+};
+
+#[derive(Clone, Debug)]
+enum ExtractJobState {
+    Pending,
+    Completed {
+        content: Vec<Content>,
+        finished_at: Instant,
+    },
+    Failed {
+        error: String,
+        finished_at: Instant,
+    },
+}
+
+impl ExtractJobState {
+    fn finished_at(&self) -> Option<Instant> {
+        match self {
+            Self::Pending => None,
+            Self::Completed { finished_at, .. } | Self::Failed { finished_at, .. } => {
+                Some(*finished_at)
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished_at().is_some()
+    }
+
+    fn to_job(&self, id: &str) -> ExtractJob {
+        let (status, content, error) = match self {
+            Self::Pending => (ExtractJobStatus::Pending, vec![], None),
+            Self::Completed { content, .. } => (ExtractJobStatus::Completed, content.clone(), None),
+            Self::Failed { error, .. } => (ExtractJobStatus::Failed, vec![], Some(error.clone())),
+        };
+        ExtractJob {
+            id: id.to_string(),
+            status,
+            content,
+            error,
+        }
+    }
+}
+
+/// Extractions running in the background for the asynchronous extract API,
+/// their results are kept for `ttl` after they finish
+pub struct ExtractJobs {
+    router: Arc<ExtractorRouter>,
+    jobs: Mutex<HashMap<String, watch::Receiver<ExtractJobState>>>,
+    ttl: Duration,
+}
+
+impl ExtractJobs {
+    pub fn new(router: Arc<ExtractorRouter>, ttl: Duration) -> Self {
+        Self {
+            router,
+            jobs: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Starts the extraction and returns the id of its job
+    pub fn submit(&self, request: ExtractRequest) -> String {
+        let id = nanoid::nanoid!();
+        let (tx, rx) = watch::channel(ExtractJobState::Pending);
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            self.purge(&mut jobs);
+            jobs.insert(id.clone(), rx);
+        }
+        let router = self.router.clone();
+        let job_id = id.clone();
+        let extraction = tokio::spawn(async move {
+            router
+                .extract_content(&request.name, request.content, request.input_params)
+                .await
+        });
+        // awaited separately so that a panicking extraction fails its job
+        tokio::spawn(async move {
+            let result = match extraction.await {
+                Ok(result) => result,
+                Err(e) => Err(anyhow!("extraction panicked: {}", e)),
+            };
+            let state = match result {
+                Ok(content) => ExtractJobState::Completed {
+                    content,
+                    finished_at: Instant::now(),
+                },
+                Err(e) => {
+                    error!("extract job {} failed: {}", job_id, e);
+                    ExtractJobState::Failed {
+                        error: e.to_string(),
+                        finished_at: Instant::now(),
+                    }
+                }
+            };
+            let _ = tx.send(state);
+        });
+        id
+    }
+
+    /// Returns the job, waiting up to `wait` for it to finish. Returns None
+    /// for unknown and expired jobs.
+    pub async fn get(&self, id: &str, wait: Duration) -> Option<ExtractJob> {
+        let mut state = {
+            let mut jobs = self.jobs.lock().unwrap();
+            self.purge(&mut jobs);
+            jobs.get(id)?.clone()
+        };
+        if !wait.is_zero() {
+            let _ = tokio::time::timeout(wait, state.wait_for(ExtractJobState::is_finished)).await;
+        }
+        let job = state.borrow().to_job(id);
+        Some(job)
+    }
+
+    /// Removes expired jobs and pending jobs which can't finish anymore
+    /// because their extraction is gone
+    fn purge(&self, jobs: &mut HashMap<String, watch::Receiver<ExtractJobState>>) {
+        jobs.retain(|_, state| match state.borrow().finished_at() {
+            Some(finished_at) => finished_at.elapsed() < self.ttl,
+            None => state.has_changed().is_ok(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use tokio::sync::watch;
+
+    use super::{ExtractJobState, ExtractJobs};
+    use crate::{
+        api::ExtractJobStatus,
+        coordinator_client::CoordinatorClient,
+        extractor_router::ExtractorRouter,
+    };
+
+    fn extract_jobs(ttl: Duration) -> ExtractJobs {
+        let coordinator_client = CoordinatorClient::new("localhost:0");
+        ExtractJobs {
+            router: ExtractorRouter::new(coordinator_client.into())
+                .unwrap()
+                .into(),
+            jobs: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_job() {
+        let jobs = extract_jobs(Duration::from_secs(60));
+        let (tx, rx) = watch::channel(ExtractJobState::Pending);
+        jobs.jobs.lock().unwrap().insert("job".to_string(), rx);
+
+        let job = jobs.get("job", Duration::ZERO).await.unwrap();
+        assert_eq!(ExtractJobStatus::Pending, job.status);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let _ = tx.send(ExtractJobState::Failed {
+                error: "no extractor found".to_string(),
+                finished_at: Instant::now(),
+            });
+        });
+        let job = jobs.get("job", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(ExtractJobStatus::Failed, job.status);
+        assert_eq!(Some("no extractor found".to_string()), job.error);
+
+        assert!(jobs.get("unknown", Duration::ZERO).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expired_jobs() {
+        let jobs = extract_jobs(Duration::from_secs(60));
+        let (_tx, rx) = watch::channel(ExtractJobState::Completed {
+            content: vec![],
+            finished_at: Instant::now() - Duration::from_secs(120),
+        });
+        jobs.jobs.lock().unwrap().insert("expired".to_string(), rx);
+        let (_tx, rx) = watch::channel(ExtractJobState::Pending);
+        jobs.jobs.lock().unwrap().insert("pending".to_string(), rx);
+
+        let (tx, rx) = watch::channel(ExtractJobState::Pending);
+        jobs.jobs
+            .lock()
+            .unwrap()
+            .insert("abandoned".to_string(), rx);
+        drop(tx);
+
+        assert!(jobs.get("expired", Duration::ZERO).await.is_none());
+        assert!(jobs.get("pending", Duration::ZERO).await.is_some());
+        // pending jobs without an extraction never finish
+        assert!(jobs.get("abandoned", Duration::ZERO).await.is_none());
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, Result};
+use flate2::read::GzDecoder;
+use indexify_internal_api as internal_api;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+use super::{builtin_params, Extractor, ExtractorSchema};
+
+pub const NAME: &str = "archive_expander";
+
+#[derive(Debug, Deserialize)]
+struct ExpansionParams {
+    max_entries: usize,
+    max_total_bytes: u64,
+    max_depth: usize,
+}
+
+#[derive(Debug, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Detects the format of an archive from its first bytes
+fn archive_format(bytes: &[u8]) -> Option<ArchiveFormat> {
+    if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        return Some(ArchiveFormat::Zip);
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut header = Vec::new();
+        GzDecoder::new(bytes)
+            .take(512)
+            .read_to_end(&mut header)
+            .ok()?;
+        return is_tar(&header).then_some(ArchiveFormat::TarGz);
+    }
+    is_tar(bytes).then_some(ArchiveFormat::Tar)
+}
+
+fn is_tar(bytes: &[u8]) -> bool {
+    bytes.get(257..262) == Some(b"ustar")
+}
+
+/// Path of an archive entry relative to the root of the archive, `None` for
+/// absolute paths and paths escaping the root
+fn relative_path(path: &Path) -> Option<String> {
+    let mut parts = vec![];
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Files of an archive and of the archives nested in it, within the limits
+/// of the expansion
+struct Expansion<'a> {
+    params: &'a ExpansionParams,
+    files: Vec<internal_api::Content>,
+    total_bytes: u64,
+}
+
+impl<'a> Expansion<'a> {
+    fn new(params: &'a ExpansionParams) -> Self {
+        Self {
+            params,
+            files: vec![],
+            total_bytes: 0,
+        }
+    }
+
+    fn expand(&mut self, bytes: &[u8], prefix: &str, depth: usize) -> Result<()> {
+        match archive_format(bytes) {
+            Some(ArchiveFormat::Zip) => self.expand_zip(bytes, prefix, depth),
+            Some(ArchiveFormat::Tar) => self.expand_tar(tar::Archive::new(bytes), prefix, depth),
+            Some(ArchiveFormat::TarGz) => {
+                self.expand_tar(tar::Archive::new(GzDecoder::new(bytes)), prefix, depth)
+            }
+            None => Err(anyhow!("content is not a zip or tar archive")),
+        }
+    }
+
+    fn expand_zip(&mut self, bytes: &[u8], prefix: &str, depth: usize) -> Result<()> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if !file.is_file() {
+                continue;
+            }
+            let Some(path) = file.enclosed_name().and_then(relative_path) else {
+                warn!("skipping archive entry with unsafe path: {}", file.name());
+                continue;
+            };
+            self.add_file(&mut file, format!("{}{}", prefix, path), depth)?;
+        }
+        Ok(())
+    }
+
+    fn expand_tar<R: Read>(
+        &mut self,
+        mut archive: tar::Archive<R>,
+        prefix: &str,
+        depth: usize,
+    ) -> Result<()> {
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            // directories and links don't have content of their own
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?;
+            let Some(path) = relative_path(&path) else {
+                warn!(
+                    "skipping archive entry with unsafe path: {}",
+                    path.display()
+                );
+                continue;
+            };
+            self.add_file(&mut entry, format!("{}{}", prefix, path), depth)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a file of the archive, archives nested in it are expanded in
+    /// its place until the maximum depth
+    fn add_file(&mut self, reader: &mut impl Read, path: String, depth: usize) -> Result<()> {
+        if self.files.len() >= self.params.max_entries {
+            return Err(anyhow!(
+                "archive has more than {} entries",
+                self.params.max_entries
+            ));
+        }
+        // the sizes in the headers can't be trusted, the read itself is
+        // bounded
+        let remaining_bytes = self.params.max_total_bytes - self.total_bytes;
+        let mut bytes = Vec::new();
+        reader.take(remaining_bytes + 1).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > remaining_bytes {
+            return Err(anyhow!(
+                "archive expands to more than {} bytes",
+                self.params.max_total_bytes
+            ));
+        }
+        if depth < self.params.max_depth && archive_format(&bytes).is_some() {
+            return self.expand(&bytes, &format!("{}/", path), depth + 1);
+        }
+        self.total_bytes += bytes.len() as u64;
+        self.files.push(internal_api::Content {
+            mime: mime_guess::from_path(&path)
+                .first_or_octet_stream()
+                .to_string(),
+            bytes,
+            features: vec![],
+            labels: HashMap::from([(internal_api::FILE_NAME_LABEL.to_string(), path)]),
+        });
+        Ok(())
+    }
+}
+
+/// Built-in extractor unpacking zip, tar and gzipped tar archives, one
+/// output content per file named after its path in the archive
+#[derive(Debug, Default)]
+pub struct ArchiveExpander;
+
+impl Extractor for ArchiveExpander {
+    fn schemas(&self) -> Result<ExtractorSchema> {
+        Ok(ExtractorSchema {
+            name: NAME.to_string(),
+            version: crate::VERSION.to_string(),
+            description: "unpacks zip and tar archives into their files".to_string(),
+            input_params: json!({
+                "type": "object",
+                "properties": {
+                    "max_entries": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 10000,
+                        "description": "maximum number of files of an archive",
+                    },
+                    "max_total_bytes": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 256 * 1024 * 1024,
+                        "description": "maximum total size of the files of an archive",
+                    },
+                    "max_depth": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "default": 3,
+                        "description": "levels of nested archives expanded, deeper archives \
+                                        are kept as files",
+                    },
+                },
+            }),
+            input_mimes: vec![
+                "application/zip".to_string(),
+                "application/x-tar".to_string(),
+                "application/gzip".to_string(),
+                "application/x-gzip".to_string(),
+                "application/x-compressed".to_string(),
+            ],
+            ..Default::default()
+        })
+    }
+
+    fn extract(
+        &self,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<internal_api::Content>>> {
+        let params: ExpansionParams = builtin_params(self, input_params)?;
+        content
+            .into_iter()
+            .map(|content| {
+                let mut expansion = Expansion::new(&params);
+                expansion.expand(&content.bytes, "", 1)?;
+                Ok(expansion.files)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, data) in files {
+            // the name is set as is, the builder refuses unsafe paths
+            let mut header = tar::Header::new_ustar();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&builder.into_inner().unwrap()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn archive_content(bytes: Vec<u8>) -> internal_api::Content {
+        internal_api::Content {
+            mime: "application/zip".to_string(),
+            bytes,
+            features: vec![],
+            labels: HashMap::new(),
+        }
+    }
+
+    fn file_names(files: &[internal_api::Content]) -> Vec<&str> {
+        files
+            .iter()
+            .map(|f| f.labels[internal_api::FILE_NAME_LABEL].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_expander() {
+        let expander = ArchiveExpander;
+        assert_eq!(NAME, expander.schemas().unwrap().name);
+
+        let inner = tar_gz_archive(&[("notes.md", b"# notes"), ("../escape.txt", b"no")]);
+        let outer = zip_archive(&[
+            ("docs/report.pdf", b"%PDF"),
+            ("docs/inner.tar.gz", &inner),
+            ("readme.txt", b"hello"),
+        ]);
+        let outputs = expander
+            .extract(
+                vec![archive_content(outer.clone())],
+                serde_json::Value::Null,
+            )
+            .unwrap();
+        let files = &outputs[0];
+        assert_eq!(
+            vec![
+                "docs/report.pdf",
+                "docs/inner.tar.gz/notes.md",
+                "readme.txt"
+            ],
+            file_names(files)
+        );
+        assert_eq!("application/pdf", files[0].mime);
+        assert_eq!("text/markdown", files[1].mime);
+        assert_eq!(b"hello".to_vec(), files[2].bytes);
+
+        // nested archives deeper than the maximum depth are kept as files
+        let outputs = expander
+            .extract(
+                vec![archive_content(outer.clone())],
+                json!({"max_depth": 1}),
+            )
+            .unwrap();
+        assert_eq!(
+            vec!["docs/report.pdf", "docs/inner.tar.gz", "readme.txt"],
+            file_names(&outputs[0])
+        );
+
+        let limits = [json!({"max_entries": 2}), json!({"max_total_bytes": 8})];
+        for params in limits {
+            assert!(expander
+                .extract(vec![archive_content(outer.clone())], params)
+                .is_err());
+        }
+        assert!(expander
+            .extract(vec![archive_content(b"not an archive".to_vec())], json!({}))
+            .is_err());
+    }
+
+    #[test]
+    fn test_zip_bomb() {
+        // a small archive expanding to far more than the limit
+        let bomb = zip_archive(&[("zeros", &vec![0; 1024 * 1024])]);
+        assert!(bomb.len() < 16 * 1024);
+        let err = ArchiveExpander
+            .extract(
+                vec![archive_content(bomb)],
+                json!({"max_total_bytes": 1024}),
+            )
+            .unwrap_err();
+        assert_eq!("archive expands to more than 1024 bytes", err.to_string());
+    }
+}
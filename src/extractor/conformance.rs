@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Result};
+use indexify_proto::indexify_extractor::{
+    self as proto,
+    extractor_service_client::ExtractorServiceClient,
+    DescribeRequest,
+    DescribeResponse,
+    ExtractRequest,
+};
+use tonic::transport::Channel;
+
+use super::grpc_extractor::content_from_proto;
+
+/// Outcome of a single check of the conformance kit
+#[derive(Debug)]
+pub struct ConformanceCheck {
+    pub name: &'static str,
+    pub result: Result<()>,
+}
+
+impl ConformanceCheck {
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Checks that the extractor served at `addr` implements the
+/// `ExtractorService` protocol the way the executor expects. `sample` is
+/// content the extractor accepts; every check is run even if an earlier one
+/// fails.
+pub async fn check_conformance(
+    addr: &str,
+    sample: proto::Content,
+) -> Result<Vec<ConformanceCheck>> {
+    let mut client = ExtractorServiceClient::connect(format!("http://{}", addr))
+        .await
+        .map_err(|e| anyhow!("unable to connect to extractor at {}: {}", addr, e))?;
+    let mut checks = Vec::new();
+
+    let description = client.describe(DescribeRequest {}).await;
+    let description = description.map(|r| r.into_inner()).map_err(|e| anyhow!(e));
+    let (result, description) = match description {
+        Ok(description) => (check_description(&description), Some(description)),
+        Err(err) => (Err(err), None),
+    };
+    checks.push(ConformanceCheck {
+        name: "describe",
+        result,
+    });
+
+    checks.push(ConformanceCheck {
+        name: "extract single content",
+        result: check_extract(&mut client, vec![sample.clone()], description.as_ref()).await,
+    });
+    checks.push(ConformanceCheck {
+        name: "extract batch",
+        result: check_extract(&mut client, vec![sample.clone(); 3], description.as_ref()).await,
+    });
+    checks.push(ConformanceCheck {
+        name: "extract empty batch",
+        result: check_extract(&mut client, vec![], description.as_ref()).await,
+    });
+    checks.push(ConformanceCheck {
+        name: "reject invalid input params",
+        result: check_invalid_input_params(&mut client, sample.clone()).await,
+    });
+    checks.push(ConformanceCheck {
+        name: "extract stream",
+        result: check_extract_stream(&mut client, sample).await,
+    });
+    Ok(checks)
+}
+
+fn check_description(description: &DescribeResponse) -> Result<()> {
+    if description.name.is_empty() {
+        return Err(anyhow!("name is empty"));
+    }
+    if description.input_mime_types.is_empty() {
+        return Err(anyhow!("no input mime types"));
+    }
+    if !description.input_params.is_empty() {
+        serde_json::from_str::<serde_json::Value>(&description.input_params)
+            .map_err(|e| anyhow!("input params schema is not valid JSON: {}", e))?;
+    }
+    for (name, schema) in &description.metadata_schemas {
+        serde_json::from_str::<serde_json::Value>(schema).map_err(|e| {
+            anyhow!(
+                "schema of metadata output {} is not valid JSON: {}",
+                name,
+                e
+            )
+        })?;
+    }
+    for (name, schema) in &description.embedding_schemas {
+        if schema.dim == 0 {
+            return Err(anyhow!("embedding output {} has no dimensions", name));
+        }
+    }
+    Ok(())
+}
+
+fn check_outputs(
+    inputs: usize,
+    outputs: Vec<proto::ContentList>,
+    description: Option<&DescribeResponse>,
+) -> Result<()> {
+    if outputs.len() != inputs {
+        return Err(anyhow!(
+            "expected {} output lists, got {}",
+            inputs,
+            outputs.len()
+        ));
+    }
+    let embedding_schemas = description
+        .map(|d| d.embedding_schemas.clone())
+        .unwrap_or_default();
+    for content in outputs.into_iter().flat_map(|list| list.content) {
+        let content = content_from_proto(content)?;
+        for feature in content.features {
+            let Some(schema) = embedding_schemas.get(&feature.name) else {
+                continue;
+            };
+            let dim = feature
+                .data
+                .get("values")
+                .and_then(|v| v.as_array())
+                .map(|v| v.len())
+                .ok_or(anyhow!("embedding {} has no values", feature.name))?;
+            if dim as u64 != schema.dim {
+                return Err(anyhow!(
+                    "embedding {} has {} dimensions, described as {}",
+                    feature.name,
+                    dim,
+                    schema.dim
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_extract(
+    client: &mut ExtractorServiceClient<Channel>,
+    content: Vec<proto::Content>,
+    description: Option<&DescribeResponse>,
+) -> Result<()> {
+    let inputs = content.len();
+    let response = client
+        .extract(ExtractRequest {
+            content,
+            input_params: "{}".to_string(),
+        })
+        .await?
+        .into_inner();
+    check_outputs(inputs, response.outputs, description)
+}
+
+async fn check_invalid_input_params(
+    client: &mut ExtractorServiceClient<Channel>,
+    sample: proto::Content,
+) -> Result<()> {
+    let response = client
+        .extract(ExtractRequest {
+            content: vec![sample],
+            input_params: "not json".to_string(),
+        })
+        .await;
+    match response {
+        Ok(_) => Err(anyhow!("invalid input params were accepted")),
+        Err(_) => Ok(()),
+    }
+}
+
+async fn check_extract_stream(
+    client: &mut ExtractorServiceClient<Channel>,
+    sample: proto::Content,
+) -> Result<()> {
+    let batch_sizes = [1, 2];
+    let requests = batch_sizes.map(|size| ExtractRequest {
+        content: vec![sample.clone(); size],
+        input_params: "{}".to_string(),
+    });
+    let mut responses = client
+        .extract_stream(tokio_stream::iter(requests))
+        .await?
+        .into_inner();
+    let mut outputs = Vec::new();
+    while let Some(response) = responses.message().await? {
+        outputs.push(response.outputs.len());
+    }
+    if outputs != batch_sizes {
+        return Err(anyhow!(
+            "expected output lists per response {:?}, got {:?}",
+            batch_sizes,
+            outputs
+        ));
+    }
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, str::FromStr};
 use anyhow::{anyhow, Ok, Result};
 use indexify_internal_api as internal_api;
 
-use super::ExtractorTS;
+use super::{ContentStream, ExtractorTS};
 use crate::{
     api,
     api::{ExtractorDescription, ExtractorOutputSchema, IndexDistance},
@@ -28,6 +28,18 @@ impl ExtractorRunner {
         Ok(extracted_content)
     }
 
+    pub fn streams(&self) -> bool {
+        self.extractor.streams()
+    }
+
+    pub fn extract_stream(
+        &self,
+        content: internal_api::Content,
+        input_params: serde_json::Value,
+    ) -> Result<ContentStream> {
+        self.extractor.extract_stream(content, input_params)
+    }
+
     pub fn extract_from_data(
         &self,
         data: Vec<u8>,
@@ -76,6 +88,7 @@ impl ExtractorRunner {
             .collect();
         let extractor_description = ExtractorDescription {
             name: extractor_schema.name.clone(),
+            version: extractor_schema.version.clone(),
             description: extractor_schema.description.clone(),
             input_params: extractor_schema.input_params,
             outputs,
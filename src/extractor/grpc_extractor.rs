@@ -0,0 +1,402 @@
+use std::{collections::HashMap, pin::Pin, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use indexify_internal_api as internal_api;
+use indexify_proto::indexify_extractor::{
+    self as proto,
+    extractor_service_client::ExtractorServiceClient,
+    extractor_service_server::ExtractorService,
+    DescribeRequest,
+    DescribeResponse,
+    ExtractRequest,
+    ExtractResponse,
+};
+use tokio::{runtime::Handle, sync::mpsc};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tonic::{transport::Channel, Request, Response, Status, Streaming};
+use tracing::info;
+
+use super::{EmbeddingSchema, Extractor, ExtractorSchema, ExtractorTS};
+
+pub fn content_to_proto(content: internal_api::Content) -> proto::Content {
+    proto::Content {
+        mime: content.mime,
+        data: content.bytes,
+        features: content.features.into_iter().map(feature_to_proto).collect(),
+        labels: content.labels,
+    }
+}
+
+pub fn feature_to_proto(feature: internal_api::Feature) -> proto::Feature {
+    proto::Feature {
+        feature_type: match feature.feature_type {
+            internal_api::FeatureType::Embedding => "embedding",
+            internal_api::FeatureType::Metadata => "metadata",
+            internal_api::FeatureType::Unknown => "unknown",
+        }
+        .to_string(),
+        name: feature.name,
+        value: feature.data.to_string(),
+    }
+}
+
+pub fn feature_from_proto(feature: proto::Feature) -> Result<internal_api::Feature> {
+    Ok(internal_api::Feature {
+        feature_type: internal_api::FeatureType::from_str(&feature.feature_type)
+            .map_err(|_| anyhow!("invalid feature type: {}", feature.feature_type))?,
+        data: serde_json::from_str(&feature.value)
+            .map_err(|e| anyhow!("invalid value of feature {}: {}", feature.name, e))?,
+        name: feature.name,
+    })
+}
+
+pub fn content_from_proto(content: proto::Content) -> Result<internal_api::Content> {
+    Ok(internal_api::Content {
+        mime: content.mime,
+        bytes: content.data,
+        features: content
+            .features
+            .into_iter()
+            .map(feature_from_proto)
+            .collect::<Result<_>>()?,
+        labels: content.labels,
+    })
+}
+
+fn schema_from_proto(description: DescribeResponse) -> Result<ExtractorSchema> {
+    let mut metadata_schemas = HashMap::new();
+    for (name, schema) in description.metadata_schemas {
+        let schema = serde_json::from_str(&schema)
+            .map_err(|e| anyhow!("invalid schema of metadata output {}: {}", name, e))?;
+        metadata_schemas.insert(name, schema);
+    }
+    let input_params = match description.input_params.as_str() {
+        "" => serde_json::Value::Null,
+        input_params => serde_json::from_str(input_params)
+            .map_err(|e| anyhow!("invalid input params schema: {}", e))?,
+    };
+    Ok(ExtractorSchema {
+        name: description.name,
+        version: description.version,
+        description: description.description,
+        python_dependencies: vec![],
+        system_dependencies: vec![],
+        embedding_schemas: description
+            .embedding_schemas
+            .into_iter()
+            .map(|(name, schema)| {
+                (
+                    name,
+                    EmbeddingSchema {
+                        dim: schema.dim as usize,
+                        distance: schema.distance,
+                    },
+                )
+            })
+            .collect(),
+        metadata_schemas,
+        input_params,
+        input_mimes: description.input_mime_types,
+    })
+}
+
+fn schema_to_proto(schema: ExtractorSchema) -> DescribeResponse {
+    DescribeResponse {
+        name: schema.name,
+        version: schema.version,
+        description: schema.description,
+        input_params: schema.input_params.to_string(),
+        input_mime_types: schema.input_mimes,
+        embedding_schemas: schema
+            .embedding_schemas
+            .into_iter()
+            .map(|(name, schema)| {
+                (
+                    name,
+                    proto::EmbeddingSchema {
+                        dim: schema.dim as u64,
+                        distance: schema.distance,
+                    },
+                )
+            })
+            .collect(),
+        metadata_schemas: schema
+            .metadata_schemas
+            .into_iter()
+            .map(|(name, schema)| (name, schema.to_string()))
+            .collect(),
+    }
+}
+
+/// Extractor running outside of the executor, e.g. as a sidecar, which
+/// implements the `ExtractorService` gRPC protocol.
+#[derive(Debug)]
+pub struct GrpcExtractor {
+    client: ExtractorServiceClient<Channel>,
+    extractor_schema: ExtractorSchema,
+    handle: Handle,
+}
+
+impl GrpcExtractor {
+    pub async fn connect(addr: &str) -> Result<Self> {
+        let mut client = ExtractorServiceClient::connect(format!("http://{}", addr))
+            .await
+            .map_err(|e| anyhow!("unable to connect to extractor at {}: {}", addr, e))?;
+        let description = client
+            .describe(DescribeRequest {})
+            .await
+            .map_err(|e| anyhow!("unable to describe extractor at {}: {}", addr, e))?
+            .into_inner();
+        let extractor_schema = schema_from_proto(description)?;
+        info!(
+            "connected to extractor {} at {}",
+            extractor_schema.name, addr
+        );
+        Ok(Self {
+            client,
+            extractor_schema,
+            handle: Handle::current(),
+        })
+    }
+}
+
+impl Extractor for GrpcExtractor {
+    fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+        Ok(self.extractor_schema.clone())
+    }
+
+    fn extract(
+        &self,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+        let num_inputs = content.len();
+        let request = ExtractRequest {
+            content: content.into_iter().map(content_to_proto).collect(),
+            input_params: input_params.to_string(),
+        };
+        let mut client = self.client.clone();
+        // extract is synchronous, block_in_place keeps blocking on the request
+        // safe when this is called from a runtime worker thread
+        let response = tokio::task::block_in_place(|| {
+            self.handle
+                .block_on(async move { client.extract(request).await })
+        })
+        .map_err(|e| anyhow!("error calling extract method: {}", e.message()))?
+        .into_inner();
+        if response.outputs.len() != num_inputs {
+            return Err(anyhow!(
+                "extractor returned {} outputs for {} inputs",
+                response.outputs.len(),
+                num_inputs
+            ));
+        }
+        response
+            .outputs
+            .into_iter()
+            .map(|list| list.content.into_iter().map(content_from_proto).collect())
+            .collect()
+    }
+}
+
+/// Serves an extractor of this process over the `ExtractorService` gRPC
+/// protocol, so that it can run as a sidecar of an executor.
+pub struct ExtractorGrpcService {
+    extractor: ExtractorTS,
+}
+
+impl ExtractorGrpcService {
+    pub fn new(extractor: ExtractorTS) -> Self {
+        Self { extractor }
+    }
+
+    async fn run_extract(&self, request: ExtractRequest) -> Result<ExtractResponse, Status> {
+        let input_params = match request.input_params.as_str() {
+            "" => serde_json::Value::Null,
+            input_params => serde_json::from_str(input_params)
+                .map_err(|e| Status::invalid_argument(format!("invalid input params: {}", e)))?,
+        };
+        let content = request
+            .content
+            .into_iter()
+            .map(content_from_proto)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let extractor = self.extractor.clone();
+        let outputs = tokio::task::spawn_blocking(move || extractor.extract(content, input_params))
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(ExtractResponse {
+            outputs: outputs
+                .into_iter()
+                .map(|list| proto::ContentList {
+                    content: list.into_iter().map(content_to_proto).collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+type ExtractResponseStream = Pin<Box<dyn Stream<Item = Result<ExtractResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ExtractorService for ExtractorGrpcService {
+    type ExtractStreamStream = ExtractResponseStream;
+
+    async fn describe(
+        &self,
+        _request: Request<DescribeRequest>,
+    ) -> Result<Response<DescribeResponse>, Status> {
+        let schema = self
+            .extractor
+            .schemas()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(schema_to_proto(schema)))
+    }
+
+    async fn extract(
+        &self,
+        request: Request<ExtractRequest>,
+    ) -> Result<Response<ExtractResponse>, Status> {
+        let response = self.run_extract(request.into_inner()).await?;
+        Ok(Response::new(response))
+    }
+
+    async fn extract_stream(
+        &self,
+        request: Request<Streaming<ExtractRequest>>,
+    ) -> Result<Response<Self::ExtractStreamStream>, Status> {
+        let service = Self {
+            extractor: self.extractor.clone(),
+        };
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let response = match request {
+                    Ok(request) => service.run_extract(request).await,
+                    Err(err) => Err(err),
+                };
+                if tx.send(response).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, sync::Arc};
+
+    use indexify_proto::indexify_extractor::extractor_service_server::ExtractorServiceServer;
+    use serde_json::json;
+
+    use super::*;
+    use crate::extractor::conformance::check_conformance;
+
+    #[derive(Debug)]
+    struct UppercaseExtractor;
+
+    impl Extractor for UppercaseExtractor {
+        fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+            Ok(ExtractorSchema {
+                name: "uppercase".to_string(),
+                input_params: json!({"type": "object"}),
+                input_mimes: vec![mime::TEXT_PLAIN.to_string()],
+                embedding_schemas: HashMap::from([(
+                    "embedding".to_string(),
+                    EmbeddingSchema {
+                        dim: 2,
+                        distance: "cosine".to_string(),
+                    },
+                )]),
+                ..Default::default()
+            })
+        }
+
+        fn extract(
+            &self,
+            content: Vec<internal_api::Content>,
+            _input_params: serde_json::Value,
+        ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+            Ok(content
+                .into_iter()
+                .map(|c| {
+                    vec![internal_api::Content {
+                        mime: c.mime,
+                        bytes: c.bytes.to_ascii_uppercase(),
+                        features: vec![internal_api::Feature {
+                            feature_type: internal_api::FeatureType::Embedding,
+                            name: "embedding".to_string(),
+                            data: json!({"values": [1.0, 0.0], "distance": "cosine"}),
+                        }],
+                        labels: c.labels,
+                    }]
+                })
+                .collect())
+        }
+    }
+
+    async fn serve_extractor() -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let service = ExtractorGrpcService::new(Arc::new(UppercaseExtractor));
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ExtractorServiceServer::new(service))
+                .serve_with_incoming(futures::stream::unfold(listener, |listener| async {
+                    let stream = listener.accept().await.map(|(stream, _)| stream);
+                    Some((stream, listener))
+                })),
+        );
+        addr
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_grpc_extractor() {
+        let addr = serve_extractor().await;
+        let extractor = Arc::new(GrpcExtractor::connect(&addr.to_string()).await.unwrap());
+        let schema = extractor.schemas().unwrap();
+        assert_eq!("uppercase", schema.name);
+        assert_eq!(2, schema.embedding_schemas["embedding"].dim);
+
+        let content = internal_api::Content {
+            mime: mime::TEXT_PLAIN.to_string(),
+            bytes: b"hello".to_vec(),
+            features: vec![],
+            labels: HashMap::from([("source".to_string(), "test".to_string())]),
+        };
+        let outputs = tokio::task::spawn_blocking(move || {
+            extractor.extract(vec![content.clone(), content], json!({}))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(2, outputs.len());
+        assert_eq!(b"HELLO".to_vec(), outputs[0][0].bytes);
+        assert_eq!("test", outputs[0][0].labels["source"]);
+        assert_eq!(
+            json!({"values": [1.0, 0.0], "distance": "cosine"}),
+            outputs[1][0].features[0].data
+        );
+    }
+
+    #[tokio::test]
+    async fn test_conformance() {
+        let addr = serve_extractor().await;
+        let sample = proto::Content {
+            mime: mime::TEXT_PLAIN.to_string(),
+            data: b"hello".to_vec(),
+            features: vec![],
+            labels: HashMap::new(),
+        };
+        let checks = check_conformance(&addr.to_string(), sample).await.unwrap();
+        assert_eq!(6, checks.len());
+        for check in checks {
+            assert!(check.passed(), "{}: {:?}", check.name, check.result);
+        }
+    }
+}
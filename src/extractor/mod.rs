@@ -7,16 +7,26 @@ use bollard::{
     Docker,
 };
 use pyo3::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tokio_stream::StreamExt;
 
+pub mod archive_expander;
+pub mod conformance;
 pub mod extractor_runner;
+pub mod grpc_extractor;
 pub mod py_extractors;
+pub mod py_worker_pool;
 
 use indexify_internal_api as internal_api;
 
+use crate::{api_utils::validate_input_params, server_config::WasmConfig};
+
 pub mod python_path;
 mod scaffold;
+pub mod task_deadline;
+pub mod task_logs;
+pub mod text_chunker;
+pub mod wasm_extractor;
 
 /// EmbeddingSchema describes the embedding output by an extractor
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, FromPyObject)]
@@ -29,6 +39,10 @@ pub struct EmbeddingSchema {
     pub dim: usize,
 }
 
+/// Content extracted from a single input, produced while the extraction
+/// runs
+pub type ContentStream = Box<dyn Iterator<Item = Result<internal_api::Content>> + Send>;
+
 pub trait Extractor: Debug {
     /// Returns the extractor schema
     fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error>;
@@ -39,9 +53,27 @@ pub trait Extractor: Debug {
         content: Vec<internal_api::Content>,
         input_params: serde_json::Value,
     ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error>;
+
+    /// Whether `extract_stream` returns content before the whole input is
+    /// processed
+    fn streams(&self) -> bool {
+        false
+    }
+
+    /// Extracts content from a single input. Extractors which don't stream
+    /// return all of it once the extraction is done.
+    fn extract_stream(
+        &self,
+        content: internal_api::Content,
+        input_params: serde_json::Value,
+    ) -> Result<ContentStream, anyhow::Error> {
+        let extracted_content = self.extract(vec![content], input_params)?;
+        Ok(Box::new(extracted_content.into_iter().flatten().map(Ok)))
+    }
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExtractorSchema {
     pub name: String,
     pub version: String,
@@ -152,6 +184,44 @@ pub async fn run_docker_extractor(
     Ok(vec![])
 }
 
+/// Loads the extractor at `extractor_path`, either a WebAssembly module
+/// ending in `.wasm` or a python extractor `path/to/module.py:ClassName`
+pub fn load_extractor(extractor_path: &str, wasm_config: &WasmConfig) -> Result<ExtractorTS> {
+    if wasm_extractor::is_wasm_extractor_path(extractor_path) {
+        let extractor =
+            wasm_extractor::WasmExtractor::new_from_path(extractor_path, wasm_config.clone())?;
+        return Ok(Arc::new(extractor));
+    }
+    python_path::set_python_path(extractor_path)?;
+    let extractor = py_extractors::PythonExtractor::new_from_extractor_path(extractor_path)?;
+    Ok(Arc::new(extractor))
+}
+
+/// Input params of a built-in extractor, the defaults declared by its input
+/// params schema filled in
+fn builtin_params<T: DeserializeOwned>(
+    extractor: &dyn Extractor,
+    input_params: serde_json::Value,
+) -> Result<T> {
+    let schema = extractor.schemas()?.input_params;
+    let input_params = validate_input_params(&schema, input_params)?;
+    serde_json::from_value(input_params).map_err(|e| anyhow!("invalid input params: {}", e))
+}
+
+/// Returns the extractor built into the binary with the given name
+pub fn builtin_extractor(name: &str) -> Result<ExtractorTS> {
+    match name {
+        text_chunker::NAME => Ok(Arc::new(text_chunker::TextChunker)),
+        archive_expander::NAME => Ok(Arc::new(archive_expander::ArchiveExpander)),
+        _ => Err(anyhow!(
+            "unknown builtin extractor: {}, available: {}, {}",
+            name,
+            text_chunker::NAME,
+            archive_expander::NAME
+        )),
+    }
+}
+
 pub fn create_extractor_template(extractor_path: &str, name: &str) -> Result<(), anyhow::Error> {
     std::fs::create_dir_all(extractor_path)?;
     scaffold::render_extractor_templates(extractor_path, name)
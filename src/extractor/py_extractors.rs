@@ -1,15 +1,24 @@
-use std::{collections::HashMap, path::Path, str::FromStr};
+use std::{
+    collections::HashMap,
+    os::raw::c_long,
+    path::Path,
+    str::FromStr,
+    sync::mpsc,
+    time::Instant,
+};
 
 use anyhow::{anyhow, Ok, Result};
 use indexify_internal_api as internal_api;
 use pyo3::{
+    ffi,
     prelude::*,
-    types::{PyList, PyString},
+    types::{IntoPyDict, PyIterator, PyList, PyString},
 };
 
-use super::{EmbeddingSchema, Extractor, ExtractorSchema};
+use super::{task_deadline, task_logs, ContentStream, EmbeddingSchema, Extractor, ExtractorSchema};
 
 const EXTRACT_METHOD: &str = "extract";
+const EXTRACT_STREAM_METHOD: &str = "extract_stream";
 
 #[derive(Clone)]
 #[pyclass]
@@ -117,38 +126,208 @@ impl PyContent {
     }
 }
 
+/// Splits an extractor path of the form `path/to/module.py:ClassName` into
+/// the module and class name.
+pub fn parse_extractor_path(extractor_path: &str) -> Result<(String, String)> {
+    let tokens: Vec<&str> = extractor_path.split(':').collect();
+    if tokens.len() != 2 {
+        return Err(anyhow!("invalid extractor path: {}", extractor_path));
+    }
+    let module_path = tokens[0];
+    let class_name = tokens[1].trim();
+    let module_file_name = Path::new(module_path)
+        .file_name()
+        .ok_or(anyhow!("couldn't find model file name: {:?}", module_path))?
+        .to_str()
+        .ok_or(anyhow!("couldn't find model file name: {:?}", module_path))?;
+
+    let module_name = module_file_name.trim_end_matches(".py");
+    Ok((module_name.to_string(), class_name.to_string()))
+}
+
+/// Replaces `sys.stdout` and `sys.stderr` of python extractors, what they
+/// print while running a task is captured in the logs of the task
+#[pyclass]
+struct LogWriter {
+    stderr: bool,
+}
+
+#[pymethods]
+impl LogWriter {
+    fn write(&self, text: &str) -> usize {
+        if !task_logs::write(text) {
+            if self.stderr {
+                eprint!("{}", text);
+            } else {
+                print!("{}", text);
+            }
+        }
+        text.len()
+    }
+
+    fn flush(&self) {}
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+fn install_log_writers(py: Python) -> Result<()> {
+    let sys = py.import("sys")?;
+    sys.setattr("stdout", Py::new(py, LogWriter { stderr: false })?)?;
+    sys.setattr("stderr", Py::new(py, LogWriter { stderr: true })?)?;
+    // extractors which don't configure logging log to the captured stderr
+    let logging = py.import("logging")?;
+    let root_logger = logging.call_method0("getLogger")?;
+    if !root_logger.call_method0("hasHandlers")?.is_true()? {
+        let config = [
+            ("level", logging.getattr("INFO")?),
+            (
+                "format",
+                PyString::new(py, "%(asctime)s %(levelname)s %(name)s: %(message)s"),
+            ),
+        ]
+        .into_py_dict(py);
+        logging.call_method("basicConfig", (), Some(config))?;
+    }
+    Ok(())
+}
+
+/// Runs `f`, which calls into python on the current thread, raising a
+/// `TimeoutError` in it when the deadline of the task passes. Python raises
+/// the error at its next instruction, a call blocked in native code stops
+/// once it returns to python.
+fn until_task_deadline<T>(py: Python, f: impl FnOnce() -> PyResult<T>) -> PyResult<T> {
+    let Some(deadline) = task_deadline::current() else {
+        return f();
+    };
+    let thread_id: c_long = py
+        .import("threading")?
+        .call_method0("get_ident")?
+        .extract()?;
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+    let watchdog = std::thread::spawn(move || {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if done_rx.recv_timeout(timeout) != Err(mpsc::RecvTimeoutError::Timeout) {
+            return false;
+        }
+        Python::with_gil(|_| {
+            // the call may have returned while waiting for the gil, it signals
+            // it while holding the gil
+            if done_rx.try_recv() != Err(mpsc::TryRecvError::Empty) {
+                return false;
+            }
+            unsafe { ffi::PyThreadState_SetAsyncExc(thread_id, ffi::PyExc_TimeoutError) };
+            true
+        })
+    });
+    let result = f();
+    drop(done_tx);
+    let interrupted = py.allow_threads(|| watchdog.join().unwrap_or(false));
+    if interrupted {
+        // the call may have returned before python raised the error, it
+        // mustn't be raised in the next task
+        unsafe { ffi::PyThreadState_SetAsyncExc(thread_id, std::ptr::null_mut()) };
+    }
+    result
+}
+
+fn call_error(py: Python, method: &str, e: PyErr) -> anyhow::Error {
+    let trace_back = e
+        .traceback(py)
+        .and_then(|t| t.format().ok())
+        .unwrap_or_default();
+    task_logs::write(&format!("{}{}\n", trace_back, e));
+    anyhow!(
+        "error calling {} method: err {}, trace back: {}",
+        method,
+        e.to_string(),
+        trace_back
+    )
+}
+
+/// Converts a `Content` returned by a python extractor
+fn content_from_py(py: Python, py_content: &PyObject) -> Result<internal_api::Content> {
+    let mime: String = py_content.getattr(py, "content_type")?.extract(py)?;
+    let data: Vec<u8> = py_content.getattr(py, "data")?.extract(py)?;
+    let py_features: Vec<PyObject> = py_content.getattr(py, "features")?.extract(py)?;
+    let mut features = Vec::new();
+    for py_feature in py_features {
+        let feature_type: String = py_feature.getattr(py, "feature_type")?.extract(py)?;
+        let feature_type = internal_api::FeatureType::from_str(&feature_type)?;
+        let name: String = py_feature.getattr(py, "name")?.extract(py)?;
+        let value: String = py_feature.getattr(py, "value")?.extract(py)?;
+        let value: serde_json::Value = serde_json::from_str(&value)?;
+        features.push(internal_api::Feature {
+            feature_type,
+            name,
+            data: value,
+        });
+    }
+    let py_labels: Option<PyObject> = py_content.getattr(py, "labels")?.extract(py)?;
+
+    let labels = match py_labels {
+        Some(py_labels) => {
+            let labels: HashMap<String, String> = py_labels.extract(py)?;
+            labels
+        }
+        None => HashMap::new(),
+    };
+    Ok(internal_api::Content {
+        mime,
+        bytes: data,
+        features,
+        labels,
+    })
+}
+
+/// Content yielded by the generator of a python extractor, every item is
+/// produced by resuming the generator
+struct PyContentStream {
+    iterator: PyObject,
+}
+
+impl Iterator for PyContentStream {
+    type Item = Result<internal_api::Content>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Python::with_gil(|py| {
+            until_task_deadline(py, || {
+                PyIterator::from_object(self.iterator.as_ref(py))
+                    .and_then(|mut iterator| iterator.next().transpose())
+            })
+            .map_err(|e| call_error(py, EXTRACT_STREAM_METHOD, e))
+            .transpose()
+            .map(|py_content| {
+                py_content.and_then(|py_content| content_from_py(py, &py_content.into_py(py)))
+            })
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct PythonExtractor {
     extractor_wrapper: PyObject,
     extractor_schema: ExtractorSchema,
+    /// The extract method of the extractor is a generator
+    streaming: bool,
 }
 
 impl PythonExtractor {
     pub fn new_from_extractor_path(extractor_path: &str) -> Result<Self> {
-        let tokens: Vec<&str> = extractor_path.split(':').collect();
-        if tokens.len() != 2 {
-            return Err(anyhow!("invalid extractor path: {}", extractor_path));
-        }
-        let module_path = tokens[0];
-        let class_name = tokens[1].trim();
-        let module_file_name = Path::new(module_path)
-            .file_name()
-            .ok_or(anyhow!("couldn't find model file name: {:?}", module_path))?
-            .to_str()
-            .ok_or(anyhow!("couldn't find model file name: {:?}", module_path))?;
-
-        let module_name = module_file_name.trim_end_matches(".py");
-        Self::new(module_name, class_name)
+        let (module_name, class_name) = parse_extractor_path(extractor_path)?;
+        Self::new(&module_name, &class_name)
     }
 
     pub fn new(module_name: &str, class_name: &str) -> Result<Self, anyhow::Error> {
-        let (extractor_wrapper, extractor_schema) = Python::with_gil(|py| {
+        let (extractor_wrapper, extractor_schema, streaming) = Python::with_gil(|py| {
             let syspath: &PyList = py
                 .import("sys")?
                 .getattr("path")?
                 .downcast()
                 .map_err(|e| anyhow!(e.to_string()))?;
             syspath.insert(0, ".")?;
+            install_log_writers(py)?;
             let module = PyModule::import(
                 py,
                 PyString::new(py, "indexify_extractor_sdk.base_extractor"),
@@ -171,7 +350,7 @@ impl PythonExtractor {
                 .map_err(|e| anyhow!(e.to_string()))?;
             let mut metadata_schemas = HashMap::new();
             for (key, value) in metadata_schemas_temp.iter() {
-                let value: serde_json::Value = serde_json::from_str(&value)?;
+                let value: serde_json::Value = serde_json::from_str(value)?;
                 metadata_schemas.insert(key.clone(), value);
             }
             let embedding_schemas: HashMap<String, EmbeddingSchema> = description
@@ -188,6 +367,11 @@ impl PythonExtractor {
                 .getattr(py, "system_dependencies")?
                 .extract(py)?;
             let version: String = description.getattr(py, "version")?.extract(py)?;
+            // older versions of the sdk don't stream
+            let streaming: bool = description
+                .getattr(py, "streaming")
+                .and_then(|streaming| streaming.extract(py))
+                .unwrap_or_default();
 
             let extractor_schema = ExtractorSchema {
                 name,
@@ -200,11 +384,12 @@ impl PythonExtractor {
                 input_params,
                 input_mimes,
             };
-            Ok((extractor_wrapper, extractor_schema))
+            Ok((extractor_wrapper, extractor_schema, streaming))
         })?;
         Ok(Self {
             extractor_wrapper,
             extractor_schema,
+            streaming,
         })
     }
 }
@@ -228,56 +413,17 @@ impl Extractor for PythonExtractor {
                 .map(PyContent::try_from)
                 .collect::<Result<Vec<PyContent>, anyhow::Error>>()?;
 
-            let extracted_data = self
-                .extractor_wrapper
-                .call_method1(py, EXTRACT_METHOD, (content, json_string))
-                .map_err(|e| {
-                    let trace_back = e.traceback(py).map(|t| t.format());
-                    anyhow!(
-                        "error calling extract method: err {}, trace back: {:?}",
-                        e.to_string(),
-                        trace_back.unwrap().unwrap()
-                    )
-                })?;
-            let py_extracted_data: Vec<Vec<PyObject>> = extracted_data.clone().extract(py).unwrap();
+            let extracted_data = until_task_deadline(py, || {
+                self.extractor_wrapper
+                    .call_method1(py, EXTRACT_METHOD, (content, json_string))
+            })
+            .map_err(|e| call_error(py, EXTRACT_METHOD, e))?;
+            let py_extracted_data: Vec<Vec<PyObject>> = extracted_data.extract(py)?;
             let mut extracted_content = Vec::new();
             for list1 in py_extracted_data.iter() {
                 let mut temp = Vec::new();
                 for py_content in list1.iter() {
-                    let mime: String = py_content.getattr(py, "content_type")?.extract(py)?;
-                    let data: Vec<u8> = py_content.getattr(py, "data")?.extract(py)?;
-                    let py_features: Vec<PyObject> =
-                        py_content.getattr(py, "features")?.extract(py)?;
-                    let mut features = Vec::new();
-                    for py_feature in py_features {
-                        let feature_type: String =
-                            py_feature.getattr(py, "feature_type")?.extract(py)?;
-                        let feature_type = internal_api::FeatureType::from_str(&feature_type)?;
-                        let name: String = py_feature.getattr(py, "name")?.extract(py)?;
-                        let value: String = py_feature.getattr(py, "value")?.extract(py)?;
-                        let value: serde_json::Value = serde_json::from_str(&value)?;
-                        features.push(internal_api::Feature {
-                            feature_type,
-                            name,
-                            data: value,
-                        });
-                    }
-                    let py_labels: Option<PyObject> =
-                        py_content.getattr(py, "labels")?.extract(py)?;
-
-                    let labels = match py_labels {
-                        Some(py_labels) => {
-                            let labels: HashMap<String, String> = py_labels.extract(py)?;
-                            labels
-                        }
-                        None => HashMap::new(),
-                    };
-                    temp.push(internal_api::Content {
-                        mime,
-                        bytes: data,
-                        features,
-                        labels,
-                    });
+                    temp.push(content_from_py(py, py_content)?);
                 }
                 extracted_content.push(temp);
             }
@@ -286,6 +432,30 @@ impl Extractor for PythonExtractor {
 
         Ok(extracted_content)
     }
+
+    fn streams(&self) -> bool {
+        self.streaming
+    }
+
+    fn extract_stream(
+        &self,
+        content: internal_api::Content,
+        input_params: serde_json::Value,
+    ) -> Result<ContentStream, anyhow::Error> {
+        let iterator = Python::with_gil(|py| {
+            let json_string = serde_json::to_string(&input_params)?.into_py(py);
+            let content = PyContent::try_from(content)?;
+            until_task_deadline(py, || {
+                self.extractor_wrapper.call_method1(
+                    py,
+                    EXTRACT_STREAM_METHOD,
+                    (content, json_string),
+                )
+            })
+            .map_err(|e| call_error(py, EXTRACT_STREAM_METHOD, e))
+        })?;
+        Ok(Box::new(PyContentStream { iterator }))
+    }
 }
 
 #[cfg(test)]
@@ -372,4 +542,23 @@ mod tests {
 
         assert_eq!(extractor_schema.version, "0.0.0".to_string());
     }
+
+    #[test]
+    fn test_task_deadline() {
+        Python::with_gil(|py| {
+            let deadline = Instant::now() + std::time::Duration::from_millis(200);
+            let result = task_deadline::with_deadline(deadline, || {
+                until_task_deadline(py, || py.run("while True: pass", None, None))
+            });
+            assert!(result
+                .unwrap_err()
+                .is_instance_of::<pyo3::exceptions::PyTimeoutError>(py));
+            // calls finishing in time aren't interrupted later
+            let result = task_deadline::with_deadline(Instant::now(), || {
+                until_task_deadline(py, || py.run("x = 1", None, None))
+            });
+            assert!(result.is_ok());
+            assert!(py.run("y = sum(range(1000000))", None, None).is_ok());
+        });
+    }
 }
@@ -0,0 +1,495 @@
+use std::{
+    collections::HashMap,
+    io::{BufReader, BufWriter, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{mpsc, Arc, Condvar, Mutex},
+    time::Instant,
+};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use indexify_internal_api as internal_api;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{
+    py_extractors::{parse_extractor_path, PyContent, PyFeature},
+    task_deadline,
+    task_logs,
+    EmbeddingSchema,
+    Extractor,
+    ExtractorSchema,
+};
+use crate::server_config::PythonWorkerConfig;
+
+const WORKER_SCRIPT: &str = include_str!("python_worker.py");
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkerFeature {
+    feature_type: String,
+    name: String,
+    value: String,
+}
+
+/// Content as exchanged with the python worker, the data is base64 encoded
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkerContent {
+    content_type: String,
+    data: String,
+    features: Vec<WorkerFeature>,
+    labels: HashMap<String, String>,
+}
+
+impl From<PyContent> for WorkerContent {
+    fn from(content: PyContent) -> Self {
+        Self {
+            content_type: content.content_type,
+            data: general_purpose::STANDARD.encode(content.data),
+            features: content
+                .features
+                .into_iter()
+                .map(|f| WorkerFeature {
+                    feature_type: f.feature_type,
+                    name: f.name,
+                    value: f.value,
+                })
+                .collect(),
+            labels: content.labels,
+        }
+    }
+}
+
+impl TryFrom<WorkerContent> for PyContent {
+    type Error = anyhow::Error;
+
+    fn try_from(content: WorkerContent) -> Result<Self> {
+        Ok(Self {
+            content_type: content.content_type,
+            data: general_purpose::STANDARD.decode(content.data)?,
+            features: content
+                .features
+                .into_iter()
+                .map(|f| PyFeature {
+                    feature_type: f.feature_type,
+                    name: f.name,
+                    value: f.value,
+                })
+                .collect(),
+            labels: content.labels,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum WorkerRequest {
+    Describe,
+    Extract {
+        content: Vec<WorkerContent>,
+        params: String,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerResponse {
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+    // what the extractor printed or logged while handling the request
+    #[serde(default)]
+    logs: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerDescription {
+    name: String,
+    version: String,
+    description: String,
+    python_dependencies: Vec<String>,
+    system_dependencies: Vec<String>,
+    embedding_schemas: HashMap<String, EmbeddingSchema>,
+    metadata_schemas: HashMap<String, String>,
+    input_params: Option<String>,
+    input_mime_types: Vec<String>,
+}
+
+impl TryFrom<WorkerDescription> for ExtractorSchema {
+    type Error = anyhow::Error;
+
+    fn try_from(description: WorkerDescription) -> Result<Self> {
+        let mut metadata_schemas = HashMap::new();
+        for (name, schema) in description.metadata_schemas {
+            metadata_schemas.insert(name, serde_json::from_str(&schema)?);
+        }
+        let input_params = match description.input_params {
+            Some(input_params) => serde_json::from_str(&input_params)?,
+            None => serde_json::Value::Null,
+        };
+        Ok(Self {
+            name: description.name,
+            version: description.version,
+            description: description.description,
+            python_dependencies: description.python_dependencies,
+            system_dependencies: description.system_dependencies,
+            embedding_schemas: description.embedding_schemas,
+            metadata_schemas,
+            input_params,
+            input_mimes: description.input_mime_types,
+        })
+    }
+}
+
+fn write_frame(writer: &mut impl Write, message: &[u8]) -> Result<()> {
+    writer.write_u32::<BigEndian>(message.len() as u32)?;
+    writer.write_all(message)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_frame(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let len = reader.read_u32::<BigEndian>()?;
+    let mut message = vec![0; len as usize];
+    reader.read_exact(&mut message)?;
+    Ok(message)
+}
+
+/// Errors of a call to a worker. A transport error means the worker is
+/// unusable and has to be replaced.
+enum CallError {
+    Transport(anyhow::Error),
+    Extractor(anyhow::Error),
+}
+
+struct PythonWorker {
+    // shared with the watchdog killing the worker at the task deadline
+    child: Arc<Mutex<Child>>,
+    stdin: BufWriter<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    tasks_completed: usize,
+}
+
+impl PythonWorker {
+    fn spawn(config: &PythonWorkerConfig, module_name: &str, class_name: &str) -> Result<Self> {
+        let mut child = Command::new(&config.python)
+            .arg("-c")
+            .arg(WORKER_SCRIPT)
+            .arg(module_name)
+            .arg(class_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| anyhow!("unable to start python worker: {}", e))?;
+        info!("started python worker, pid: {}", child.id());
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or(anyhow!("python worker has no stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(anyhow!("python worker has no stdout"))?;
+        Ok(Self {
+            child: Arc::new(Mutex::new(child)),
+            stdin: BufWriter::new(stdin),
+            stdout: BufReader::new(stdout),
+            tasks_completed: 0,
+        })
+    }
+
+    fn call(&mut self, request: &WorkerRequest) -> Result<serde_json::Value, CallError> {
+        let request = serde_json::to_vec(request).map_err(|e| CallError::Extractor(e.into()))?;
+        write_frame(&mut self.stdin, &request).map_err(CallError::Transport)?;
+        let response = read_frame(&mut self.stdout).map_err(CallError::Transport)?;
+        let response: WorkerResponse =
+            serde_json::from_slice(&response).map_err(|e| CallError::Transport(e.into()))?;
+        if !task_logs::write(&response.logs) {
+            eprint!("{}", response.logs);
+        }
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(CallError::Extractor(anyhow!(
+                "error calling extract method: {}",
+                error
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(CallError::Transport(anyhow!(
+                "python worker returned an empty response"
+            ))),
+        }
+    }
+
+    /// Calls the worker, killing it if the call is still running at the
+    /// deadline
+    fn call_until(
+        &mut self,
+        request: &WorkerRequest,
+        deadline: Option<Instant>,
+    ) -> Result<serde_json::Value, CallError> {
+        let Some(deadline) = deadline else {
+            return self.call(request);
+        };
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let watchdog = std::thread::spawn({
+            let child = self.child.clone();
+            move || {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if done_rx.recv_timeout(timeout) != Err(mpsc::RecvTimeoutError::Timeout) {
+                    return false;
+                }
+                let _ = child.lock().unwrap().kill();
+                true
+            }
+        });
+        let result = self.call(request);
+        drop(done_tx);
+        if watchdog.join().unwrap_or(false) {
+            return Err(CallError::Transport(anyhow!(
+                "the task deadline passed, killed the python worker"
+            )));
+        }
+        result
+    }
+
+    fn id(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+}
+
+impl Drop for PythonWorker {
+    fn drop(&mut self) {
+        let mut child = self.child.lock().unwrap();
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Runs a python extractor in a pool of worker processes instead of the
+/// executor's embedded interpreter, so extractions run in parallel without
+/// contending for the GIL. Workers which crash or are still running at the
+/// deadline of their task are replaced on the next call and workers are
+/// recycled after `max_tasks_per_worker` calls to contain memory leaks.
+pub struct PythonWorkerPool {
+    config: PythonWorkerConfig,
+    module_name: String,
+    class_name: String,
+    extractor_schema: ExtractorSchema,
+    // idle slots of the pool, `None` is a slot whose worker isn't running
+    idle: Mutex<Vec<Option<PythonWorker>>>,
+    slot_released: Condvar,
+}
+
+impl std::fmt::Debug for PythonWorkerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PythonWorkerPool")
+            .field("config", &self.config)
+            .field("module_name", &self.module_name)
+            .field("class_name", &self.class_name)
+            .finish()
+    }
+}
+
+impl PythonWorkerPool {
+    pub fn new_from_extractor_path(
+        extractor_path: &str,
+        config: PythonWorkerConfig,
+    ) -> Result<Self> {
+        let (module_name, class_name) = parse_extractor_path(extractor_path)?;
+        let mut worker = PythonWorker::spawn(&config, &module_name, &class_name)?;
+        let description = worker.call(&WorkerRequest::Describe).map_err(|e| match e {
+            CallError::Transport(e) | CallError::Extractor(e) => {
+                anyhow!("unable to describe extractor: {}", e)
+            }
+        })?;
+        let description: WorkerDescription = serde_json::from_value(description)?;
+        let extractor_schema = description.try_into()?;
+
+        let mut idle: Vec<Option<PythonWorker>> =
+            (1..config.workers.max(1)).map(|_| None).collect();
+        idle.push(Some(worker));
+        Ok(Self {
+            config,
+            module_name,
+            class_name,
+            extractor_schema,
+            idle: Mutex::new(idle),
+            slot_released: Condvar::new(),
+        })
+    }
+
+    /// Runs `f` with an idle worker, waiting for one until the deadline
+    fn with_worker<T>(
+        &self,
+        deadline: Option<Instant>,
+        f: impl FnOnce(&mut PythonWorker) -> Result<T, CallError>,
+    ) -> Result<T> {
+        let slot = {
+            let mut idle = self.idle.lock().unwrap();
+            loop {
+                if let Some(slot) = idle.pop() {
+                    break slot;
+                }
+                idle = match deadline {
+                    Some(deadline) => {
+                        let timeout = deadline.saturating_duration_since(Instant::now());
+                        let (idle, wait) = self.slot_released.wait_timeout(idle, timeout).unwrap();
+                        if wait.timed_out() && idle.is_empty() {
+                            return Err(anyhow!(
+                                "the task deadline passed waiting for a python worker"
+                            ));
+                        }
+                        idle
+                    }
+                    None => self.slot_released.wait(idle).unwrap(),
+                };
+            }
+        };
+        let mut worker = match slot {
+            Some(worker) => worker,
+            None => match PythonWorker::spawn(&self.config, &self.module_name, &self.class_name) {
+                Ok(worker) => worker,
+                Err(err) => {
+                    self.release(None);
+                    return Err(err);
+                }
+            },
+        };
+        let result = f(&mut worker);
+        worker.tasks_completed += 1;
+        match result {
+            Ok(value) => {
+                self.release(Some(worker));
+                Ok(value)
+            }
+            Err(CallError::Extractor(err)) => {
+                self.release(Some(worker));
+                Err(err)
+            }
+            Err(CallError::Transport(err)) => {
+                warn!(
+                    "python worker {} failed, restarting it: {}",
+                    worker.id(),
+                    err
+                );
+                self.release(None);
+                Err(anyhow!("python worker failed: {}", err))
+            }
+        }
+    }
+
+    fn release(&self, worker: Option<PythonWorker>) {
+        let max_tasks = self.config.max_tasks_per_worker;
+        let worker = worker.filter(|w| max_tasks == 0 || w.tasks_completed < max_tasks);
+        self.idle.lock().unwrap().push(worker);
+        self.slot_released.notify_one();
+    }
+}
+
+impl Extractor for PythonWorkerPool {
+    fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+        Ok(self.extractor_schema.clone())
+    }
+
+    fn extract(
+        &self,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+        let content = content
+            .into_iter()
+            .map(|c| PyContent::try_from(c).map(WorkerContent::from))
+            .collect::<Result<Vec<_>>>()?;
+        let request = WorkerRequest::Extract {
+            content,
+            params: serde_json::to_string(&input_params)?,
+        };
+        let deadline = task_deadline::current();
+        let result = self.with_worker(deadline, |worker| worker.call_until(&request, deadline))?;
+        let extracted: Vec<Vec<WorkerContent>> = serde_json::from_value(result)?;
+        extracted
+            .into_iter()
+            .map(|list| {
+                list.into_iter()
+                    .map(|c| PyContent::try_from(c)?.try_into())
+                    .collect::<Result<Vec<internal_api::Content>>>()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, b"hello").unwrap();
+        write_frame(&mut buf, b"").unwrap();
+        assert_eq!(&buf[..4], &[0, 0, 0, 5]);
+        let mut reader = Cursor::new(buf);
+        assert_eq!(b"hello".to_vec(), read_frame(&mut reader).unwrap());
+        assert!(read_frame(&mut reader).unwrap().is_empty());
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_call_until_deadline() {
+        // a worker which never answers
+        let mut child = Command::new("sleep")
+            .arg("60")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut worker = PythonWorker {
+            stdin: BufWriter::new(child.stdin.take().unwrap()),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child: Arc::new(Mutex::new(child)),
+            tasks_completed: 0,
+        };
+        let deadline = Instant::now() + std::time::Duration::from_millis(200);
+        let result = worker.call_until(&WorkerRequest::Describe, Some(deadline));
+        assert!(matches!(result, Err(CallError::Transport(_))));
+        // the worker was killed instead of sleeping until it exits
+        assert!(!worker.child.lock().unwrap().wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_worker_response() {
+        let response: WorkerResponse =
+            serde_json::from_str(r#"{"result": [], "logs": "loaded model\n"}"#).unwrap();
+        assert_eq!("loaded model\n", response.logs);
+        let response: WorkerResponse = serde_json::from_str(r#"{"error": "boom"}"#).unwrap();
+        assert!(response.logs.is_empty());
+    }
+
+    #[test]
+    fn test_worker_content() {
+        let content = internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: b"hello world".to_vec(),
+            features: vec![internal_api::Feature {
+                feature_type: internal_api::FeatureType::Metadata,
+                name: "metadata".to_string(),
+                data: serde_json::json!({"key": "value"}),
+            }],
+            labels: HashMap::from([("url".to_string(), "test.com".to_string())]),
+        };
+        let worker_content = WorkerContent::from(PyContent::try_from(content).unwrap());
+        assert_eq!("aGVsbG8gd29ybGQ=", worker_content.data);
+        assert_eq!("metadata", worker_content.features[0].feature_type);
+
+        let content: internal_api::Content = PyContent::try_from(worker_content)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(b"hello world".to_vec(), content.bytes);
+        assert_eq!(
+            serde_json::json!({"key": "value"}),
+            content.features[0].data
+        );
+        assert_eq!("test.com", content.labels["url"]);
+    }
+}
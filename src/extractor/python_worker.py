@@ -0,0 +1,124 @@
+# Runs a python extractor in a worker process of the indexify executor.
+#
+# Requests and responses are JSON messages framed with a 4 byte big endian
+# length prefix, exchanged over stdin and stdout. Anything the extractor
+# prints or logs while handling a request is captured and returned in the
+# "logs" field of the response, so that it doesn't corrupt the protocol and
+# ends up in the logs of the task.
+import base64
+import json
+import logging
+import struct
+import sys
+import traceback
+
+from indexify_extractor_sdk.base_extractor import Content, ExtractorWrapper, Feature
+
+
+def read_frame(stream):
+    header = stream.read(4)
+    if len(header) < 4:
+        return None
+    (length,) = struct.unpack(">I", header)
+    return json.loads(stream.read(length))
+
+
+def write_frame(stream, message):
+    data = json.dumps(message).encode("utf-8")
+    stream.write(struct.pack(">I", len(data)))
+    stream.write(data)
+    stream.flush()
+
+
+class LogWriter:
+    """Replaces sys.stdout and sys.stderr, output is captured while a request
+    is handled and goes to stderr otherwise."""
+
+    def __init__(self, stderr):
+        self.stderr = stderr
+        self.captured = None
+
+    def write(self, text):
+        if self.captured is None:
+            self.stderr.write(text)
+        else:
+            self.captured.append(text)
+        return len(text)
+
+    def flush(self):
+        if self.captured is None:
+            self.stderr.flush()
+
+    def isatty(self):
+        return False
+
+    def capture(self):
+        self.captured = []
+
+    def release(self):
+        captured, self.captured = self.captured, None
+        return "".join(captured or [])
+
+
+def to_content(content):
+    return Content(
+        content_type=content["content_type"],
+        data=base64.b64decode(content["data"]),
+        features=[Feature(**feature) for feature in content["features"]],
+        labels=content["labels"],
+    )
+
+
+def from_content(content):
+    return {
+        "content_type": content.content_type or "",
+        "data": base64.b64encode(content.data).decode("ascii"),
+        "features": [
+            {"feature_type": f.feature_type, "name": f.name, "value": f.value}
+            for f in content.features
+        ],
+        "labels": content.labels or {},
+    }
+
+
+def main():
+    module_name, class_name = sys.argv[1], sys.argv[2]
+    protocol_in = sys.stdin.buffer
+    protocol_out = sys.stdout.buffer
+    writer = LogWriter(sys.stderr)
+    sys.stdout = writer
+    sys.stderr = writer
+    # extractors which don't configure logging log to the captured stderr
+    if not logging.getLogger().hasHandlers():
+        logging.basicConfig(
+            level=logging.INFO,
+            format="%(asctime)s %(levelname)s %(name)s: %(message)s",
+            stream=writer,
+        )
+    sys.path.insert(0, ".")
+    wrapper = ExtractorWrapper(module_name, class_name)
+    while True:
+        request = read_frame(protocol_in)
+        if request is None:
+            break
+        writer.capture()
+        try:
+            if request["method"] == "describe":
+                result = wrapper.describe().model_dump()
+            elif request["method"] == "extract":
+                content = [to_content(c) for c in request["content"]]
+                extracted = wrapper.extract(content, request["params"])
+                result = [[from_content(c) for c in batch] for batch in extracted]
+            else:
+                raise ValueError("unknown method: {}".format(request["method"]))
+            response = {"result": result}
+        except Exception:
+            error = traceback.format_exc()
+            writer.write(error)
+            response = {"error": error}
+        response["logs"] = writer.release()
+        write_frame(protocol_out, response)
+
+
+if __name__ == "__main__":
+    main()
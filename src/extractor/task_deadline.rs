@@ -0,0 +1,19 @@
+use std::{cell::Cell, time::Instant};
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = Cell::new(None);
+}
+
+/// Runs `f` on the current thread with the deadline of the task it works
+/// on, extractors which can be interrupted stop once it passes
+pub fn with_deadline<T>(deadline: Instant, f: impl FnOnce() -> T) -> T {
+    let previous = DEADLINE.with(|current| current.replace(Some(deadline)));
+    let result = f();
+    DEADLINE.with(|current| current.set(previous));
+    result
+}
+
+/// Deadline of the task running on the current thread, if any
+pub fn current() -> Option<Instant> {
+    DEADLINE.with(|current| current.get())
+}
@@ -0,0 +1,127 @@
+use std::{
+    cell::RefCell,
+    sync::{Arc, Mutex},
+};
+
+thread_local! {
+    static CAPTURE: RefCell<Option<TaskLogs>> = RefCell::new(None);
+}
+
+#[derive(Debug, Default)]
+struct LogBuffer {
+    logs: String,
+    limit: usize,
+    truncated_bytes: usize,
+}
+
+impl LogBuffer {
+    fn write(&mut self, text: &str) {
+        self.logs.push_str(text);
+        // trim in bulk so that writing many small lines stays cheap
+        if self.logs.len() > self.limit.saturating_mul(2).max(1024) {
+            self.truncate();
+        }
+    }
+
+    /// Keeps the last `limit` bytes, the end of the logs of a failed task
+    /// is the most useful part
+    fn truncate(&mut self) {
+        if self.logs.len() <= self.limit {
+            return;
+        }
+        let mut start = self.logs.len() - self.limit;
+        while !self.logs.is_char_boundary(start) {
+            start += 1;
+        }
+        self.logs.drain(..start);
+        self.truncated_bytes += start;
+    }
+}
+
+/// Output of an extractor captured while it runs a task, at most `limit`
+/// bytes of it are kept
+#[derive(Clone, Debug, Default)]
+pub struct TaskLogs {
+    buffer: Arc<Mutex<LogBuffer>>,
+}
+
+impl TaskLogs {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(LogBuffer {
+                limit,
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Runs `f`, capturing what is logged on the current thread
+    pub fn capture<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = CAPTURE.with(|capture| capture.replace(Some(self.clone())));
+        let result = f();
+        CAPTURE.with(|capture| capture.replace(previous));
+        result
+    }
+
+    /// Returns the logs captured so far
+    pub fn contents(&self) -> String {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.truncate();
+        if buffer.truncated_bytes == 0 {
+            return buffer.logs.clone();
+        }
+        format!(
+            "[{} bytes truncated]\n{}",
+            buffer.truncated_bytes, buffer.logs
+        )
+    }
+}
+
+/// Appends to the logs captured on the current thread, returns false if
+/// they aren't captured
+pub fn write(text: &str) -> bool {
+    CAPTURE.with(|capture| match capture.borrow().as_ref() {
+        Some(logs) => {
+            logs.buffer.lock().unwrap().write(text);
+            true
+        }
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write, TaskLogs};
+
+    #[test]
+    fn test_capture() {
+        let logs = TaskLogs::new(1024);
+        let written = logs.capture(|| write("hello\n") && write("world\n"));
+        assert!(written);
+        assert_eq!("hello\nworld\n", logs.contents());
+        // only the thread running the capture is captured
+        assert!(!write("other\n"));
+        let other = std::thread::spawn({
+            let logs = logs.clone();
+            move || logs.capture(|| std::thread::spawn(|| write("other\n")).join())
+        });
+        assert!(!other.join().unwrap().unwrap());
+        assert_eq!("hello\nworld\n", logs.contents());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let logs = TaskLogs::new(10);
+        logs.capture(|| {
+            for i in 0..1000 {
+                write(&format!("{:04}\n", i));
+            }
+        });
+        assert_eq!("[4990 bytes truncated]\n0998\n0999\n", logs.contents());
+
+        // truncated on a char boundary
+        let logs = TaskLogs::new(3);
+        logs.capture(|| write("aéé"));
+        assert_eq!("[3 bytes truncated]\né", logs.contents());
+    }
+}
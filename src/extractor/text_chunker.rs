@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use indexify_internal_api as internal_api;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{builtin_params, Extractor, ExtractorSchema};
+use crate::text_splitters::{get_splitter, TextSplitterKind};
+
+pub const NAME: &str = "text_chunker";
+
+#[derive(Debug, Deserialize)]
+struct ChunkingParams {
+    #[serde(flatten)]
+    splitter: TextSplitterKind,
+    chunk_size: usize,
+    overlap: usize,
+}
+
+/// Built-in extractor splitting text into chunks, one output content per
+/// chunk. Chunk sizes are measured in characters.
+#[derive(Debug, Default)]
+pub struct TextChunker;
+
+impl Extractor for TextChunker {
+    fn schemas(&self) -> Result<ExtractorSchema> {
+        Ok(ExtractorSchema {
+            name: NAME.to_string(),
+            version: crate::VERSION.to_string(),
+            description: "splits text into chunks at line breaks, regex matches, separators \
+                          or markdown headings"
+                .to_string(),
+            input_params: json!({
+                "type": "object",
+                "properties": {
+                    "splitter": {
+                        "type": "string",
+                        "enum": ["new_line", "regex", "recursive", "markdown"],
+                        "default": "recursive",
+                    },
+                    "chunk_size": {"type": "integer", "minimum": 1, "default": 1000},
+                    "overlap": {"type": "integer", "minimum": 0, "default": 0},
+                    "pattern": {
+                        "type": "string",
+                        "description": "pattern to split after, required by the regex splitter",
+                    },
+                    "separators": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "separators tried in order by the recursive splitter",
+                    },
+                },
+            }),
+            input_mimes: vec!["text/plain".to_string(), "text/markdown".to_string()],
+            ..Default::default()
+        })
+    }
+
+    fn extract(
+        &self,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<internal_api::Content>>> {
+        let params: ChunkingParams = builtin_params(self, input_params)?;
+        let splitter = get_splitter(params.splitter)?;
+        content
+            .into_iter()
+            .map(|content| {
+                let text = String::from_utf8(content.bytes)
+                    .map_err(|e| anyhow!("content is not valid utf-8: {}", e))?;
+                let chunks = splitter.split(&text, params.chunk_size, params.overlap)?;
+                Ok(chunks
+                    .into_iter()
+                    .map(|chunk| internal_api::Content {
+                        mime: content.mime.clone(),
+                        bytes: chunk.into_bytes(),
+                        features: vec![],
+                        labels: HashMap::new(),
+                    })
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_content(text: &str) -> internal_api::Content {
+        internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: text.as_bytes().to_vec(),
+            features: vec![],
+            labels: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_text_chunker() {
+        let chunker = TextChunker;
+        assert_eq!(NAME, chunker.schemas().unwrap().name);
+
+        let outputs = chunker
+            .extract(
+                vec![text_content("one\ntwo\nthree"), text_content("four")],
+                json!({"splitter": "new_line", "chunk_size": 5}),
+            )
+            .unwrap();
+        assert_eq!(2, outputs.len());
+        let chunks: Vec<&[u8]> = outputs[0].iter().map(|c| c.bytes.as_slice()).collect();
+        assert_eq!(vec![&b"one\n"[..], b"two\n", b"three"], chunks);
+        assert_eq!(1, outputs[1].len());
+
+        // defaults apply to missing params
+        let outputs = chunker
+            .extract(vec![text_content("one two")], serde_json::Value::Null)
+            .unwrap();
+        assert_eq!(b"one two".to_vec(), outputs[0][0].bytes);
+
+        let invalid = [
+            json!({"splitter": "regex"}),
+            json!({"splitter": "regex", "pattern": "("}),
+            json!({"chunk_size": 10, "overlap": 10}),
+            json!("recursive"),
+        ];
+        for params in invalid {
+            assert!(chunker.extract(vec![text_content("one")], params).is_err());
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use indexify_internal_api as internal_api;
+use wasmtime::{
+    component::{Component, Linker},
+    Config,
+    Engine,
+    Store,
+    StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use self::bindings::indexify::extractor::types;
+use super::{EmbeddingSchema, Extractor, ExtractorSchema};
+use crate::server_config::WasmConfig;
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        world: "extractor",
+        path: "wit/extractor.wit",
+    });
+}
+
+/// Returns true if the extractor path points to a WebAssembly component
+pub fn is_wasm_extractor_path(extractor_path: &str) -> bool {
+    Path::new(extractor_path)
+        .extension()
+        .map_or(false, |ext| ext == "wasm")
+}
+
+fn content_to_wasm(content: internal_api::Content) -> types::Content {
+    types::Content {
+        mime: content.mime,
+        bytes: content.bytes,
+        features: content.features.into_iter().map(feature_to_wasm).collect(),
+        labels: content.labels.into_iter().collect(),
+    }
+}
+
+fn feature_to_wasm(feature: internal_api::Feature) -> types::Feature {
+    types::Feature {
+        feature_type: match feature.feature_type {
+            internal_api::FeatureType::Embedding => "embedding",
+            internal_api::FeatureType::Metadata => "metadata",
+            internal_api::FeatureType::Unknown => "unknown",
+        }
+        .to_string(),
+        name: feature.name,
+        data: feature.data.to_string(),
+    }
+}
+
+fn feature_from_wasm(feature: types::Feature) -> Result<internal_api::Feature> {
+    Ok(internal_api::Feature {
+        feature_type: internal_api::FeatureType::from_str(&feature.feature_type)
+            .map_err(|_| anyhow!("invalid feature type: {}", feature.feature_type))?,
+        data: serde_json::from_str(&feature.data)
+            .map_err(|e| anyhow!("invalid value of feature {}: {}", feature.name, e))?,
+        name: feature.name,
+    })
+}
+
+fn content_from_wasm(content: types::Content) -> Result<internal_api::Content> {
+    Ok(internal_api::Content {
+        mime: content.mime,
+        bytes: content.bytes,
+        features: content
+            .features
+            .into_iter()
+            .map(feature_from_wasm)
+            .collect::<Result<_>>()?,
+        labels: content.labels.into_iter().collect(),
+    })
+}
+
+fn schema_from_wasm(schema: types::ExtractorSchema) -> Result<ExtractorSchema> {
+    let embedding_schemas: HashMap<String, EmbeddingSchema> =
+        serde_json::from_str(&schema.embedding_schemas)
+            .map_err(|e| anyhow!("invalid embedding schemas: {}", e))?;
+    let metadata_schemas = serde_json::from_str(&schema.metadata_schemas)
+        .map_err(|e| anyhow!("invalid metadata schemas: {}", e))?;
+    let input_params = match schema.input_params.as_str() {
+        "" => serde_json::Value::Null,
+        input_params => serde_json::from_str(input_params)
+            .map_err(|e| anyhow!("invalid input params schema: {}", e))?,
+    };
+    Ok(ExtractorSchema {
+        name: schema.name,
+        version: schema.version,
+        description: schema.description,
+        python_dependencies: vec![],
+        system_dependencies: vec![],
+        embedding_schemas,
+        metadata_schemas,
+        input_params,
+        input_mimes: schema.input_mimes,
+    })
+}
+
+/// Runs an extractor compiled to a WebAssembly component implementing the
+/// `indexify:extractor` world of `wit/extractor.wit` in a sandbox. The world
+/// doesn't import any host functions, so the extractor has no access to the
+/// host, and each call runs in a fresh instance bounded by the configured
+/// memory and fuel.
+pub struct WasmExtractor {
+    engine: Engine,
+    component: Component,
+    linker: Linker<StoreLimits>,
+    config: WasmConfig,
+    extractor_schema: ExtractorSchema,
+}
+
+impl std::fmt::Debug for WasmExtractor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmExtractor")
+            .field("config", &self.config)
+            .field("name", &self.extractor_schema.name)
+            .finish()
+    }
+}
+
+impl WasmExtractor {
+    pub fn new_from_path(path: &str, config: WasmConfig) -> Result<Self> {
+        let wasm = std::fs::read(path)
+            .map_err(|e| anyhow!("unable to read wasm extractor {}: {}", path, e))?;
+        Self::new(&wasm, config)
+    }
+
+    pub fn new(wasm: &[u8], config: WasmConfig) -> Result<Self> {
+        let mut engine_config = Config::new();
+        engine_config.wasm_component_model(true);
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config)?;
+        let component = Component::new(&engine, wasm)?;
+        let mut extractor = Self {
+            linker: Linker::new(&engine),
+            engine,
+            component,
+            config,
+            extractor_schema: ExtractorSchema::default(),
+        };
+        let (mut store, instance) = extractor.instantiate()?;
+        let schema = instance.call_schemas(&mut store)?;
+        extractor.extractor_schema = schema_from_wasm(schema)
+            .map_err(|e| anyhow!("invalid schemas of wasm extractor: {}", e))?;
+        Ok(extractor)
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, bindings::Extractor)> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.config.max_memory_bytes)
+            .build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.config.fuel)?;
+        let instance = bindings::Extractor::instantiate(&mut store, &self.component, &self.linker)?;
+        Ok((store, instance))
+    }
+}
+
+impl Extractor for WasmExtractor {
+    fn schemas(&self) -> Result<ExtractorSchema, anyhow::Error> {
+        Ok(self.extractor_schema.clone())
+    }
+
+    fn extract(
+        &self,
+        content: Vec<internal_api::Content>,
+        input_params: serde_json::Value,
+    ) -> Result<Vec<Vec<internal_api::Content>>, anyhow::Error> {
+        let content: Vec<_> = content.into_iter().map(content_to_wasm).collect();
+        let (mut store, instance) = self.instantiate()?;
+        let outputs = instance
+            .call_extract(&mut store, &content, &input_params.to_string())
+            .map_err(|e| anyhow!("error calling extract method: {}", e))?
+            .map_err(|e| anyhow!("error calling extract method: {}", e))?;
+        outputs
+            .into_iter()
+            .map(|output| output.into_iter().map(content_from_wasm).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Returns a component implementing the extractor world whose core
+    /// `extract` function runs `extract_body` and returns the address of its
+    /// result, `OK` or `ERROR`
+    fn test_component(extract_body: &str) -> String {
+        format!(
+            r#"(component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (global $next (mut i32) (i32.const 1024))
+                    (data (i32.const 0) "wasm_test")
+                    (data (i32.const 16) "text/plain")
+                    (data (i32.const 32) "hi")
+                    (data (i32.const 40) "{{}}")
+                    (data (i32.const 48) "boom")
+                    ;; input-mimes
+                    (data (i32.const 64) "\10\00\00\00\0a\00\00\00")
+                    ;; extractor-schema
+                    (data (i32.const 128)
+                        "\00\00\00\00\09\00\00\00" "\00\00\00\00\00\00\00\00"
+                        "\00\00\00\00\00\00\00\00" "\28\00\00\00\02\00\00\00"
+                        "\40\00\00\00\01\00\00\00" "\28\00\00\00\02\00\00\00"
+                        "\28\00\00\00\02\00\00\00")
+                    ;; content "hi"
+                    (data (i32.const 256)
+                        "\10\00\00\00\0a\00\00\00" "\20\00\00\00\02\00\00\00"
+                        "\00\00\00\00\00\00\00\00" "\00\00\00\00\00\00\00\00")
+                    ;; outputs of a single input
+                    (data (i32.const 320) "\00\01\00\00\01\00\00\00")
+                    ;; OK, ok with the outputs
+                    (data (i32.const 384) "\00\00\00\00\40\01\00\00\01\00\00\00")
+                    ;; ERROR, error "boom"
+                    (data (i32.const 400) "\01\00\00\00\30\00\00\00\04\00\00\00")
+                    (func (export "realloc")
+                        (param i32 i32) (param $align i32) (param $len i32) (result i32)
+                        (local $ptr i32)
+                        (local.set $ptr (i32.and
+                            (i32.add (global.get $next) (i32.sub (local.get $align) (i32.const 1)))
+                            (i32.sub (i32.const 0) (local.get $align))))
+                        (global.set $next (i32.add (local.get $ptr) (local.get $len)))
+                        (local.get $ptr))
+                    (func (export "schemas") (result i32)
+                        (i32.const 128))
+                    (func (export "extract") (param i32 i32 i32 i32) (result i32)
+                        {extract_body}))
+                (core instance $i (instantiate $m))
+                (type $feature' (record
+                    (field "feature-type" string) (field "name" string) (field "data" string)))
+                (export $feature "feature" (type $feature'))
+                (type $content' (record
+                    (field "mime" string)
+                    (field "bytes" (list u8))
+                    (field "features" (list $feature))
+                    (field "labels" (list (tuple string string)))))
+                (export $content "content" (type $content'))
+                (type $schema' (record
+                    (field "name" string)
+                    (field "version" string)
+                    (field "description" string)
+                    (field "input-params" string)
+                    (field "input-mimes" (list string))
+                    (field "embedding-schemas" string)
+                    (field "metadata-schemas" string)))
+                (export $schema "extractor-schema" (type $schema'))
+                (func (export "schemas") (result $schema)
+                    (canon lift (core func $i "schemas") (memory $i "memory")))
+                (func (export "extract")
+                    (param "content" (list $content)) (param "input-params" string)
+                    (result (result (list (list $content)) (error string)))
+                    (canon lift (core func $i "extract")
+                        (memory $i "memory") (realloc (func $i "realloc")))))"#,
+            extract_body = extract_body,
+        )
+    }
+
+    const OK: &str = "(i32.const 384)";
+    const ERROR: &str = "(i32.const 400)";
+
+    fn text_content() -> internal_api::Content {
+        internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: b"hello".to_vec(),
+            features: vec![],
+            labels: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_wasm_extractor() {
+        let extractor =
+            WasmExtractor::new(test_component(OK).as_bytes(), WasmConfig::default()).unwrap();
+        let schema = extractor.schemas().unwrap();
+        assert_eq!("wasm_test", schema.name);
+        assert_eq!(vec!["text/plain".to_string()], schema.input_mimes);
+
+        let outputs = extractor.extract(vec![text_content()], json!({})).unwrap();
+        assert_eq!(1, outputs.len());
+        assert_eq!(b"hi".to_vec(), outputs[0][0].bytes);
+
+        let extractor =
+            WasmExtractor::new(test_component(ERROR).as_bytes(), WasmConfig::default()).unwrap();
+        let err = extractor
+            .extract(vec![text_content()], json!({}))
+            .unwrap_err();
+        assert!(err.to_string().contains("boom"));
+    }
+
+    #[test]
+    fn test_wasm_extractor_limits() {
+        // runaway extractors run out of fuel
+        let extractor = WasmExtractor::new(
+            test_component(&format!("(loop $forever (br $forever)) {}", OK)).as_bytes(),
+            WasmConfig {
+                fuel: 100_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(extractor.extract(vec![text_content()], json!({})).is_err());
+
+        // memory can't grow beyond the limit, the extractor only returns its
+        // output if growing the memory failed
+        let grow = format!(
+            "(if (result i32) (i32.eq (memory.grow (i32.const 100)) (i32.const -1))
+                (then {}) (else {}))",
+            OK, ERROR
+        );
+        let extractor = WasmExtractor::new(
+            test_component(&grow).as_bytes(),
+            WasmConfig {
+                max_memory_bytes: 2 * 65536,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(extractor.extract(vec![text_content()], json!({})).is_ok());
+
+        // components can't import host functions
+        let component = r#"(component (import "f" (func)))"#;
+        assert!(WasmExtractor::new(component.as_bytes(), WasmConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_wasm_core_module() {
+        let module = r#"(module (memory (export "memory") 1))"#;
+        assert!(WasmExtractor::new(module.as_bytes(), WasmConfig::default()).is_err());
+    }
+}
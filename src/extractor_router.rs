@@ -1,14 +1,23 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+};
 
 use anyhow::{anyhow, Result};
 use indexify_internal_api as internal_api;
 use indexify_proto::indexify_coordinator::GetExtractorCoordinatesRequest;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::{
     api::Content,
+    api_utils::validate_input_params,
     caching::{Cache, NoOpCache},
     coordinator_client::CoordinatorClient,
 };
@@ -26,10 +35,33 @@ pub struct ExtractContentCacheValue {
     content: Arc<Vec<Content>>,
 }
 
+/// Decrements the count of in flight requests of an executor when the
+/// request finishes
+struct InFlightRequest {
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+    addr: String,
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(&self.addr) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.addr);
+            }
+        }
+    }
+}
+
 pub struct ExtractorRouter {
     coordinator_client: Arc<CoordinatorClient>,
     cache: Arc<RwLock<Box<dyn Cache<ExtractContentCacheKey, ExtractContentCacheValue>>>>,
     client: reqwest::Client,
+    /// Requests sent by this router still waiting for a response, by
+    /// executor address
+    in_flight: Arc<Mutex<HashMap<String, usize>>>,
+    next: AtomicUsize,
 }
 
 impl ExtractorRouter {
@@ -42,6 +74,8 @@ impl ExtractorRouter {
             coordinator_client,
             cache: Arc::new(RwLock::new(Box::new(NoOpCache::new()))),
             client: request_client,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            next: AtomicUsize::new(0),
         })
     }
 
@@ -59,6 +93,33 @@ impl ExtractorRouter {
         content: Content,
         input_params: Option<serde_json::Value>,
     ) -> Result<Vec<Content>, anyhow::Error> {
+        let req = GetExtractorCoordinatesRequest {
+            extractor: extractor_name.to_string(),
+        };
+        let resp = self
+            .coordinator_client
+            .get()
+            .await?
+            .get_extractor_coordinates(req)
+            .await?
+            .into_inner();
+        let addresses = resp.addrs;
+        if addresses.is_empty() {
+            return Err(anyhow!("no extractor found"));
+        }
+        // fill in defaults before looking up the cache so that explicit and
+        // default values share cache entries
+        let input_params = match resp.extractor {
+            Some(extractor) => {
+                let extractor: internal_api::ExtractorDescription = extractor.into();
+                Some(validate_input_params(
+                    &extractor.input_params,
+                    input_params.unwrap_or_default(),
+                )?)
+            }
+            None => input_params,
+        };
+
         // check cache first
         let cache_key = ExtractContentCacheKey {
             content: content.clone().into(), /* TODO: maybe there's a better way where we don't
@@ -89,46 +150,9 @@ impl ExtractorRouter {
                 labels: HashMap::new(),
             },
             input_params,
+            extractor_name: Some(extractor_name.to_string()),
         };
-
-        let req = GetExtractorCoordinatesRequest {
-            extractor: extractor_name.to_string(),
-        };
-        let resp = self
-            .coordinator_client
-            .get()
-            .await?
-            .get_extractor_coordinates(req)
-            .await?
-            .into_inner();
-        let addresses = resp.addrs;
-        if addresses.is_empty() {
-            return Err(anyhow!("no extractor found"));
-        }
-        let extractor_addr = addresses[0].clone();
-        let resp = self
-            .client
-            .post(&format!("http://{}/extract", extractor_addr))
-            .json(&request)
-            .send()
-            .await
-            .map_err(|e| anyhow::anyhow!("unable to embed query: {}", e))?;
-
-        if !&resp.status().is_success() {
-            return Err(anyhow!(
-                "unable to extract query: status: {}, error: {}",
-                resp.status(),
-                resp.text().await?
-            ));
-        }
-        let response_body = resp
-            .text()
-            .await
-            .map_err(|e| anyhow!("unable to get response body: {}", e))?;
-
-        let extractor_response: internal_api::ExtractResponse =
-            serde_json::from_str(&response_body)
-                .map_err(|e| anyhow!("unable to extract response from json: {}", e))?;
+        let extractor_response = self.send_extract_request(&addresses, &request).await?;
 
         let content_list: Vec<Content> = extractor_response
             .content
@@ -158,4 +182,114 @@ impl ExtractorRouter {
         }
         Ok(content_list)
     }
+
+    /// Sends the request to the least loaded executor, and to the next ones
+    /// if it can't connect to it
+    async fn send_extract_request(
+        &self,
+        addresses: &[String],
+        request: &internal_api::ExtractRequest,
+    ) -> Result<internal_api::ExtractResponse> {
+        let candidates = {
+            let in_flight = self.in_flight.lock().unwrap();
+            candidate_order(
+                addresses,
+                &in_flight,
+                self.next.fetch_add(1, Ordering::Relaxed),
+            )
+        };
+        let mut connect_error = None;
+        for extractor_addr in candidates {
+            let _in_flight = self.start_request(&extractor_addr);
+            let resp = match self
+                .client
+                .post(&format!("http://{}/extract", extractor_addr))
+                .json(request)
+                .send()
+                .await
+            {
+                Ok(resp) => resp,
+                Err(e) if e.is_connect() => {
+                    warn!(
+                        "unable to connect to executor {}, trying the next one: {}",
+                        extractor_addr, e
+                    );
+                    connect_error = Some(e);
+                    continue;
+                }
+                Err(e) => return Err(anyhow!("unable to extract content: {}", e)),
+            };
+
+            if !&resp.status().is_success() {
+                return Err(anyhow!(
+                    "unable to extract content: status: {}, error: {}",
+                    resp.status(),
+                    resp.text().await?
+                ));
+            }
+            let response_body = resp
+                .text()
+                .await
+                .map_err(|e| anyhow!("unable to get response body: {}", e))?;
+            return serde_json::from_str(&response_body)
+                .map_err(|e| anyhow!("unable to extract response from json: {}", e));
+        }
+        Err(anyhow!(
+            "unable to connect to any executor of the extractor: {}",
+            connect_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    fn start_request(&self, addr: &str) -> InFlightRequest {
+        *self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(addr.to_string())
+            .or_default() += 1;
+        InFlightRequest {
+            in_flight: self.in_flight.clone(),
+            addr: addr.to_string(),
+        }
+    }
+}
+
+/// Orders the executors of an extractor from the least loaded one, executors
+/// with the same load are taken round-robin starting at `offset`
+fn candidate_order(
+    addresses: &[String],
+    in_flight: &HashMap<String, usize>,
+    offset: usize,
+) -> Vec<String> {
+    (0..addresses.len())
+        .map(|i| &addresses[(offset + i) % addresses.len()])
+        .sorted_by_key(|addr| in_flight.get(*addr).copied().unwrap_or_default())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::candidate_order;
+
+    #[test]
+    fn test_candidate_order() {
+        let addresses = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            vec!["b", "c", "a"],
+            candidate_order(&addresses, &HashMap::new(), 1)
+        );
+        assert_eq!(
+            vec!["a", "b", "c"],
+            candidate_order(&addresses, &HashMap::new(), 3)
+        );
+
+        let in_flight = HashMap::from([("a".to_string(), 2), ("b".to_string(), 1)]);
+        assert_eq!(
+            vec!["c", "b", "a"],
+            candidate_order(&addresses, &in_flight, 0)
+        );
+    }
 }
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+use indexify_proto::indexify_ingestion::{
+    ingestion_service_client::IngestionServiceClient,
+    ReadContentRequest,
+};
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    service::{interceptor::InterceptedService, Interceptor},
+    transport::Channel,
+    Request,
+    Status,
+};
+
+/// Adds the bearer token of the executor to every call
+#[derive(Clone)]
+pub struct TokenInterceptor {
+    authorization: Option<MetadataValue<Ascii>>,
+}
+
+impl Interceptor for TokenInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.clone());
+        }
+        Ok(request)
+    }
+}
+
+pub type IngestionGrpcClient =
+    IngestionServiceClient<InterceptedService<Channel, TokenInterceptor>>;
+
+/// Client of the gRPC ingestion endpoint of the server
+#[derive(Clone)]
+pub struct IngestionClient {
+    client: IngestionGrpcClient,
+}
+
+impl IngestionClient {
+    /// Connects lazily to the endpoint at `addr`, authenticating with
+    /// `token` when set
+    pub fn new(addr: &str, token: Option<&str>) -> Result<Self> {
+        let channel = Channel::from_shared(format!("http://{}", addr))?.connect_lazy();
+        let authorization = token
+            .map(|token| MetadataValue::try_from(format!("Bearer {}", token)))
+            .transpose()
+            .map_err(|e| anyhow!("invalid ingestion token: {}", e))?;
+        Ok(Self {
+            client: IngestionServiceClient::with_interceptor(
+                channel,
+                TokenInterceptor { authorization },
+            ),
+        })
+    }
+
+    pub fn client(&self) -> IngestionGrpcClient {
+        self.client.clone()
+    }
+
+    /// Reads the data of a content, streamed in chunks by the server
+    pub async fn read_content(&self, content_id: &str) -> Result<Vec<u8>> {
+        let mut chunks = self
+            .client()
+            .read_content(ReadContentRequest {
+                content_id: content_id.to_string(),
+            })
+            .await
+            .map_err(|e| anyhow!("unable to read content {}: {}", content_id, e.message()))?
+            .into_inner();
+        let mut data = Vec::new();
+        while let Some(response) = chunks
+            .message()
+            .await
+            .map_err(|e| anyhow!("unable to read content {}: {}", content_id, e.message()))?
+        {
+            data.extend_from_slice(&response.chunk);
+        }
+        Ok(data)
+    }
+}
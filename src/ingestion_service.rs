@@ -0,0 +1,336 @@
+use std::{net::SocketAddr, pin::Pin, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use indexify_internal_api as internal_api;
+use indexify_proto::{
+    indexify_coordinator,
+    indexify_ingestion::{
+        ingestion_service_server::{IngestionService, IngestionServiceServer},
+        write_extracted_content_request,
+        write_extracted_content_response,
+        ContentStart,
+        ContentWritten,
+        ReadContentRequest,
+        ReadContentResponse,
+        TaskHeader,
+        TaskWritten,
+        WriteExtractedContentRequest,
+        WriteExtractedContentResponse,
+    },
+};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tonic::{service::Interceptor, Request, Response, Status, Streaming};
+use tracing::{error, info};
+
+use crate::{
+    data_repository_manager::{result_content_id, DataRepositoryManager},
+    extractor::grpc_extractor,
+};
+
+type ReadContentStream = Pin<Box<dyn Stream<Item = Result<ReadContentResponse, Status>> + Send>>;
+
+type WriteExtractedContentStream =
+    Pin<Box<dyn Stream<Item = Result<WriteExtractedContentResponse, Status>> + Send>>;
+
+/// gRPC ingestion endpoint of the server, executors stream the content
+/// extracted by their tasks through it instead of posting JSON
+pub struct IngestionGrpcService {
+    repository_manager: Arc<DataRepositoryManager>,
+    token: Option<String>,
+}
+
+impl IngestionGrpcService {
+    pub fn new(repository_manager: Arc<DataRepositoryManager>, token: Option<String>) -> Self {
+        Self {
+            repository_manager,
+            token,
+        }
+    }
+
+    pub async fn run(
+        self,
+        addr: SocketAddr,
+        shutdown: impl std::future::Future<Output = ()>,
+    ) -> Result<()> {
+        info!("starting ingestion grpc server on: {}", addr);
+        let auth = TokenAuth {
+            token: self.token.clone(),
+        };
+        tonic::transport::Server::builder()
+            .add_service(IngestionServiceServer::with_interceptor(self, auth))
+            .serve_with_shutdown(addr, shutdown)
+            .await
+            .map_err(|e| anyhow!("unable to start ingestion grpc server: {}", e))
+    }
+}
+
+/// Rejects calls without the bearer token of the server, if it has one
+#[derive(Clone)]
+struct TokenAuth {
+    token: Option<String>,
+}
+
+impl Interceptor for TokenAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(token) = &self.token else {
+            return Ok(request);
+        };
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(bearer) if bearer == token => Ok(request),
+            _ => Err(Status::unauthenticated("invalid ingestion token")),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl IngestionService for IngestionGrpcService {
+    type ReadContentStream = ReadContentStream;
+    type WriteExtractedContentStream = WriteExtractedContentStream;
+
+    async fn read_content(
+        &self,
+        request: Request<ReadContentRequest>,
+    ) -> Result<Response<Self::ReadContentStream>, Status> {
+        let content_id = request.into_inner().content_id;
+        let chunks = self
+            .repository_manager
+            .read_content_stream(&content_id)
+            .await
+            .map_err(|e| Status::not_found(e.to_string()))?;
+        let responses = chunks.map(|chunk| {
+            chunk
+                .map(|chunk| ReadContentResponse { chunk })
+                .map_err(|e| Status::unavailable(e.to_string()))
+        });
+        Ok(Response::new(Box::pin(responses)))
+    }
+
+    async fn write_extracted_content(
+        &self,
+        request: Request<Streaming<WriteExtractedContentRequest>>,
+    ) -> Result<Response<Self::WriteExtractedContentStream>, Status> {
+        let repository_manager = self.repository_manager.clone();
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::channel(4);
+        tokio::spawn(async move {
+            let result = write_task(repository_manager, &mut requests, tx.clone()).await;
+            if let Err(status) = result {
+                error!("unable to write extracted content: {}", status.message());
+                let _ = tx.send(Err(status)).await;
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+async fn next_message(
+    requests: &mut Streaming<WriteExtractedContentRequest>,
+) -> Result<Option<write_extracted_content_request::Message>, Status> {
+    match requests.next().await {
+        Some(request) => request?
+            .message
+            .map(Some)
+            .ok_or_else(|| Status::invalid_argument("empty message")),
+        None => Ok(None),
+    }
+}
+
+async fn write_task(
+    repository_manager: Arc<DataRepositoryManager>,
+    requests: &mut Streaming<WriteExtractedContentRequest>,
+    tx: mpsc::Sender<Result<WriteExtractedContentResponse, Status>>,
+) -> Result<(), Status> {
+    let Some(write_extracted_content_request::Message::Task(task)) = next_message(requests).await?
+    else {
+        return Err(Status::invalid_argument(
+            "the stream has to start with the task header",
+        ));
+    };
+    let outcome: internal_api::TaskOutcome =
+        indexify_coordinator::TaskOutcome::try_from(task.outcome)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?
+            .into();
+    if !task.idempotency_key.is_empty() &&
+        repository_manager.is_result_written(&task.idempotency_key)
+    {
+        info!("ignoring already written result {}", task.idempotency_key);
+        return send_response(
+            &tx,
+            write_extracted_content_response::Message::Task(TaskWritten {}),
+        )
+        .await;
+    }
+
+    let mut content_list = vec![];
+    while let Some(message) = next_message(requests).await? {
+        let write_extracted_content_request::Message::ContentStart(content) = message else {
+            return Err(Status::invalid_argument("expected the start of a content"));
+        };
+        // results without an idempotency key keep ids derived from their data
+        let id = Some(&task.idempotency_key)
+            .filter(|key| !key.is_empty())
+            .map(|key| result_content_id(key, content_list.len()));
+        let content_metadata =
+            write_content(repository_manager.clone(), &task, id, content, requests).await?;
+        send_response(
+            &tx,
+            write_extracted_content_response::Message::Content(ContentWritten {
+                index: content_list.len() as u64,
+                content_id: content_metadata.id.clone(),
+            }),
+        )
+        .await?;
+        content_list.push(content_metadata);
+    }
+
+    repository_manager
+        .update_task(
+            &task.executor_id,
+            &task.task_id,
+            outcome,
+            content_list,
+            Some(task.error_message).filter(|e| !e.is_empty()),
+            Some(task.logs).filter(|logs| !logs.is_empty()),
+        )
+        .await
+        .map_err(|e| Status::unavailable(e.to_string()))?;
+    if !task.idempotency_key.is_empty() {
+        repository_manager
+            .mark_result_written(task.idempotency_key)
+            .await;
+    }
+    send_response(
+        &tx,
+        write_extracted_content_response::Message::Task(TaskWritten {}),
+    )
+    .await
+}
+
+/// Writes the data of a content to the blob store as its chunks arrive,
+/// then adds its features to their indexes
+async fn write_content(
+    repository_manager: Arc<DataRepositoryManager>,
+    task: &TaskHeader,
+    id: Option<String>,
+    content: ContentStart,
+    requests: &mut Streaming<WriteExtractedContentRequest>,
+) -> Result<indexify_coordinator::ContentMetadata, Status> {
+    let mut features = vec![];
+    for feature in content.features {
+        let feature = grpc_extractor::feature_from_proto(feature)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let index_table_name = task
+            .index_table_names
+            .get(&feature.name)
+            .ok_or_else(|| {
+                Status::invalid_argument(format!("no index for feature {}", feature.name))
+            })?
+            .clone();
+        features.push((index_table_name, feature));
+    }
+
+    let (chunk_tx, chunk_rx) = mpsc::channel(4);
+    let blob_write = {
+        let repository_manager = repository_manager.clone();
+        let repository = task.repository.clone();
+        let parent_id = task.parent_content_id.clone();
+        let source = task.extractor_binding.clone();
+        tokio::spawn(async move {
+            repository_manager
+                .write_content_stream(
+                    &repository,
+                    id,
+                    content.mime,
+                    content.labels,
+                    &parent_id,
+                    &source,
+                    Box::pin(ReceiverStream::new(chunk_rx)),
+                )
+                .await
+        })
+    };
+    loop {
+        let message = next_message(requests).await;
+        let err = match message {
+            Ok(Some(write_extracted_content_request::Message::Chunk(chunk))) => {
+                // the blob write failed if it stopped receiving chunks, its error
+                // is returned below
+                if chunk_tx.send(Ok(chunk)).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Ok(Some(write_extracted_content_request::Message::ContentEnd(_))) => break,
+            Ok(_) => Status::invalid_argument("content ended without a content end"),
+            Err(status) => status,
+        };
+        let _ = chunk_tx.send(Err(anyhow!("{}", err.message()))).await;
+        return Err(err);
+    }
+    drop(chunk_tx);
+    let content_metadata = blob_write
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::unavailable(e.to_string()))?;
+
+    for (index_table_name, feature) in features {
+        repository_manager
+            .add_extracted_feature(
+                &task.repository,
+                &index_table_name,
+                &content_metadata,
+                feature.into(),
+            )
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+    }
+    Ok(content_metadata)
+}
+
+async fn send_response(
+    tx: &mpsc::Sender<Result<WriteExtractedContentResponse, Status>>,
+    message: write_extracted_content_response::Message,
+) -> Result<(), Status> {
+    tx.send(Ok(WriteExtractedContentResponse {
+        message: Some(message),
+    }))
+    .await
+    .map_err(|_| Status::cancelled("the executor closed the stream"))
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::{metadata::MetadataValue, service::Interceptor, Request};
+
+    use super::TokenAuth;
+
+    fn request(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            request.metadata_mut().insert(
+                "authorization",
+                MetadataValue::try_from(authorization).unwrap(),
+            );
+        }
+        request
+    }
+
+    #[test]
+    fn test_token_auth() {
+        let mut auth = TokenAuth {
+            token: Some("secret".to_string()),
+        };
+        assert!(auth.call(request(Some("Bearer secret"))).is_ok());
+        assert!(auth.call(request(Some("Bearer other"))).is_err());
+        assert!(auth.call(request(Some("secret"))).is_err());
+        assert!(auth.call(request(None)).is_err());
+
+        let mut auth = TokenAuth { token: None };
+        assert!(auth.call(request(None)).is_ok());
+    }
+}
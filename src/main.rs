@@ -24,11 +24,18 @@ mod coordinator;
 mod coordinator_client;
 mod data_repository_manager;
 mod executor;
+mod extract_jobs;
 mod extractor_router;
 mod grpc_helper;
+mod ingestion_client;
+mod ingestion_service;
 mod metadata_index;
+mod metrics;
+mod records;
+mod result_uploader;
 mod task_store;
 mod test_util;
+mod text_splitters;
 mod tls;
 mod tonic_streamer;
 mod utils;
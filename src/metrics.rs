@@ -0,0 +1,117 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use opentelemetry::{
+    global,
+    metrics::{ObservableGauge, Unit},
+    KeyValue,
+};
+use tracing::error;
+
+use crate::{api, data_repository_manager::DataRepositoryManager};
+
+const BINDING_STATS_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Exports the processing stats of all extractor bindings as OpenTelemetry
+/// gauges. The coordinator is polled periodically because gauge callbacks
+/// can't be async.
+pub struct BindingStatsMetrics {
+    stats: Arc<RwLock<Vec<api::ExtractorBindingStats>>>,
+    _content_matched: ObservableGauge<u64>,
+    _tasks: ObservableGauge<u64>,
+    _percent_complete: ObservableGauge<f64>,
+    _task_latency: ObservableGauge<f64>,
+}
+
+fn binding_attributes(stats: &api::ExtractorBindingStats) -> Vec<KeyValue> {
+    vec![
+        KeyValue::new("repository", stats.repository.clone()),
+        KeyValue::new("extractor_binding", stats.extractor_binding.clone()),
+    ]
+}
+
+impl BindingStatsMetrics {
+    pub fn new() -> Self {
+        let meter = global::meter("indexify");
+        let stats: Arc<RwLock<Vec<api::ExtractorBindingStats>>> = Arc::new(RwLock::new(vec![]));
+
+        let content_stats = stats.clone();
+        let content_matched = meter
+            .u64_observable_gauge("indexify.binding.content_matched")
+            .with_description("Content matched by the extractor binding")
+            .with_callback(move |observer| {
+                for s in content_stats.read().unwrap().iter() {
+                    observer.observe(s.content_matched, &binding_attributes(s));
+                }
+            })
+            .init();
+
+        let task_stats = stats.clone();
+        let tasks = meter
+            .u64_observable_gauge("indexify.binding.tasks")
+            .with_description("Tasks of the extractor binding by state")
+            .with_callback(move |observer| {
+                for s in task_stats.read().unwrap().iter() {
+                    for (state, count) in [
+                        ("pending", s.tasks_pending),
+                        ("assigned", s.tasks_assigned),
+                        ("succeeded", s.tasks_succeeded),
+                        ("failed", s.tasks_failed),
+                        ("cancelled", s.tasks_cancelled),
+                    ] {
+                        let mut attributes = binding_attributes(s);
+                        attributes.push(KeyValue::new("state", state));
+                        observer.observe(count, &attributes);
+                    }
+                }
+            })
+            .init();
+
+        let progress_stats = stats.clone();
+        let percent_complete = meter
+            .f64_observable_gauge("indexify.binding.percent_complete")
+            .with_description("Percentage of matched content processed by the extractor binding")
+            .with_unit(Unit::new("%"))
+            .with_callback(move |observer| {
+                for s in progress_stats.read().unwrap().iter() {
+                    observer.observe(s.percent_complete, &binding_attributes(s));
+                }
+            })
+            .init();
+
+        let latency_stats = stats.clone();
+        let task_latency = meter
+            .f64_observable_gauge("indexify.binding.task_latency")
+            .with_description("Average time from assignment to completion of tasks")
+            .with_unit(Unit::new("s"))
+            .with_callback(move |observer| {
+                for s in latency_stats.read().unwrap().iter() {
+                    observer.observe(s.average_latency_secs, &binding_attributes(s));
+                }
+            })
+            .init();
+
+        Self {
+            stats,
+            _content_matched: content_matched,
+            _tasks: tasks,
+            _percent_complete: percent_complete,
+            _task_latency: task_latency,
+        }
+    }
+
+    pub fn start(self: Arc<Self>, repository_manager: Arc<DataRepositoryManager>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BINDING_STATS_REFRESH_INTERVAL);
+            loop {
+                interval.tick().await;
+                match repository_manager.list_binding_stats("").await {
+                    Ok(stats) => *self.stats.write().unwrap() = stats,
+                    Err(err) => error!("unable to refresh binding stats: {}", err),
+                }
+            }
+        });
+    }
+}
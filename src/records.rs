@@ -0,0 +1,276 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use crate::api::{Content, RecordFormat, RecordMapping};
+
+type Record = Map<String, Value>;
+
+/// Turns the records of a structured file into content, one per record
+pub fn records_content(
+    data: &[u8],
+    file_name: &str,
+    mapping: &RecordMapping,
+) -> Result<Vec<Content>> {
+    let format = mapping
+        .format
+        .or_else(|| RecordFormat::from_file_name(file_name))
+        .ok_or_else(|| {
+            anyhow!(
+                "unable to guess the format of {}, set the format of the mapping",
+                file_name
+            )
+        })?;
+    let text = std::str::from_utf8(data).map_err(|e| anyhow!("file is not valid utf-8: {}", e))?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let records = match format {
+        RecordFormat::Json => json_records(text)?,
+        RecordFormat::Jsonl => jsonl_records(text)?,
+        RecordFormat::Csv => csv_records(text)?,
+    };
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            record_content(record, mapping).map_err(|e| anyhow!("record {}: {}", i, e))
+        })
+        .collect()
+}
+
+fn record_content(record: Record, mapping: &RecordMapping) -> Result<Content> {
+    let labels = mapping
+        .label_fields
+        .iter()
+        .filter_map(|field| Some((field.clone(), label_value(record.get(field)?)?)))
+        .collect();
+    let (content_type, bytes) = match &mapping.text_field {
+        Some(field) => {
+            let text = record
+                .get(field)
+                .ok_or_else(|| anyhow!("missing text field {}", field))?;
+            let text = match text {
+                Value::String(text) => text.clone(),
+                text => text.to_string(),
+            };
+            (mime::TEXT_PLAIN.to_string(), text.into_bytes())
+        }
+        None => (
+            mime::APPLICATION_JSON.to_string(),
+            serde_json::to_vec(&record)?,
+        ),
+    };
+    Ok(Content {
+        content_type,
+        bytes,
+        features: vec![],
+        labels,
+    })
+}
+
+/// Labels are strings, null values aren't copied
+fn label_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn as_record(value: Value) -> Result<Record> {
+    match value {
+        Value::Object(record) => Ok(record),
+        value => Err(anyhow!("records must be objects, found {}", value)),
+    }
+}
+
+fn json_records(text: &str) -> Result<Vec<Record>> {
+    let Value::Array(values) = serde_json::from_str(text)? else {
+        return Err(anyhow!("a json file must contain an array of records"));
+    };
+    values.into_iter().map(as_record).collect()
+}
+
+fn jsonl_records(text: &str) -> Result<Vec<Record>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(anyhow::Error::from)
+                .and_then(as_record)
+                .map_err(|e| anyhow!("line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// Records of a csv file, keyed by the columns of its header row
+fn csv_records(text: &str) -> Result<Vec<Record>> {
+    let mut rows = csv_rows(text)?.into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(vec![]);
+    };
+    rows.enumerate()
+        .map(|(i, row)| {
+            if row.len() != header.len() {
+                return Err(anyhow!(
+                    "row {} has {} fields, the header has {}",
+                    i + 1,
+                    row.len(),
+                    header.len()
+                ));
+            }
+            Ok(header
+                .iter()
+                .cloned()
+                .zip(row.into_iter().map(Value::String))
+                .collect())
+        })
+        .collect()
+}
+
+/// Splits csv text into rows of fields. Fields may be quoted, quoted fields
+/// can contain commas, line breaks and quotes escaped by doubling them. A
+/// quote further in an unquoted field is kept as is, e.g. `12" pipe`.
+fn csv_rows(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut field_start = true;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field_start => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+        field_start = matches!(c, ',' | '\n');
+    }
+    if quoted {
+        return Err(anyhow!("unterminated quoted field"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    // blank lines don't hold records
+    rows.retain(|row| row.len() > 1 || !row[0].is_empty());
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn mapping(text_field: Option<&str>, label_fields: &[&str]) -> RecordMapping {
+        RecordMapping {
+            format: None,
+            text_field: text_field.map(|f| f.to_string()),
+            label_fields: label_fields.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let rows = csv_rows("a,b\r\n\"x, \"\"y\"\"\",\"multi\nline\"\n\n1,\n").unwrap();
+        assert_eq!(
+            vec![
+                vec!["a", "b"],
+                vec!["x, \"y\"", "multi\nline"],
+                vec!["1", ""],
+            ],
+            rows
+        );
+        assert!(csv_rows("a,\"b\n").is_err());
+
+        // quotes within unquoted fields are literal
+        let rows = csv_rows("name,size\n12\" pipe,3\"\nvalve,1\n").unwrap();
+        assert_eq!(
+            vec![
+                vec!["name", "size"],
+                vec!["12\" pipe", "3\""],
+                vec!["valve", "1"],
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn test_records_content() {
+        let csv = "\u{feff}sku,category,description\n1,books,\"A book, used\"\n2,toys,A ball\n";
+        let content = records_content(
+            csv.as_bytes(),
+            "catalog.csv",
+            &mapping(Some("description"), &["sku", "category"]),
+        )
+        .unwrap();
+        assert_eq!(2, content.len());
+        assert_eq!(b"A book, used".to_vec(), content[0].bytes);
+        assert_eq!("text/plain", content[0].content_type);
+        assert_eq!(
+            HashMap::from([
+                ("sku".to_string(), "1".to_string()),
+                ("category".to_string(), "books".to_string()),
+            ]),
+            content[0].labels
+        );
+
+        // records without a text field are stored as json
+        let jsonl = "{\"id\": 7, \"body\": \"help\", \"tag\": null}\n\n{\"id\": 8}\n";
+        let content = records_content(
+            jsonl.as_bytes(),
+            "tickets.jsonl",
+            &mapping(None, &["id", "tag"]),
+        )
+        .unwrap();
+        assert_eq!("application/json", content[0].content_type);
+        assert_eq!(
+            serde_json::json!({"id": 7, "body": "help", "tag": null}),
+            serde_json::from_slice::<Value>(&content[0].bytes).unwrap()
+        );
+        assert_eq!(
+            HashMap::from([("id".to_string(), "7".to_string())]),
+            content[0].labels
+        );
+
+        let json = "[{\"body\": \"a\"}, {\"body\": 1}]";
+        let content = records_content(
+            json.as_bytes(),
+            "export",
+            &RecordMapping {
+                format: Some(RecordFormat::Json),
+                ..mapping(Some("body"), &[])
+            },
+        )
+        .unwrap();
+        assert_eq!(b"1".to_vec(), content[1].bytes);
+
+        let invalid = [
+            ("[1, 2]", "a.json", mapping(None, &[])),
+            ("{\"body\": \"a\"}", "a.json", mapping(None, &[])),
+            ("{\"other\": \"a\"}", "a.jsonl", mapping(Some("body"), &[])),
+            ("a,b\n1\n", "a.csv", mapping(None, &[])),
+            ("a,b\n1,2\n", "a.txt", mapping(None, &[])),
+        ];
+        for (data, file_name, mapping) in invalid {
+            assert!(records_content(data.as_bytes(), file_name, &mapping).is_err());
+        }
+    }
+}
@@ -0,0 +1,584 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use bytes::Bytes;
+use indexify_internal_api as internal_api;
+use indexify_proto::{
+    indexify_coordinator::{self, UpdateTaskRequest},
+    indexify_ingestion::{
+        write_extracted_content_request::Message,
+        write_extracted_content_response,
+        ContentEnd,
+        ContentStart,
+        TaskHeader,
+        WriteExtractedContentRequest,
+    },
+};
+use tracing::{error, warn};
+
+use crate::{
+    api::WriteExtractedContent,
+    coordinator_client::CoordinatorClient,
+    extractor::grpc_extractor::feature_to_proto,
+    ingestion_client::{IngestionClient, IngestionGrpcClient},
+    server_config::UploadConfig,
+};
+
+/// Error of a single attempt to write a result
+enum WriteError {
+    /// Sending the write again may succeed
+    Transient(anyhow::Error),
+    Permanent(anyhow::Error),
+}
+
+impl From<tonic::Status> for WriteError {
+    fn from(status: tonic::Status) -> Self {
+        let err = anyhow!("ingestion server returned {}", status);
+        match status.code() {
+            tonic::Code::InvalidArgument => WriteError::Permanent(err),
+            _ => WriteError::Transient(err),
+        }
+    }
+}
+
+/// Writes task results to the ingestion server, retrying failed writes with
+/// exponential backoff. A result that can't be written is reported to the
+/// coordinator as a failed task.
+pub struct ResultUploader {
+    config: UploadConfig,
+    client: reqwest::Client,
+    ingestion_api: String,
+    /// Streams results over gRPC instead of posting them as JSON when set
+    ingestion_client: Option<IngestionClient>,
+    coordinator_client: Arc<CoordinatorClient>,
+}
+
+impl ResultUploader {
+    pub fn new(
+        config: UploadConfig,
+        ingestion_addr: &str,
+        ingestion_client: Option<IngestionClient>,
+        coordinator_client: Arc<CoordinatorClient>,
+    ) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            ingestion_api: format!("http://{}/write_content", ingestion_addr),
+            ingestion_client,
+            coordinator_client,
+        }
+    }
+
+    /// Writes the result of a task. Returns an error only when neither the
+    /// result nor the failure to write it could be reported, the result
+    /// has to be uploaded again later.
+    pub async fn upload(
+        &self,
+        executor_id: &str,
+        task: &internal_api::Task,
+        task_result: internal_api::TaskResult,
+    ) -> Result<()> {
+        let result = self.write(executor_id, task, task_result, &task.id).await;
+        if let Err(err) = result {
+            error!(
+                "giving up writing extracted content of task {}: {}",
+                task.id, err
+            );
+            return self.report_failure(executor_id, task, err).await;
+        }
+        Ok(())
+    }
+
+    /// Writes the `part`-th batch of content streamed by a running task,
+    /// the task stays assigned to the executor until its result is
+    /// uploaded
+    pub async fn upload_partial(
+        &self,
+        executor_id: &str,
+        task: &internal_api::Task,
+        part: usize,
+        content_list: Vec<internal_api::Content>,
+    ) -> Result<()> {
+        let task_result = internal_api::TaskResult::partial(&task.id, content_list);
+        let idempotency_key = format!("{}/partial/{}", task.id, part);
+        self.write(executor_id, task, task_result, &idempotency_key)
+            .await
+    }
+
+    async fn write(
+        &self,
+        executor_id: &str,
+        task: &internal_api::Task,
+        task_result: internal_api::TaskResult,
+        idempotency_key: &str,
+    ) -> Result<()> {
+        match &self.ingestion_client {
+            Some(client) => {
+                self.stream(client, executor_id, task, task_result, idempotency_key)
+                    .await
+            }
+            None => {
+                self.post(executor_id, task, &task_result, idempotency_key)
+                    .await
+            }
+        }
+    }
+
+    async fn post(
+        &self,
+        executor_id: &str,
+        task: &internal_api::Task,
+        task_result: &internal_api::TaskResult,
+        idempotency_key: &str,
+    ) -> Result<()> {
+        let requests = write_requests(
+            executor_id,
+            task,
+            task_result,
+            idempotency_key,
+            self.config.max_request_bytes,
+        )?;
+        for req in &requests {
+            self.retry(&task.id, || self.post_once(req)).await?;
+        }
+        Ok(())
+    }
+
+    async fn post_once(&self, req: &WriteExtractedContent) -> Result<(), WriteError> {
+        let resp = self
+            .client
+            .post(&self.ingestion_api)
+            .json(req)
+            .send()
+            .await
+            .map_err(|e| WriteError::Transient(e.into()))?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = resp.text().await.unwrap_or_default();
+        let err = anyhow!("ingestion server returned {}: {}", status, body);
+        // the request itself is invalid, sending it again won't help
+        if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(WriteError::Permanent(err));
+        }
+        Err(WriteError::Transient(err))
+    }
+
+    async fn stream(
+        &self,
+        client: &IngestionClient,
+        executor_id: &str,
+        task: &internal_api::Task,
+        task_result: internal_api::TaskResult,
+        idempotency_key: &str,
+    ) -> Result<()> {
+        let result_stream = ResultStream::new(
+            executor_id,
+            task,
+            task_result,
+            idempotency_key,
+            self.config.chunk_bytes,
+        )?;
+        self.retry(&task.id, || {
+            Self::stream_once(client.client(), result_stream.requests())
+        })
+        .await
+    }
+
+    async fn stream_once(
+        mut client: IngestionGrpcClient,
+        requests: impl Iterator<Item = WriteExtractedContentRequest> + Send + 'static,
+    ) -> Result<(), WriteError> {
+        let mut responses = client
+            .write_extracted_content(futures::stream::iter(requests))
+            .await?
+            .into_inner();
+        while let Some(response) = responses.message().await? {
+            if let Some(write_extracted_content_response::Message::Task(_)) = response.message {
+                return Ok(());
+            }
+        }
+        Err(WriteError::Transient(anyhow!(
+            "the ingestion server closed the stream before the task was written"
+        )))
+    }
+
+    async fn retry<F, Fut>(&self, task_id: &str, mut write: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<(), WriteError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let err = match write().await {
+                Ok(()) => return Ok(()),
+                Err(WriteError::Permanent(err)) => return Err(err),
+                Err(WriteError::Transient(err)) => err,
+            };
+            if attempt >= self.config.max_retries {
+                return Err(err);
+            }
+            let delay = backoff(&self.config, attempt);
+            warn!(
+                "unable to write extracted content of task {}, retrying in {:?}: {}",
+                task_id, delay, err
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn report_failure(
+        &self,
+        executor_id: &str,
+        task: &internal_api::Task,
+        err: anyhow::Error,
+    ) -> Result<()> {
+        let req = UpdateTaskRequest {
+            executor_id: executor_id.to_string(),
+            task_id: task.id.clone(),
+            outcome: indexify_coordinator::TaskOutcome::Failed as i32,
+            content_list: vec![],
+            error_message: format!("unable to write extracted content: {}", err),
+            logs_url: String::new(),
+        };
+        self.coordinator_client
+            .get()
+            .await?
+            .update_task(req)
+            .await
+            .map_err(|e| anyhow!("unable to report failed task {}: {}", task.id, e.message()))?;
+        Ok(())
+    }
+}
+
+/// Delay before retrying a write for the `attempt`-th time
+fn backoff(config: &UploadConfig, attempt: u32) -> Duration {
+    let delay = config
+        .initial_backoff_ms
+        .saturating_mul(1 << attempt.min(32))
+        .min(config.max_backoff_ms);
+    Duration::from_millis(delay)
+}
+
+/// Builds the requests writing a task result, each at most
+/// `max_request_bytes` large unless a single content is larger. Every content
+/// is sent once with all its features, the server writes them to the indexes
+/// of the binding. Only the last request carries the outcome of the task, so
+/// that the task finishes once all its content is written.
+fn write_requests(
+    executor_id: &str,
+    task: &internal_api::Task,
+    task_result: &internal_api::TaskResult,
+    idempotency_key: &str,
+    max_request_bytes: usize,
+) -> Result<Vec<WriteExtractedContent>> {
+    for content in &task_result.extracted_content {
+        for feature in &content.features {
+            if !task.output_index_table_mapping.contains_key(&feature.name) {
+                return Err(anyhow!("no index for feature {}", feature.name));
+            }
+        }
+    }
+    let parts =
+        split_content_list_by_size(task_result.extracted_content.clone(), max_request_bytes);
+    let mut requests: Vec<WriteExtractedContent> = parts
+        .into_iter()
+        .enumerate()
+        .map(|(part, content_list)| WriteExtractedContent {
+            parent_content_id: task.content_metadata.id.clone(),
+            task_id: task.id.clone(),
+            repository: task.repository.clone(),
+            content_list,
+            index_table_name: None,
+            index_table_names: task.output_index_table_mapping.clone(),
+            executor_id: executor_id.to_string(),
+            task_outcome: internal_api::TaskOutcome::Unknown,
+            extractor_binding: task.extractor_binding.clone(),
+            error_message: None,
+            idempotency_key: Some(format!("{}/{}", idempotency_key, part)),
+            logs: None,
+        })
+        .collect();
+    if let Some(last) = requests.last_mut() {
+        last.task_outcome = task_result.outcome.clone();
+        last.error_message = task_result.error_msg.clone();
+        last.logs = Some(task_result.logs.clone()).filter(|logs| !logs.is_empty());
+    }
+    Ok(requests)
+}
+
+/// A task result prepared for streaming: the task header, then the start,
+/// the data in chunks of at most `chunk_bytes` and the end of every content.
+/// The messages are produced as they are sent, every attempt slices the same
+/// data.
+struct ResultStream {
+    header: TaskHeader,
+    content: Arc<Vec<(ContentStart, Bytes)>>,
+    chunk_bytes: usize,
+}
+
+impl ResultStream {
+    fn new(
+        executor_id: &str,
+        task: &internal_api::Task,
+        task_result: internal_api::TaskResult,
+        idempotency_key: &str,
+        chunk_bytes: usize,
+    ) -> Result<Self> {
+        let header = TaskHeader {
+            task_id: task.id.clone(),
+            repository: task.repository.clone(),
+            parent_content_id: task.content_metadata.id.clone(),
+            executor_id: executor_id.to_string(),
+            extractor_binding: task.extractor_binding.clone(),
+            outcome: indexify_coordinator::TaskOutcome::from(task_result.outcome) as i32,
+            error_message: task_result.error_msg.unwrap_or_default(),
+            index_table_names: task.output_index_table_mapping.clone(),
+            idempotency_key: idempotency_key.to_string(),
+            logs: task_result.logs,
+        };
+        let mut content_list = vec![];
+        for content in task_result.extracted_content {
+            for feature in &content.features {
+                if !task.output_index_table_mapping.contains_key(&feature.name) {
+                    return Err(anyhow!("no index for feature {}", feature.name));
+                }
+            }
+            let start = ContentStart {
+                mime: content.mime,
+                features: content.features.into_iter().map(feature_to_proto).collect(),
+                labels: content.labels,
+            };
+            content_list.push((start, Bytes::from(content.bytes)));
+        }
+        Ok(Self {
+            header,
+            content: Arc::new(content_list),
+            chunk_bytes: chunk_bytes.max(1),
+        })
+    }
+
+    /// Messages of one attempt to write the result
+    fn requests(&self) -> impl Iterator<Item = WriteExtractedContentRequest> + Send + 'static {
+        let content = self.content.clone();
+        let chunk_bytes = self.chunk_bytes;
+        let body = (0..content.len()).flat_map(move |i| {
+            let (start, data) = content[i].clone();
+            let chunks = (0..data.len()).step_by(chunk_bytes).map(move |offset| {
+                Message::Chunk(data.slice(offset..data.len().min(offset + chunk_bytes)))
+            });
+            std::iter::once(Message::ContentStart(start))
+                .chain(chunks)
+                .chain(std::iter::once(Message::ContentEnd(ContentEnd {})))
+        });
+        std::iter::once(Message::Task(self.header.clone()))
+            .chain(body)
+            .map(|message| WriteExtractedContentRequest {
+                message: Some(message),
+            })
+    }
+}
+
+/// Splits a content list into consecutive parts whose serialized size is at
+/// most `max_bytes`, always returning at least one part
+fn split_content_list_by_size(
+    content_list: Vec<internal_api::Content>,
+    max_bytes: usize,
+) -> Vec<Vec<internal_api::Content>> {
+    let mut parts = vec![vec![]];
+    let mut part_bytes = 0;
+    for content in content_list {
+        let content_bytes = serde_json::to_vec(&content)
+            .map(|json| json.len())
+            .unwrap_or(content.bytes.len());
+        let part = parts.last_mut().unwrap();
+        if !part.is_empty() && part_bytes + content_bytes > max_bytes {
+            parts.push(vec![content]);
+            part_bytes = content_bytes;
+        } else {
+            part.push(content);
+            part_bytes += content_bytes;
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use indexify_internal_api as internal_api;
+
+    use super::*;
+    use crate::state::store::SledStorableTestFactory;
+
+    fn text(text: &str) -> internal_api::Content {
+        internal_api::Content {
+            mime: "text/plain".to_string(),
+            bytes: text.as_bytes().to_vec(),
+            features: vec![],
+            labels: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_split_content_list_by_size() {
+        let content_size = serde_json::to_vec(&text("aaaa")).unwrap().len();
+        let content_list = vec![text("aaaa"), text("bbbb"), text("cccc")];
+
+        let parts = split_content_list_by_size(content_list.clone(), 2 * content_size);
+        assert_eq!(2, parts.len());
+        assert_eq!(2, parts[0].len());
+        assert_eq!(1, parts[1].len());
+
+        // content larger than the limit is still written on its own
+        let parts = split_content_list_by_size(content_list, 1);
+        assert_eq!(3, parts.len());
+
+        let parts = split_content_list_by_size(vec![], 1);
+        assert_eq!(1, parts.len());
+        assert!(parts[0].is_empty());
+    }
+
+    #[test]
+    fn test_write_requests() {
+        let task = internal_api::Task::spawn_instance_for_store_test();
+        let task_result = internal_api::TaskResult::success(
+            &task.id,
+            vec![text("aaaa"), text("bbbb"), text("cccc")],
+        );
+        let content_size = serde_json::to_vec(&text("aaaa")).unwrap().len();
+        let requests =
+            write_requests("executor", &task, &task_result, &task.id, content_size).unwrap();
+        let keys: Vec<_> = requests
+            .iter()
+            .map(|req| req.idempotency_key.clone().unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                format!("{}/0", task.id),
+                format!("{}/1", task.id),
+                format!("{}/2", task.id)
+            ],
+            keys
+        );
+        // the task only finishes with the last part
+        let outcomes: Vec<_> = requests
+            .iter()
+            .map(|req| req.task_outcome.clone())
+            .collect();
+        assert_eq!(
+            vec![
+                internal_api::TaskOutcome::Unknown,
+                internal_api::TaskOutcome::Unknown,
+                internal_api::TaskOutcome::Success
+            ],
+            outcomes
+        );
+
+        let task_result = internal_api::TaskResult::failed(&task.id, None);
+        let requests =
+            write_requests("executor", &task, &task_result, &task.id, content_size).unwrap();
+        assert_eq!(1, requests.len());
+        assert!(requests[0].content_list.is_empty());
+        assert_eq!(internal_api::TaskOutcome::Failed, requests[0].task_outcome);
+
+        // the parts of a streamed result have their own keys
+        let task_result = internal_api::TaskResult::partial(&task.id, vec![text("aaaa")]);
+        let key = format!("{}/partial/3", task.id);
+        let requests = write_requests("executor", &task, &task_result, &key, content_size).unwrap();
+        assert_eq!(
+            Some(format!("{}/0", key)),
+            requests[0].idempotency_key.clone()
+        );
+        assert_eq!(internal_api::TaskOutcome::Unknown, requests[0].task_outcome);
+    }
+
+    #[test]
+    fn test_write_requests_multiple_indexes() {
+        let task = internal_api::Task {
+            output_index_table_mapping: HashMap::from([
+                ("embedding".to_string(), "repo.embeddings".to_string()),
+                ("metadata".to_string(), "repo.metadata".to_string()),
+            ]),
+            ..internal_api::Task::spawn_instance_for_store_test()
+        };
+        let feature = |feature_type, name: &str| internal_api::Feature {
+            feature_type,
+            name: name.to_string(),
+            data: serde_json::json!({}),
+        };
+        let content = internal_api::Content {
+            features: vec![
+                feature(internal_api::FeatureType::Embedding, "embedding"),
+                feature(internal_api::FeatureType::Metadata, "metadata"),
+            ],
+            ..text("aaaa")
+        };
+        let task_result = internal_api::TaskResult::success(&task.id, vec![content]);
+        let requests =
+            write_requests("executor", &task, &task_result, &task.id, usize::MAX).unwrap();
+        // the content is written once, with both its features
+        assert_eq!(1, requests.len());
+        assert_eq!(1, requests[0].content_list.len());
+        assert_eq!(2, requests[0].content_list[0].features.len());
+        assert_eq!(
+            task.output_index_table_mapping,
+            requests[0].index_table_names
+        );
+
+        let content = internal_api::Content {
+            features: vec![feature(internal_api::FeatureType::Metadata, "unknown")],
+            ..text("aaaa")
+        };
+        let task_result = internal_api::TaskResult::success(&task.id, vec![content]);
+        assert!(write_requests("executor", &task, &task_result, &task.id, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_result_stream() {
+        let task = internal_api::Task::spawn_instance_for_store_test();
+        let task_result =
+            internal_api::TaskResult::success(&task.id, vec![text("hello world"), text("")]);
+        let result_stream = ResultStream::new("executor", &task, task_result, &task.id, 4).unwrap();
+        let messages: Vec<_> = result_stream
+            .requests()
+            .map(|r| r.message.unwrap())
+            .collect();
+        assert_eq!(8, messages.len());
+        assert!(matches!(&messages[0], Message::Task(header) if header.task_id == task.id));
+        assert!(matches!(&messages[1], Message::ContentStart(_)));
+        let chunks: Vec<_> = messages[2..5]
+            .iter()
+            .map(|message| match message {
+                Message::Chunk(chunk) => chunk.to_vec(),
+                _ => panic!("expected a chunk, got {:?}", message),
+            })
+            .collect();
+        assert_eq!(
+            vec![b"hell".to_vec(), b"o wo".to_vec(), b"rld".to_vec()],
+            chunks
+        );
+        assert!(matches!(&messages[5], Message::ContentEnd(_)));
+        // empty content has no chunks
+        assert!(matches!(&messages[6], Message::ContentStart(_)));
+        assert!(matches!(&messages[7], Message::ContentEnd(_)));
+        // every attempt sends the same messages
+        assert_eq!(8, result_stream.requests().count());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = UploadConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            ..Default::default()
+        };
+        assert_eq!(Duration::from_millis(100), backoff(&config, 0));
+        assert_eq!(Duration::from_millis(400), backoff(&config, 2));
+        assert_eq!(Duration::from_millis(1000), backoff(&config, 10));
+        assert_eq!(Duration::from_millis(1000), backoff(&config, 100));
+    }
+}
@@ -3,9 +3,9 @@ use std::{net::SocketAddr, sync::Arc};
 use anyhow::{anyhow, Result};
 use axum::{
     extract::{DefaultBodyLimit, Multipart, Path, Query, Request, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::IntoResponse,
     routing::{get, post},
-    Extension,
     Json,
     Router,
 };
@@ -31,11 +31,16 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     api::{self, *},
+    api_utils::InputParamsError,
     caching::caches_extension::Caches,
     coordinator_client::CoordinatorClient,
     data_repository_manager::DataRepositoryManager,
+    extract_jobs::ExtractJobs,
     extractor_router::ExtractorRouter,
+    ingestion_service::IngestionGrpcService,
     metadata_index::MetadataIndexManager,
+    metrics::BindingStatsMetrics,
+    records,
     server_config::ServerConfig,
     tls::build_mtls_acceptor,
     vector_index::VectorIndexManager,
@@ -44,10 +49,19 @@ use crate::{
 
 const DEFAULT_SEARCH_LIMIT: u64 = 5;
 
+/// Longest wait for an extract job, below the connection timeout of the
+/// server
+const MAX_EXTRACT_JOB_WAIT_SECS: u64 = 50;
+
 #[derive(Clone, Debug)]
 pub struct RepositoryEndpointState {
     repository_manager: Arc<DataRepositoryManager>,
-    coordinator_client: Arc<CoordinatorClient>,
+}
+
+#[derive(Clone)]
+pub struct ExtractEndpointState {
+    extractor_router: Arc<ExtractorRouter>,
+    extract_jobs: Arc<ExtractJobs>,
 }
 
 #[derive(OpenApi)]
@@ -62,14 +76,21 @@ pub struct RepositoryEndpointState {
             list_extractors,
             bind_extractor,
             metadata_lookup,
-            list_executors
+            list_executors,
+            list_tasks,
+            get_task,
+            get_task_logs,
+            cancel_task,
+            list_binding_stats
         ),
         components(
             schemas(CreateRepository, CreateRepositoryResponse, IndexDistance,
                 TextAddRequest, TextAdditionResponse, Text, IndexSearchResponse,
                 DocumentFragment, ListIndexesResponse, ExtractorOutputSchema, Index, SearchRequest, ListRepositoriesResponse, ListExtractorsResponse
             , ExtractorDescription, DataRepository, ExtractorBinding, ExtractorBindRequest, ExtractorBindResponse, Executor,
-            MetadataResponse, ExtractedMetadata, ListExecutorsResponse)
+            MetadataResponse, ExtractedMetadata, ListExecutorsResponse, Task, TaskOutcome,
+            ContentMetadata, ListTasksFilters, ListTasksResponse, GetTaskResponse,
+            ExtractorBindingStats, ListBindingStatsResponse, InputParamsErrorResponse, InputParamsError)
         ),
         tags(
             (name = "indexify", description = "Indexify API")
@@ -119,12 +140,38 @@ impl Server {
             )
             .await?,
         );
+        let ingestion_service = IngestionGrpcService::new(
+            repository_manager.clone(),
+            self.config.ingestion_grpc_token.clone(),
+        );
+        let ingestion_addr = self.config.ingestion_grpc_addr_sock()?;
+        tokio::spawn(async move {
+            if let Err(err) = ingestion_service
+                .run(ingestion_addr, shutdown_signal())
+                .await
+            {
+                error!("ingestion grpc server failed: {}", err);
+            }
+        });
         let repository_endpoint_state = RepositoryEndpointState {
             repository_manager: repository_manager.clone(),
-            coordinator_client: coordinator_client.clone(),
         };
         let caches = Caches::new(self.config.cache.clone());
+        let extractor_router = Arc::new(
+            ExtractorRouter::new(coordinator_client.clone())?
+                .with_cache(caches.cache_extract_content.clone()),
+        );
+        let extract_endpoint_state = ExtractEndpointState {
+            extractor_router: extractor_router.clone(),
+            extract_jobs: Arc::new(ExtractJobs::new(
+                extractor_router,
+                std::time::Duration::from_secs(self.config.extract_job_ttl_secs),
+            )),
+        };
         let metrics = HttpMetricsLayerBuilder::new().build();
+        // binding gauges have to be registered after the metrics layer installs the
+        // global meter provider
+        Arc::new(BindingStatsMetrics::new()).start(repository_manager.clone());
         let app = Router::new()
             .merge(metrics.routes())
             .merge(SwaggerUi::new("/api-docs-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
@@ -135,6 +182,10 @@ impl Server {
                 "/repositories/:repository_name/extractor_bindings",
                 post(bind_extractor).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/extractor_bindings/stats",
+                get(list_binding_stats).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/indexes",
                 get(list_indexes).with_state(repository_endpoint_state.clone()),
@@ -155,6 +206,10 @@ impl Server {
                 "/repositories/:repository_name/upload_file",
                 post(upload_file).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/upload_records",
+                post(upload_records).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories/:repository_name/search",
                 post(index_search).with_state(repository_endpoint_state.clone()),
@@ -163,6 +218,22 @@ impl Server {
                 "/repositories/:repository_name/metadata",
                 get(metadata_lookup).with_state(repository_endpoint_state.clone()),
             )
+            .route(
+                "/repositories/:repository_name/tasks",
+                get(list_tasks).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/tasks/:task_id",
+                get(get_task).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/tasks/:task_id/logs",
+                get(get_task_logs).with_state(repository_endpoint_state.clone()),
+            )
+            .route(
+                "/tasks/:task_id/cancel",
+                post(cancel_task).with_state(repository_endpoint_state.clone()),
+            )
             .route(
                 "/repositories",
                 post(create_repository).with_state(repository_endpoint_state.clone()),
@@ -189,11 +260,18 @@ impl Server {
             )
             .route(
                 "/extractors/extract",
-                post(extract_content).with_state(repository_endpoint_state.clone()),
+                post(extract_content).with_state(extract_endpoint_state.clone()),
+            )
+            .route(
+                "/extractors/extract_jobs",
+                post(create_extract_job).with_state(extract_endpoint_state.clone()),
+            )
+            .route(
+                "/extractors/extract_jobs/:job_id",
+                get(get_extract_job).with_state(extract_endpoint_state.clone()),
             )
             .layer(OtelAxumLayer::default())
             .layer(metrics)
-            .layer(Extension(caches))
             .layer(DefaultBodyLimit::disable());
 
         let (signal_tx, signal_rx) = tokio::sync::watch::channel(());
@@ -431,6 +509,7 @@ async fn get_repository(
     tag = "indexify",
     responses(
         (status = 200, description = "Extractor binded successfully", body = ExtractorBindResponse),
+        (status = BAD_REQUEST, description = "Input params don't match the extractor's schema", body = InputParamsErrorResponse),
         (status = INTERNAL_SERVER_ERROR, description = "Unable to bind extractor to repository")
     ),
 )]
@@ -447,9 +526,10 @@ async fn bind_extractor(
         .add_extractor_binding(&repository_name, &payload.extractor_binding)
         .await
         .map_err(|e| {
-            IndexifyAPIError::new(
+            IndexifyAPIError::from_input_params_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("failed to bind extractor: {}", e),
+                "failed to bind extractor",
+                e,
             )
         })?
         .into_iter()
@@ -457,6 +537,33 @@ async fn bind_extractor(
     Ok(Json(ExtractorBindResponse { index_names }))
 }
 
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/extractor_bindings/stats",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Processing stats of the extractor bindings", body = ListBindingStatsResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to load binding stats")
+    ),
+)]
+async fn list_binding_stats(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+) -> Result<Json<ListBindingStatsResponse>, IndexifyAPIError> {
+    let stats = state
+        .repository_manager
+        .list_binding_stats(&repository_name)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to list binding stats: {}", e),
+            )
+        })?;
+    Ok(Json(ListBindingStatsResponse { stats }))
+}
+
 #[tracing::instrument]
 #[utoipa::path(
     post,
@@ -486,7 +593,7 @@ async fn add_texts(
         .collect();
     state
         .repository_manager
-        .add_texts(&repository_name, content)
+        .add_texts(&repository_name, content, payload.priority)
         .await
         .map_err(|e| {
             IndexifyAPIError::new(
@@ -515,6 +622,127 @@ async fn list_content(
     Ok(Json(ListContentResponse { content_list }))
 }
 
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/repositories/{repository_name}/tasks",
+    params(ListTasksFilters),
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Tasks of the repository matching the filters", body = ListTasksResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to list tasks")
+    ),
+)]
+async fn list_tasks(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    filters: Query<ListTasksFilters>,
+) -> Result<Json<ListTasksResponse>, IndexifyAPIError> {
+    let tasks = state
+        .repository_manager
+        .list_tasks(&repository_name, &filters)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to list tasks: {}", e),
+            )
+        })?;
+    Ok(Json(ListTasksResponse { tasks }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Task with the given id", body = GetTaskResponse),
+        (status = NOT_FOUND, description = "Task not found"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to get the task")
+    ),
+)]
+async fn get_task(
+    Path(task_id): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+) -> Result<Json<GetTaskResponse>, IndexifyAPIError> {
+    let task = state
+        .repository_manager
+        .get_task(&task_id)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to get task: {}", e),
+            )
+        })?
+        .ok_or_else(|| {
+            IndexifyAPIError::new(StatusCode::NOT_FOUND, format!("task {} not found", task_id))
+        })?;
+    Ok(Json(GetTaskResponse { task }))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/tasks/{task_id}/logs",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Output of the extractor while it ran the task", content_type = "text/plain"),
+        (status = NOT_FOUND, description = "Task not found or it didn't log anything"),
+        (status = INTERNAL_SERVER_ERROR, description = "Unable to read the logs")
+    ),
+)]
+async fn get_task_logs(
+    Path(task_id): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+) -> Result<impl IntoResponse, IndexifyAPIError> {
+    let logs = state
+        .repository_manager
+        .task_logs(&task_id)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to get task logs: {}", e),
+            )
+        })?
+        .ok_or_else(|| {
+            IndexifyAPIError::new(
+                StatusCode::NOT_FOUND,
+                format!("task {} not found or it didn't log anything", task_id),
+            )
+        })?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], logs))
+}
+
+#[tracing::instrument]
+#[utoipa::path(
+    post,
+    path = "/tasks/{task_id}/cancel",
+    tag = "indexify",
+    responses(
+        (status = 200, description = "Task cancelled successfully"),
+        (status = BAD_REQUEST, description = "Task not found or already finished")
+    ),
+)]
+async fn cancel_task(
+    Path(task_id): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+) -> Result<Json<()>, IndexifyAPIError> {
+    state
+        .repository_manager
+        .cancel_task(&task_id)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::BAD_REQUEST,
+                format!("failed to cancel task: {}", e),
+            )
+        })?;
+    Ok(Json(()))
+}
+
 async fn read_content(
     Path((repository_name, content_id)): Path<(String, String)>,
     State(state): State<RepositoryEndpointState>,
@@ -532,6 +760,7 @@ async fn read_content(
 async fn upload_file(
     Path(repository_name): Path<String>,
     State(state): State<RepositoryEndpointState>,
+    params: Query<UploadFileParams>,
     mut files: Multipart,
 ) -> Result<(), IndexifyAPIError> {
     while let Some(file) = files.next_field().await.unwrap() {
@@ -544,7 +773,7 @@ async fn upload_file(
         );
         state
             .repository_manager
-            .upload_file(&repository_name, data, &name)
+            .upload_file(&repository_name, data, &name, params.priority)
             .await
             .map_err(|e| {
                 IndexifyAPIError::new(
@@ -556,6 +785,62 @@ async fn upload_file(
     Ok(())
 }
 
+/// Uploads a json, json lines or csv file in the `file` field, each of its
+/// records becomes a content. The `mapping` field is the json encoded
+/// record mapping.
+#[tracing::instrument]
+#[axum::debug_handler]
+async fn upload_records(
+    Path(repository_name): Path<String>,
+    State(state): State<RepositoryEndpointState>,
+    params: Query<UploadFileParams>,
+    mut fields: Multipart,
+) -> Result<Json<UploadRecordsResponse>, IndexifyAPIError> {
+    let bad_request = |message: String| IndexifyAPIError::new(StatusCode::BAD_REQUEST, message);
+    let mut file = None;
+    let mut mapping = RecordMapping::default();
+    while let Some(field) = fields
+        .next_field()
+        .await
+        .map_err(|e| bad_request(e.to_string()))?
+    {
+        match field.name() {
+            Some("file") => {
+                let name = field.file_name().unwrap_or_default().to_string();
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| bad_request(e.to_string()))?;
+                file = Some((name, data));
+            }
+            Some("mapping") => {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| bad_request(e.to_string()))?;
+                mapping = serde_json::from_slice(&data)
+                    .map_err(|e| bad_request(format!("invalid mapping: {}", e)))?;
+            }
+            _ => {}
+        }
+    }
+    let (name, data) = file.ok_or_else(|| bad_request("missing file field".to_string()))?;
+    // nothing is written unless all the records are valid
+    let records = records::records_content(&data, &name, &mapping)
+        .map_err(|e| bad_request(format!("invalid records: {}", e)))?;
+    let response = state
+        .repository_manager
+        .upload_records(&repository_name, data, &name, records, params.priority)
+        .await
+        .map_err(|e| {
+            IndexifyAPIError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to upload records: {}", e),
+            )
+        })?;
+    Ok(Json(response))
+}
+
 #[axum::debug_handler]
 async fn write_extracted_content(
     State(state): State<RepositoryEndpointState>,
@@ -619,21 +904,18 @@ async fn list_extractors(
 
 #[axum::debug_handler]
 async fn extract_content(
-    State(repository_endpoint): State<RepositoryEndpointState>,
-    Extension(caches): Extension<Caches>,
+    State(state): State<ExtractEndpointState>,
     Json(request): Json<ExtractRequest>,
 ) -> Result<Json<ExtractResponse>, IndexifyAPIError> {
-    let cache = caches.cache_extract_content.clone();
-    let extractor_router = ExtractorRouter::new(repository_endpoint.coordinator_client.clone())
-        .map_err(|e| IndexifyAPIError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .with_cache(cache);
-    let content_list = extractor_router
+    let content_list = state
+        .extractor_router
         .extract_content(&request.name, request.content, request.input_params)
         .await
         .map_err(|e| {
-            IndexifyAPIError::new(
+            IndexifyAPIError::from_input_params_error(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("failed to extract content: {}", e),
+                "failed to extract content",
+                e,
             )
         })?;
     Ok(Json(ExtractResponse {
@@ -641,6 +923,28 @@ async fn extract_content(
     }))
 }
 
+#[axum::debug_handler]
+async fn create_extract_job(
+    State(state): State<ExtractEndpointState>,
+    Json(request): Json<ExtractRequest>,
+) -> Result<Json<CreateExtractJobResponse>, IndexifyAPIError> {
+    let job_id = state.extract_jobs.submit(request);
+    Ok(Json(CreateExtractJobResponse { job_id }))
+}
+
+#[axum::debug_handler]
+async fn get_extract_job(
+    State(state): State<ExtractEndpointState>,
+    Path(job_id): Path<String>,
+    Query(params): Query<GetExtractJobParams>,
+) -> Result<Json<ExtractJob>, IndexifyAPIError> {
+    let wait = std::time::Duration::from_secs(params.wait_secs.min(MAX_EXTRACT_JOB_WAIT_SECS));
+    let job = state.extract_jobs.get(&job_id, wait).await.ok_or_else(|| {
+        IndexifyAPIError::new(StatusCode::NOT_FOUND, "extract job not found".to_string())
+    })?;
+    Ok(Json(job))
+}
+
 #[tracing::instrument]
 #[utoipa::path(
     get,
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fmt,
     fs,
     net::{AddrParseError, IpAddr, Ipv4Addr, SocketAddr},
@@ -18,11 +19,159 @@ fn default_executor_port() -> u64 {
     0
 }
 
+fn default_executor_batch_size() -> u32 {
+    1
+}
+
+fn default_executor_workers() -> usize {
+    1
+}
+
+fn default_task_timeout_secs() -> u64 {
+    600
+}
+
+fn default_stream_flush_size() -> usize {
+    16
+}
+
+fn default_max_task_log_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    60
+}
+
+fn default_python() -> String {
+    "python3".to_string()
+}
+
+fn default_wasm_max_memory_bytes() -> usize {
+    256 * 1024 * 1024
+}
+
+fn default_wasm_fuel() -> u64 {
+    10_000_000_000
+}
+
+fn default_upload_max_retries() -> u32 {
+    8
+}
+
+fn default_upload_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_upload_max_backoff_ms() -> u64 {
+    60_000
+}
+
+fn default_upload_max_request_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
+fn default_upload_chunk_bytes() -> usize {
+    1024 * 1024
+}
+
+/// Sandbox limits of WebAssembly extractors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmConfig {
+    /// Maximum size of the linear memory of the extractor
+    #[serde(default = "default_wasm_max_memory_bytes")]
+    pub max_memory_bytes: usize,
+    /// Fuel available to a single call, roughly the number of instructions
+    /// the extractor may execute
+    #[serde(default = "default_wasm_fuel")]
+    pub fuel: u64,
+}
+
+impl Default for WasmConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: default_wasm_max_memory_bytes(),
+            fuel: default_wasm_fuel(),
+        }
+    }
+}
+
+/// How executors write task results to the ingestion server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadConfig {
+    /// Number of times a failed write is retried before the task is
+    /// reported as failed
+    #[serde(default = "default_upload_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every retry
+    #[serde(default = "default_upload_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_upload_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Approximate maximum size of a single write, larger results are
+    /// written in several requests
+    #[serde(default = "default_upload_max_request_bytes")]
+    pub max_request_bytes: usize,
+    /// Size of the chunks content data is streamed in over gRPC
+    #[serde(default = "default_upload_chunk_bytes")]
+    pub chunk_bytes: usize,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: default_upload_max_retries(),
+            initial_backoff_ms: default_upload_initial_backoff_ms(),
+            max_backoff_ms: default_upload_max_backoff_ms(),
+            max_request_bytes: default_upload_max_request_bytes(),
+            chunk_bytes: default_upload_chunk_bytes(),
+        }
+    }
+}
+
+/// Runs the python extractor in worker processes instead of the executor's
+/// embedded interpreter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PythonWorkerConfig {
+    /// Number of worker processes
+    #[serde(default = "default_executor_workers")]
+    pub workers: usize,
+    /// Workers are restarted after running this many extractions, 0 keeps
+    /// them running forever
+    #[serde(default)]
+    pub max_tasks_per_worker: usize,
+    /// Python interpreter used to start the workers
+    #[serde(default = "default_python")]
+    pub python: String,
+}
+
+impl Default for PythonWorkerConfig {
+    fn default() -> Self {
+        Self {
+            workers: default_executor_workers(),
+            max_tasks_per_worker: 0,
+            python: default_python(),
+        }
+    }
+}
+
 // TODO: provide default https port as well?
 fn default_server_port() -> u64 {
     8900
 }
 
+fn default_ingestion_grpc_port() -> u64 {
+    8901
+}
+
+fn default_extract_job_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_max_tasks_per_executor() -> usize {
+    100
+}
+
 fn default_coordinator_port() -> u64 {
     8950
 }
@@ -180,6 +329,66 @@ pub struct ExecutorConfig {
     pub ingestion_api_addr: String,
     #[serde(default)]
     pub extractor_path: String,
+    /// Paths of further extractors hosted by the executor
+    #[serde(default)]
+    pub extractor_paths: Vec<String>,
+    /// Maximum number of tasks passed to the extractor in a single call
+    #[serde(default = "default_executor_batch_size")]
+    pub batch_size: u32,
+    /// Number of extractor calls running concurrently
+    #[serde(default = "default_executor_workers")]
+    pub workers: usize,
+    /// Time after which an extractor call is abandoned and its tasks are
+    /// marked as failed
+    #[serde(default = "default_task_timeout_secs")]
+    pub task_timeout_secs: u64,
+    /// Number of content produced by a streaming extractor written to the
+    /// ingestion server at once, while the extraction is still running
+    #[serde(default = "default_stream_flush_size")]
+    pub stream_flush_size: usize,
+    /// Size of the output of an extractor kept per task, the end of it is
+    /// kept when it's larger
+    #[serde(default = "default_max_task_log_bytes")]
+    pub max_task_log_bytes: usize,
+    /// Time given to running tasks to finish when the executor is drained,
+    /// the tasks still running after it are handed to other executors
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    /// Labels of the executor, matched against the placement of bindings
+    /// to decide which tasks it runs, e.g. `zone: eu-west-1`
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Runs the extractor in a pool of python worker processes when set
+    #[serde(default)]
+    pub python_workers: Option<PythonWorkerConfig>,
+    /// Address of an extractor serving the gRPC extractor protocol, used
+    /// instead of loading the extractor from the extractor path
+    #[serde(default)]
+    pub extractor_addr: Option<String>,
+    /// Limits of extractors loaded from a `.wasm` extractor path
+    #[serde(default)]
+    pub wasm: WasmConfig,
+    /// Names of extractors built into the binary hosted by the executor,
+    /// e.g. `text_chunker`
+    #[serde(default)]
+    pub builtin_extractors: Vec<String>,
+    /// Directory of the journal of received tasks and unreported results,
+    /// tasks are only kept in memory when unset
+    #[serde(default)]
+    pub journal_dir: Option<String>,
+    #[serde(default)]
+    pub upload: UploadConfig,
+    /// Address of the gRPC ingestion endpoint, results are streamed to it
+    /// instead of being posted as JSON when set
+    #[serde(default)]
+    pub ingestion_grpc_addr: Option<String>,
+    /// Token presented to the gRPC ingestion endpoint
+    #[serde(default)]
+    pub ingestion_grpc_token: Option<String>,
+    /// Blob store the content of tasks is read from when the executor
+    /// doesn't read it through the gRPC ingestion endpoint
+    #[serde(default)]
+    pub blob_storage: BlobStorageConfig,
 }
 
 impl Default for ExecutorConfig {
@@ -191,6 +400,23 @@ impl Default for ExecutorConfig {
             coordinator_addr: format!("localhost:{}", default_coordinator_port()),
             ingestion_api_addr: format!("localhost:{}", default_server_port()),
             extractor_path: "".into(),
+            extractor_paths: vec![],
+            batch_size: default_executor_batch_size(),
+            workers: default_executor_workers(),
+            task_timeout_secs: default_task_timeout_secs(),
+            stream_flush_size: default_stream_flush_size(),
+            max_task_log_bytes: default_max_task_log_bytes(),
+            drain_timeout_secs: default_drain_timeout_secs(),
+            labels: HashMap::new(),
+            python_workers: None,
+            extractor_addr: None,
+            wasm: WasmConfig::default(),
+            builtin_extractors: vec![],
+            journal_dir: None,
+            upload: UploadConfig::default(),
+            ingestion_grpc_addr: None,
+            ingestion_grpc_token: None,
+            blob_storage: BlobStorageConfig::default(),
         }
     }
 }
@@ -238,10 +464,124 @@ impl ExecutorConfig {
         self
     }
 
+    #[allow(dead_code)]
     pub fn with_extractor_path(mut self, path: String) -> Self {
         self.extractor_path = path;
         self
     }
+
+    pub fn with_extractor_paths(mut self, paths: Vec<String>) -> Self {
+        self.extractor_paths.extend(paths);
+        self
+    }
+
+    /// Returns the paths of all the extractors loaded from a path
+    pub fn all_extractor_paths(&self) -> Vec<String> {
+        std::iter::once(&self.extractor_path)
+            .filter(|path| !path.is_empty())
+            .chain(&self.extractor_paths)
+            .cloned()
+            .collect()
+    }
+
+    pub fn with_batch_size(mut self, batch_size: Option<u32>) -> Self {
+        if let Some(batch_size) = batch_size {
+            self.batch_size = batch_size.max(1);
+        }
+        self
+    }
+
+    pub fn with_workers(mut self, workers: Option<usize>) -> Self {
+        if let Some(workers) = workers {
+            self.workers = workers.max(1);
+        }
+        self
+    }
+
+    /// Runs the extractor in `workers` python processes, recycling them
+    /// after `max_tasks_per_worker` extractions. The number of concurrent
+    /// extractor calls is raised to the number of workers.
+    pub fn with_python_workers(
+        mut self,
+        workers: Option<usize>,
+        max_tasks_per_worker: Option<usize>,
+    ) -> Self {
+        if let Some(workers) = workers {
+            let workers = workers.max(1);
+            self.python_workers = Some(PythonWorkerConfig {
+                workers,
+                max_tasks_per_worker: max_tasks_per_worker.unwrap_or_default(),
+                ..Default::default()
+            });
+            self.workers = self.workers.max(workers);
+        }
+        self
+    }
+
+    pub fn with_extractor_addr(mut self, addr: Option<String>) -> Self {
+        self.extractor_addr = addr;
+        self
+    }
+
+    pub fn with_builtin_extractors(mut self, names: Vec<String>) -> Self {
+        self.builtin_extractors.extend(names);
+        self
+    }
+
+    pub fn with_ingestion_grpc_addr(mut self, addr: Option<String>) -> Self {
+        self.ingestion_grpc_addr = addr;
+        self
+    }
+
+    pub fn with_ingestion_grpc_token(mut self, token: Option<String>) -> Self {
+        self.ingestion_grpc_token = token;
+        self
+    }
+
+    pub fn with_blob_storage_path(mut self, path: Option<String>) -> Self {
+        if let Some(path) = path {
+            self.blob_storage.disk = Some(DiskStorageConfig { path });
+        }
+        self
+    }
+
+    pub fn with_journal_dir(mut self, journal_dir: Option<String>) -> Self {
+        self.journal_dir = journal_dir;
+        self
+    }
+
+    pub fn with_task_timeout_secs(mut self, task_timeout_secs: Option<u64>) -> Self {
+        if let Some(task_timeout_secs) = task_timeout_secs {
+            self.task_timeout_secs = task_timeout_secs;
+        }
+        self
+    }
+
+    pub fn with_stream_flush_size(mut self, stream_flush_size: Option<usize>) -> Self {
+        if let Some(stream_flush_size) = stream_flush_size {
+            self.stream_flush_size = stream_flush_size.max(1);
+        }
+        self
+    }
+
+    pub fn with_max_task_log_bytes(mut self, max_task_log_bytes: Option<usize>) -> Self {
+        if let Some(max_task_log_bytes) = max_task_log_bytes {
+            self.max_task_log_bytes = max_task_log_bytes;
+        }
+        self
+    }
+
+    pub fn with_drain_timeout_secs(mut self, drain_timeout_secs: Option<u64>) -> Self {
+        if let Some(drain_timeout_secs) = drain_timeout_secs {
+            self.drain_timeout_secs = drain_timeout_secs;
+        }
+        self
+    }
+
+    pub fn with_labels(mut self, labels: Vec<(String, String)>) -> Self {
+        self.labels.extend(labels);
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -343,6 +683,13 @@ pub struct ServerConfig {
     pub listen_if: NetworkAddress,
     #[serde(default = "default_server_port")]
     pub listen_port: u64,
+    /// Port of the gRPC endpoint executors stream extracted content to
+    #[serde(default = "default_ingestion_grpc_port")]
+    pub ingestion_grpc_port: u64,
+    /// Token executors have to present to the gRPC ingestion endpoint, the
+    /// endpoint is open when unset
+    #[serde(default)]
+    pub ingestion_grpc_token: Option<String>,
     #[serde(default = "default_coordinator_port")]
     pub coordinator_port: u64,
     pub raft_port: u64,
@@ -359,6 +706,13 @@ pub struct ServerConfig {
     pub cache: ServerCacheConfig,
     #[serde(default)]
     pub sled: SledConfig,
+    /// Seconds the results of asynchronous extract jobs are kept for
+    #[serde(default = "default_extract_job_ttl_secs")]
+    pub extract_job_ttl_secs: u64,
+    /// Maximum number of unfinished tasks assigned to a single executor,
+    /// further tasks stay pending until the executor finishes some
+    #[serde(default = "default_max_tasks_per_executor")]
+    pub max_tasks_per_executor: usize,
 }
 
 impl Default for ServerConfig {
@@ -366,6 +720,8 @@ impl Default for ServerConfig {
         Self {
             listen_if: "0.0.0.0".into(),
             listen_port: default_server_port(),
+            ingestion_grpc_port: default_ingestion_grpc_port(),
+            ingestion_grpc_token: None,
             coordinator_port: default_coordinator_port(),
             raft_port: default_raft_port(),
             index_config: VectorIndexConfig::default(),
@@ -385,6 +741,8 @@ impl Default for ServerConfig {
             }],
             cache: ServerCacheConfig::default(),
             sled: SledConfig::default(),
+            extract_job_ttl_secs: default_extract_job_ttl_secs(),
+            max_tasks_per_executor: default_max_tasks_per_executor(),
         }
     }
 }
@@ -414,6 +772,13 @@ impl ServerConfig {
         })
     }
 
+    pub fn ingestion_grpc_addr_sock(&self) -> Result<SocketAddr> {
+        let addr = format!("{}:{}", self.listen_if, self.ingestion_grpc_port);
+        addr.parse().map_err(|e: AddrParseError| {
+            anyhow!("Failed to parse listen address {} :{}", addr, e.to_string())
+        })
+    }
+
     pub fn coordinator_lis_addr_sock(&self) -> Result<SocketAddr> {
         let addr = format!("{}:{}", self.listen_if, self.coordinator_port);
         addr.parse().map_err(|e: AddrParseError| {
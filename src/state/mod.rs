@@ -136,7 +136,7 @@ impl App {
 
         info!("starting raft server at {}", addr.to_string());
         let raft_srvr = RaftApiServer::new(RaftGrpcServer::new(Arc::new(raft.clone())));
-        let (leader_change_tx, leader_change_rx) = tokio::sync::watch::channel::<bool>(false);
+        let (leader_change_tx, leader_change_rx) = watch::channel::<bool>(false);
 
         let app = Arc::new(App {
             id: server_config.node_id,
@@ -200,6 +200,10 @@ impl App {
         self.store.state_change_rx.clone()
     }
 
+    pub fn get_task_assignments_watcher(&self) -> Receiver<u64> {
+        self.store.task_assignments_rx.clone()
+    }
+
     pub async fn stop(&self) -> Result<()> {
         info!("stopping raft server");
         let _ = self.raft.shutdown().await;
@@ -346,6 +350,8 @@ impl App {
         Ok(tasks)
     }
 
+    /// Returns the executors hosting the extractor, draining executors
+    /// excepted
     pub async fn get_executors_for_extractor(
         &self,
         extractor: &str,
@@ -362,6 +368,9 @@ impl App {
                 "internal error: executor id {} not found",
                 executor_id
             ))?;
+            if executor.draining {
+                continue;
+            }
             executors.push(executor.clone());
         }
         Ok(executors)
@@ -388,6 +397,22 @@ impl App {
         Ok(content)
     }
 
+    pub async fn drain_executor(
+        &self,
+        executor_id: &str,
+        unstarted_task_ids: Vec<String>,
+    ) -> Result<()> {
+        let _resp = self
+            .raft
+            .client_write(Request::DrainExecutor {
+                executor_id: executor_id.to_string(),
+                unstarted_task_ids,
+            })
+            .await
+            .map_err(|e| anyhow!("unable to drain executor {}", e))?;
+        Ok(())
+    }
+
     pub async fn remove_executor(&self, executor_id: &str) -> Result<()> {
         let _resp = self
             .raft
@@ -508,15 +533,19 @@ impl App {
         &self,
         addr: &str,
         executor_id: &str,
-        extractor: internal_api::ExtractorDescription,
+        extractors: Vec<internal_api::ExtractorDescription>,
+        batch_size: u32,
+        labels: HashMap<String, String>,
     ) -> Result<()> {
         let _resp = self
             .raft
             .client_write(Request::RegisterExecutor {
                 addr: addr.to_string(),
                 executor_id: executor_id.to_string(),
-                extractor,
+                extractors,
+                batch_size,
                 ts_secs: timestamp_secs(),
+                labels,
             })
             .await?;
         Ok(())
@@ -540,7 +569,10 @@ impl App {
     ) -> Result<()> {
         let _resp = self
             .raft
-            .client_write(Request::AssignTask { assignments })
+            .client_write(Request::AssignTask {
+                assignments,
+                ts_secs: timestamp_secs(),
+            })
             .await?;
         Ok(())
     }
@@ -612,10 +644,101 @@ impl App {
         Ok(tasks)
     }
 
+    /// Returns the unfinished tasks assigned to every executor
+    pub async fn assigned_tasks(&self) -> Result<HashMap<ExecutorId, Vec<internal_api::Task>>> {
+        let store = self.store.state_machine.read().await;
+        let mut assigned_tasks = HashMap::new();
+        for (executor_id, task_ids) in store.task_assignments.iter() {
+            let tasks = task_ids
+                .iter()
+                .filter_map(|task_id| store.tasks.get(task_id).cloned())
+                .collect_vec();
+            assigned_tasks.insert(executor_id.clone(), tasks);
+        }
+        Ok(assigned_tasks)
+    }
+
     pub async fn task_with_id(&self, task_id: &str) -> Result<internal_api::Task> {
+        self.get_task(task_id)
+            .await
+            .ok_or(anyhow!("task not found"))
+    }
+
+    pub async fn get_task(&self, task_id: &str) -> Option<internal_api::Task> {
+        let store = self.store.state_machine.read().await;
+        store.tasks.get(task_id).cloned()
+    }
+
+    /// Returns the processing stats of every binding of the repository, or of
+    /// all repositories when `repository` is empty
+    pub async fn list_binding_stats(
+        &self,
+        repository: &str,
+    ) -> Result<Vec<(String, String, internal_api::ExtractorBindingStats)>> {
+        let store = self.store.state_machine.read().await;
+        let mut stats = vec![];
+        for (repository_name, bindings) in store.bindings_table.iter() {
+            if !repository.is_empty() && repository_name != repository {
+                continue;
+            }
+            for binding in bindings {
+                let binding_stats = store
+                    .binding_stats
+                    .get(repository_name)
+                    .and_then(|stats| stats.get(&binding.name))
+                    .cloned()
+                    .unwrap_or_default();
+                stats.push((repository_name.clone(), binding.name.clone(), binding_stats));
+            }
+        }
+        Ok(stats)
+    }
+
+    pub async fn list_tasks(&self, repository: &str) -> Result<Vec<internal_api::Task>> {
+        let store = self.store.state_machine.read().await;
+        let tasks = store
+            .tasks
+            .values()
+            .filter(|task| task.repository == repository)
+            .cloned()
+            .collect_vec();
+        Ok(tasks)
+    }
+
+    pub async fn cancel_task(&self, task_id: &str) -> Result<()> {
+        let _resp = self
+            .raft
+            .client_write(Request::CancelTask {
+                task_id: task_id.to_string(),
+                ts_secs: timestamp_secs(),
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cancelled_tasks_for_executor(&self, executor_id: &str) -> Result<Vec<TaskId>> {
         let store = self.store.state_machine.read().await;
-        let task = store.tasks.get(task_id).ok_or(anyhow!("task not found"))?;
-        Ok(task.clone())
+        let task_ids = store
+            .cancelled_task_assignments
+            .get(executor_id)
+            .map(|task_ids| task_ids.iter().cloned().collect_vec())
+            .unwrap_or_default();
+        Ok(task_ids)
+    }
+
+    pub async fn acknowledge_cancelled_tasks(
+        &self,
+        executor_id: &str,
+        task_ids: Vec<TaskId>,
+    ) -> Result<()> {
+        let _resp = self
+            .raft
+            .client_write(Request::AcknowledgeCancelledTasks {
+                executor_id: executor_id.to_string(),
+                task_ids,
+            })
+            .await?;
+        Ok(())
     }
 
     pub async fn list_indexes(&self, repository: &str) -> Result<Vec<internal_api::Index>> {
@@ -74,6 +74,10 @@ impl SledStorable for HashMap<ExtractorName, internal_api::ExtractorDescription>
 impl SledStorable for HashSet<String> {}
 impl SledStorable for HashMap<RepositoryId, HashSet<internal_api::Index>> {}
 impl SledStorable for HashMap<String, internal_api::Index> {}
+impl SledStorable
+    for HashMap<RepositoryId, HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>>
+{
+}
 impl SledStorable for StateMachine {}
 impl SledStorable for SnapshotMeta<u64, BasicNode> {}
 
@@ -88,6 +92,8 @@ impl SledStorableTestFactory for StateMachine {
             unassigned_tasks: HashSet::<TaskId>::spawn_instance_for_store_test(),
             task_assignments: HashMap::<ExecutorId, HashSet<TaskId>>::spawn_instance_for_store_test(
             ),
+            cancelled_task_assignments:
+                HashMap::<ExecutorId, HashSet<TaskId>>::spawn_instance_for_store_test(),
             extraction_events:
                 HashMap::<ExtractionEventId, internal_api::ExtractionEvent>::spawn_instance_for_store_test(),
             unprocessed_extraction_events:
@@ -105,6 +111,10 @@ impl SledStorableTestFactory for StateMachine {
             repository_extractors:
                 HashMap::<RepositoryId, HashSet<internal_api::Index>>::spawn_instance_for_store_test(),
             index_table: HashMap::<String, internal_api::Index>::spawn_instance_for_store_test(),
+            binding_stats: HashMap::<
+                RepositoryId,
+                HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>,
+            >::spawn_instance_for_store_test(),
         }
     }
 }
@@ -176,7 +186,7 @@ impl SledStorableTestFactory for Entry<TypeConfig> {
     fn spawn_instance_for_store_test() -> Self {
         Entry {
             log_id: LogId::spawn_instance_for_store_test(),
-            payload: openraft::EntryPayload::Membership(Membership::spawn_instance_for_store_test()),
+            payload: EntryPayload::Membership(Membership::spawn_instance_for_store_test()),
         }
     }
 }
@@ -265,6 +275,7 @@ impl SledStorableTestFactory for internal_api::ExtractorDescription {
     fn spawn_instance_for_store_test() -> Self {
         internal_api::ExtractorDescription {
             name: "test".to_string(),
+            version: "0.1.0".to_string(),
             description: "test".to_string(),
             input_params: test_json_value(),
             outputs: {
@@ -286,7 +297,10 @@ impl SledStorableTestFactory for internal_api::ExecutorMetadata {
             id: "test".to_string(),
             last_seen: 0,
             addr: "localhost:8080".to_string(),
-            extractor: internal_api::ExtractorDescription::spawn_instance_for_store_test(),
+            extractors: vec![internal_api::ExtractorDescription::spawn_instance_for_store_test()],
+            batch_size: 1,
+            draining: false,
+            labels: HashMap::from([("zone".to_string(), "eu-west-1".to_string())]),
         }
     }
 }
@@ -302,6 +316,14 @@ impl SledStorableTestFactory for internal_api::Task {
             content_metadata: internal_api::ContentMetadata::spawn_instance_for_store_test(),
             input_params: test_json_value(),
             outcome: internal_api::TaskOutcome::Success,
+            executor_id: Some("test".to_string()),
+            attempts: 1,
+            created_at: 1234567890,
+            assigned_at: Some(1234567890),
+            finished_at: Some(1234567890),
+            error_message: None,
+            logs_url: None,
+            priority: 0,
         }
     }
 }
@@ -337,6 +359,7 @@ impl SledStorableTestFactory for internal_api::ExtractionEvent {
             },
             created_at: 1234567890,
             processed_at: Some(1234567890),
+            priority: None,
         }
     }
 }
@@ -357,7 +380,13 @@ impl SledStorableTestFactory for internal_api::ExtractorBinding {
             input_params: test_json_value(),
             output_index_name_mapping: HashMap::new(),
             index_name_table_mapping: HashMap::new(),
+            priority: 0,
             content_source: "test_content_source".to_string(),
+            label_inheritance: internal_api::LabelInheritance::Allow(vec!["key1".to_string()]),
+            placement: internal_api::Placement {
+                constraints: HashMap::from([("zone".to_string(), "eu-west-1".to_string())]),
+                preferences: HashMap::from([("gpu".to_string(), "true".to_string())]),
+            },
         }
     }
 }
@@ -441,6 +470,33 @@ impl SledStorableTestFactory for HashMap<String, HashSet<String>> {
     }
 }
 
+impl SledStorableTestFactory
+    for HashMap<RepositoryId, HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>>
+{
+    fn spawn_instance_for_store_test() -> Self {
+        let mut hm = HashMap::new();
+        hm.insert("test".to_string(), {
+            let mut stats = HashMap::new();
+            stats.insert(
+                "test".to_string(),
+                internal_api::ExtractorBindingStats {
+                    content_matched: 10,
+                    tasks_pending: 2,
+                    tasks_assigned: 3,
+                    tasks_succeeded: 3,
+                    tasks_failed: 1,
+                    tasks_cancelled: 1,
+                    total_latency_secs: 40,
+                    latency_samples: 4,
+                    last_error: Some("test error".to_string()),
+                },
+            );
+            stats
+        });
+        hm
+    }
+}
+
 trait SledTestObject: SledStorable + SledStorableTestFactory + Debug + PartialEq {}
 
 #[allow(unused_macros)]
@@ -492,6 +548,8 @@ mod sled_tests {
     type TestRepositories = HashSet<String>;
     type TestRepositoryExtractors = HashMap<RepositoryId, HashSet<internal_api::Index>>;
     type TestIndexTable = HashMap<String, internal_api::Index>;
+    type TestBindingStats =
+        HashMap<RepositoryId, HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>>;
     type TestStateMachine = StateMachine;
     type TestVoteNodeId = Vote<NodeId>;
     type TestStoredSnapshot = StoredSnapshot;
@@ -517,6 +575,7 @@ mod sled_tests {
     test_sled_storeable!(TestRepositories);
     test_sled_storeable!(TestRepositoryExtractors);
     test_sled_storeable!(TestIndexTable);
+    test_sled_storeable!(TestBindingStats);
     test_sled_storeable!(TestVoteNodeId);
     test_sled_storeable!(TestStoredSnapshot);
     test_sled_storeable!(TestSnapshotMeta);
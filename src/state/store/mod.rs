@@ -45,8 +45,13 @@ pub enum Request {
     RegisterExecutor {
         addr: String,
         executor_id: String,
-        extractor: internal_api::ExtractorDescription,
+        #[serde(alias = "extractor", deserialize_with = "internal_api::one_or_many")]
+        extractors: Vec<internal_api::ExtractorDescription>,
+        #[serde(default)]
+        batch_size: u32,
         ts_secs: u64,
+        #[serde(default)]
+        labels: HashMap<String, String>,
     },
     CreateRepository {
         name: String,
@@ -56,6 +61,15 @@ pub enum Request {
     },
     AssignTask {
         assignments: HashMap<TaskId, ExecutorId>,
+        ts_secs: u64,
+    },
+    CancelTask {
+        task_id: TaskId,
+        ts_secs: u64,
+    },
+    AcknowledgeCancelledTasks {
+        executor_id: ExecutorId,
+        task_ids: Vec<TaskId>,
     },
     AddExtractionEvent {
         event: internal_api::ExtractionEvent,
@@ -84,6 +98,10 @@ pub enum Request {
         content_metadata: Vec<internal_api::ContentMetadata>,
         extraction_events: Vec<internal_api::ExtractionEvent>,
     },
+    DrainExecutor {
+        executor_id: String,
+        unstarted_task_ids: Vec<TaskId>,
+    },
     RemoveExecutor {
         executor_id: String,
     },
@@ -116,6 +134,7 @@ pub type ContentId = String;
 pub type ExecutorId = String;
 pub type ExtractionEventId = String;
 pub type ExtractorName = String;
+pub type ExtractorBindingName = String;
 
 #[derive(Clone)]
 pub enum ChangeType {
@@ -123,6 +142,7 @@ pub enum ChangeType {
     NewBinding,
     ExecutorAdded,
     ExecutorRemoved,
+    TaskCompleted,
 }
 
 #[derive(Clone)]
@@ -160,6 +180,8 @@ pub struct StateMachine {
 
     pub task_assignments: HashMap<ExecutorId, HashSet<TaskId>>,
 
+    pub cancelled_task_assignments: HashMap<ExecutorId, HashSet<TaskId>>,
+
     pub extraction_events: HashMap<ExtractionEventId, internal_api::ExtractionEvent>,
 
     pub unprocessed_extraction_events: HashSet<ExtractionEventId>,
@@ -179,6 +201,54 @@ pub struct StateMachine {
     pub repository_extractors: HashMap<RepositoryId, HashSet<internal_api::Index>>,
 
     pub index_table: HashMap<String, internal_api::Index>,
+
+    pub binding_stats:
+        HashMap<RepositoryId, HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>>,
+}
+
+impl StateMachine {
+    fn binding_stats_mut(
+        &mut self,
+        repository: &str,
+        extractor_binding: &str,
+    ) -> &mut internal_api::ExtractorBindingStats {
+        self.binding_stats
+            .entry(repository.to_string())
+            .or_default()
+            .entry(extractor_binding.to_string())
+            .or_default()
+    }
+
+    /// Puts unfinished tasks assigned to the executor back in the queue of
+    /// unassigned tasks
+    fn unassign_tasks(&mut self, executor_id: &str, task_ids: &[TaskId]) {
+        let Some(assigned) = self.task_assignments.get_mut(executor_id) else {
+            return;
+        };
+        let mut unassigned = vec![];
+        for task_id in task_ids {
+            if !assigned.remove(task_id) {
+                continue;
+            }
+            let Some(task) = self.tasks.get_mut(task_id) else {
+                continue;
+            };
+            if task.is_terminal() {
+                continue;
+            }
+            task.executor_id = None;
+            task.assigned_at = None;
+            unassigned.push((task.repository.clone(), task.extractor_binding.clone()));
+            self.unassigned_tasks.insert(task_id.clone());
+        }
+        if assigned.is_empty() {
+            self.task_assignments.remove(executor_id);
+        }
+        for (repository, binding) in unassigned {
+            self.binding_stats_mut(&repository, &binding)
+                .task_unassigned();
+        }
+    }
 }
 
 #[async_trait]
@@ -433,6 +503,7 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
         let state_machine_tree = self.open_tree(SledStoreTree::StateMachine);
 
         let mut change_events: Vec<StateChange> = Vec::new();
+        let mut task_assignments_changed = false;
 
         for entry in entries {
             tracing::debug!(%entry.log_id, "replicate to sm");
@@ -445,20 +516,27 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                     Request::RegisterExecutor {
                         addr,
                         executor_id,
-                        extractor,
+                        extractors,
+                        batch_size,
                         ts_secs,
+                        labels,
                     } => {
-                        sm.extractors
-                            .insert(extractor.name.clone(), extractor.clone());
-                        sm.extractor_executors_table
-                            .entry(extractor.name.clone())
-                            .or_default()
-                            .insert(executor_id.clone());
+                        for extractor in extractors {
+                            sm.extractors
+                                .insert(extractor.name.clone(), extractor.clone());
+                            sm.extractor_executors_table
+                                .entry(extractor.name.clone())
+                                .or_default()
+                                .insert(executor_id.clone());
+                        }
                         let executor_info = internal_api::ExecutorMetadata {
                             id: executor_id.clone(),
                             last_seen: *ts_secs,
                             addr: addr.clone(),
-                            extractor: extractor.clone(),
+                            extractors: extractors.clone(),
+                            batch_size: *batch_size,
+                            draining: false,
+                            labels: labels.clone(),
                         };
                         sm.executors.insert(executor_id.clone(), executor_info);
                         sm.overwrite_sled_kv(
@@ -478,18 +556,81 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                         )?;
                         res.push(Response { value: None })
                     }
+                    Request::DrainExecutor {
+                        executor_id,
+                        unstarted_task_ids,
+                    } => {
+                        task_assignments_changed = true;
+                        if let Some(executor) = sm.executors.get_mut(executor_id) {
+                            executor.draining = true;
+                        }
+                        sm.unassign_tasks(executor_id, unstarted_task_ids);
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "executors",
+                            sm.executors.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(&state_machine_tree, "tasks", sm.tasks.clone())?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "unassigned_tasks",
+                            sm.unassigned_tasks.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "task_assignments",
+                            sm.task_assignments.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
+                        change_events.push(StateChange {
+                            id: executor_id.clone(),
+                            change_type: ChangeType::ExecutorRemoved,
+                        });
+                        res.push(Response { value: None })
+                    }
                     Request::RemoveExecutor { executor_id } => {
+                        task_assignments_changed = true;
+                        // Hand the tasks the executor didn't finish to other executors
+                        let assigned_task_ids = sm
+                            .task_assignments
+                            .get(executor_id)
+                            .map(|tasks| tasks.iter().cloned().collect::<Vec<_>>())
+                            .unwrap_or_default();
+                        sm.unassign_tasks(executor_id, &assigned_task_ids);
                         // Remove this from the executors table
                         let executor_meta = sm.executors.remove(executor_id);
                         // Remove this from the extractor -> executors table
                         if let Some(executor_meta) = executor_meta {
-                            let executors = sm
-                                .extractor_executors_table
-                                .entry(executor_meta.extractor.name.clone())
-                                .or_default();
-                            executors.remove(executor_meta.extractor.name.as_str());
+                            for extractor in executor_meta.extractors {
+                                let executors = sm
+                                    .extractor_executors_table
+                                    .entry(extractor.name.clone())
+                                    .or_default();
+                                executors.remove(executor_id);
+                            }
                         }
+                        sm.cancelled_task_assignments.remove(executor_id);
                         // update the state machine in sled
+                        sm.overwrite_sled_kv(&state_machine_tree, "tasks", sm.tasks.clone())?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "unassigned_tasks",
+                            sm.unassigned_tasks.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "task_assignments",
+                            sm.task_assignments.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
                         sm.overwrite_sled_kv(
                             &state_machine_tree,
                             "executors",
@@ -500,24 +641,61 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                             "extractor_executors_table",
                             sm.extractor_executors_table.clone(),
                         )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "cancelled_task_assignments",
+                            sm.cancelled_task_assignments.clone(),
+                        )?;
+                        change_events.push(StateChange {
+                            id: executor_id.clone(),
+                            change_type: ChangeType::ExecutorRemoved,
+                        });
                         res.push(Response { value: None })
                     }
                     Request::CreateTasks { tasks } => {
                         for task in tasks {
-                            sm.tasks.insert(task.id.clone(), task.clone());
+                            let is_new = sm.tasks.insert(task.id.clone(), task.clone()).is_none();
                             sm.unassigned_tasks.insert(task.id.clone());
+                            if is_new {
+                                sm.binding_stats_mut(&task.repository, &task.extractor_binding)
+                                    .task_created();
+                            }
                         }
                         sm.overwrite_sled_kv(&state_machine_tree, "tasks", sm.tasks.clone())?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
                         res.push(Response { value: None })
                     }
-                    Request::AssignTask { assignments } => {
+                    Request::AssignTask {
+                        assignments,
+                        ts_secs,
+                    } => {
+                        task_assignments_changed = true;
                         for (task_id, executor_id) in assignments {
                             sm.task_assignments
                                 .entry(executor_id.clone())
                                 .or_default()
                                 .insert(task_id.clone());
-                            sm.unassigned_tasks.remove(task_id);
+                            let was_pending = sm.unassigned_tasks.remove(task_id);
+                            let binding = sm.tasks.get_mut(task_id).map(|task| {
+                                task.executor_id = Some(executor_id.clone());
+                                task.attempts += 1;
+                                task.assigned_at = Some(*ts_secs);
+                                (task.repository.clone(), task.extractor_binding.clone())
+                            });
+                            if let (true, Some((repository, binding))) = (was_pending, binding) {
+                                sm.binding_stats_mut(&repository, &binding).task_assigned();
+                            }
                         }
+                        sm.overwrite_sled_kv(&state_machine_tree, "tasks", sm.tasks.clone())?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
                         sm.overwrite_sled_kv(
                             &state_machine_tree,
                             "task_assignments",
@@ -530,6 +708,81 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                         )?;
                         res.push(Response { value: None })
                     }
+                    Request::CancelTask { task_id, ts_secs } => {
+                        task_assignments_changed = true;
+                        let was_terminal = sm.tasks.get(task_id).map(|task| task.is_terminal());
+                        let executor_id = sm.tasks.get_mut(task_id).and_then(|task| {
+                            task.outcome = internal_api::TaskOutcome::Cancelled;
+                            task.finished_at = Some(*ts_secs);
+                            task.executor_id.clone()
+                        });
+                        let was_pending = sm.unassigned_tasks.remove(task_id);
+                        if let Some(false) = was_terminal {
+                            let task = sm.tasks[task_id].clone();
+                            sm.binding_stats_mut(&task.repository, &task.extractor_binding)
+                                .task_finished(&task, was_pending);
+                            change_events.push(StateChange {
+                                id: task_id.clone(),
+                                change_type: ChangeType::TaskCompleted,
+                            });
+                        }
+                        // Let the executor know about the cancellation on its next heartbeat
+                        if let Some(executor_id) = executor_id {
+                            let was_assigned = sm
+                                .task_assignments
+                                .get_mut(&executor_id)
+                                .map(|tasks| tasks.remove(task_id))
+                                .unwrap_or(false);
+                            if was_assigned {
+                                sm.cancelled_task_assignments
+                                    .entry(executor_id)
+                                    .or_default()
+                                    .insert(task_id.clone());
+                            }
+                        }
+                        sm.overwrite_sled_kv(&state_machine_tree, "tasks", sm.tasks.clone())?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "unassigned_tasks",
+                            sm.unassigned_tasks.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "task_assignments",
+                            sm.task_assignments.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "cancelled_task_assignments",
+                            sm.cancelled_task_assignments.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
+                        res.push(Response { value: None })
+                    }
+                    Request::AcknowledgeCancelledTasks {
+                        executor_id,
+                        task_ids,
+                    } => {
+                        if let Some(cancelled) = sm.cancelled_task_assignments.get_mut(executor_id)
+                        {
+                            for task_id in task_ids {
+                                cancelled.remove(task_id);
+                            }
+                            if cancelled.is_empty() {
+                                sm.cancelled_task_assignments.remove(executor_id);
+                            }
+                        }
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "cancelled_task_assignments",
+                            sm.cancelled_task_assignments.clone(),
+                        )?;
+                        res.push(Response { value: None })
+                    }
                     Request::AddExtractionEvent { event } => {
                         sm.extraction_events.insert(event.id.clone(), event.clone());
                         sm.unprocessed_extraction_events.insert(event.id.clone());
@@ -683,7 +936,19 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                         content_metadata,
                         extraction_events,
                     } => {
-                        sm.tasks.insert(task.id.clone(), task.clone());
+                        let was_pending = sm.unassigned_tasks.contains(&task.id);
+                        let previous = sm.tasks.insert(task.id.clone(), task.clone());
+                        // Results may be written in several parts, only count the first
+                        // transition into a terminal outcome
+                        if task.is_terminal() && previous.map_or(false, |t| !t.is_terminal()) {
+                            sm.binding_stats_mut(&task.repository, &task.extractor_binding)
+                                .task_finished(task, was_pending);
+                            // The executor has capacity for more work
+                            change_events.push(StateChange {
+                                id: task.id.clone(),
+                                change_type: ChangeType::TaskCompleted,
+                            });
+                        }
                         if *mark_finished {
                             sm.unassigned_tasks.remove(&task.id);
                             if let Some(executor_id) = executor_id {
@@ -710,6 +975,16 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                             "unassigned_tasks",
                             sm.unassigned_tasks.clone(),
                         )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "task_assignments",
+                            sm.task_assignments.clone(),
+                        )?;
+                        sm.overwrite_sled_kv(
+                            &state_machine_tree,
+                            "binding_stats",
+                            sm.binding_stats.clone(),
+                        )?;
                         res.push(Response { value: None })
                     }
                 },
@@ -730,6 +1005,10 @@ impl RaftStorage<TypeConfig> for Arc<SledStore> {
                 tracing::error!("error sending state change event: {}", err);
             }
         }
+        if task_assignments_changed {
+            self.task_assignments_tx
+                .send_modify(|version| *version += 1);
+        }
         Ok(res)
     }
 
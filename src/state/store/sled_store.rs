@@ -26,6 +26,10 @@ pub struct SledStore {
 
     pub state_change_tx: tokio::sync::watch::Sender<StateChange>,
     pub state_change_rx: tokio::sync::watch::Receiver<StateChange>,
+
+    /// Bumped whenever tasks are assigned to or taken from executors
+    pub task_assignments_tx: tokio::sync::watch::Sender<u64>,
+    pub task_assignments_rx: tokio::sync::watch::Receiver<u64>,
 }
 
 impl SledStore {
@@ -34,6 +38,7 @@ impl SledStore {
             id: "".to_string(),
             change_type: ChangeType::NewContent,
         });
+        let (task_assignments_tx, task_assignments_rx) = tokio::sync::watch::channel(0);
 
         let sled_config = match config.path {
             None => {
@@ -61,6 +66,8 @@ impl SledStore {
             state_machine,
             state_change_tx: tx,
             state_change_rx: rx,
+            task_assignments_tx,
+            task_assignments_rx,
         }
     }
 }
@@ -428,6 +435,18 @@ impl StateMachine {
                                 )
                             })?;
                 }
+                "cancelled_task_assignments" => {
+                    state_machine.cancelled_task_assignments =
+                        HashMap::<ExecutorId, HashSet<TaskId>>::load_from_sled_value(value)
+                            .map_err(|e| {
+                                err_kind.build_with_tree_and_key(
+                                    "failed to load cancelled_task_assignments",
+                                    e,
+                                    SledStoreTree::StateMachine,
+                                    key.clone(),
+                                )
+                            })?;
+                }
                 "extraction_events" => {
                     state_machine.extraction_events = HashMap::<
                         ExtractionEventId,
@@ -562,6 +581,20 @@ impl StateMachine {
                                 )
                             })?;
                 }
+                "binding_stats" => {
+                    state_machine.binding_stats = HashMap::<
+                        RepositoryId,
+                        HashMap<ExtractorBindingName, internal_api::ExtractorBindingStats>,
+                    >::load_from_sled_value(value)
+                    .map_err(|e| {
+                        err_kind.build_with_tree_and_key(
+                            "failed to load binding_stats",
+                            e,
+                            SledStoreTree::StateMachine,
+                            key.clone(),
+                        )
+                    })?;
+                }
                 _ => {
                     return Err(StoreError::new(
                         StoreErrorKind::ParseError,
@@ -650,6 +683,12 @@ impl StateMachine {
                     .to_saveable_value()
                     .map_err(|e| err_fn(e, "task_assignments".to_string()))?,
             )?;
+            tx.insert(
+                "cancelled_task_assignments",
+                self.cancelled_task_assignments
+                    .to_saveable_value()
+                    .map_err(|e| err_fn(e, "cancelled_task_assignments".to_string()))?,
+            )?;
             tx.insert(
                 "extraction_events",
                 self.extraction_events
@@ -710,6 +749,12 @@ impl StateMachine {
                     .to_saveable_value()
                     .map_err(|e| err_fn(e, "index_table".to_string()))?,
             )?;
+            tx.insert(
+                "binding_stats",
+                self.binding_stats
+                    .to_saveable_value()
+                    .map_err(|e| err_fn(e, "binding_stats".to_string()))?,
+            )?;
             Ok(())
         })
         .map_err(|e| {
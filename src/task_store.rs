@@ -1,17 +1,136 @@
 use std::{
-    collections::{HashMap, HashSet},
-    sync::{Arc, RwLock},
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        RwLock,
+    },
 };
 
+use anyhow::Result;
 use indexify_internal_api as internal_api;
+use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::watch;
-use tracing::info;
+use tracing::{error, info};
+
+/// Ids of reported tasks remembered to ignore the coordinator delivering
+/// them again
+const MAX_REPORTED_TASKS: usize = 10_000;
+
+/// On-disk journal of the tasks received by the executor and of their
+/// results until they are reported, so that a restarted executor can pick
+/// up where it left off
+#[derive(Debug)]
+struct TaskJournal {
+    db: sled::Db,
+    tasks: sled::Tree,
+    finished: sled::Tree,
+    // ids of the last reported tasks, keyed by the order they were reported
+    // in
+    reported: sled::Tree,
+}
+
+impl TaskJournal {
+    fn open(path: &str) -> Result<Self> {
+        let db = sled::open(path)?;
+        let tasks = db.open_tree("tasks")?;
+        let finished = db.open_tree("finished")?;
+        let reported = db.open_tree("reported")?;
+        Ok(Self {
+            db,
+            tasks,
+            finished,
+            reported,
+        })
+    }
+
+    fn load_reported(&self) -> Result<ReportedTasks> {
+        let mut reported = ReportedTasks::default();
+        for entry in self.reported.iter() {
+            let (key, task_id) = entry?;
+            reported.insert(key.to_vec(), String::from_utf8(task_id.to_vec())?);
+        }
+        Ok(reported)
+    }
+
+    /// Journals a reported task, returning the key it is journaled under
+    fn insert_reported(&self, task_id: &str) -> Vec<u8> {
+        let result = self.db.generate_id().map(|id| id.to_be_bytes().to_vec());
+        let key = match result {
+            Ok(key) => key,
+            Err(err) => {
+                error!("unable to journal reported task {}: {}", task_id, err);
+                return vec![];
+            }
+        };
+        if let Err(err) = self.reported.insert(&key, task_id.as_bytes()) {
+            error!("unable to journal reported task {}: {}", task_id, err);
+        }
+        key
+    }
+
+    fn load<T: DeserializeOwned>(tree: &sled::Tree) -> Result<Vec<T>> {
+        tree.iter()
+            .values()
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect()
+    }
+
+    fn insert<T: Serialize>(tree: &sled::Tree, key: &str, value: &T) {
+        let result = serde_json::to_vec(value)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| Ok(tree.insert(key, value)?));
+        if let Err(err) = result {
+            error!("unable to journal task {}: {}", key, err);
+        }
+    }
+
+    fn remove(tree: &sled::Tree, key: &str) {
+        if let Err(err) = tree.remove(key) {
+            error!("unable to remove task {} from journal: {}", key, err);
+        }
+    }
+}
+
+/// The last `MAX_REPORTED_TASKS` reported tasks, oldest first
+#[derive(Debug, Default)]
+struct ReportedTasks {
+    order: VecDeque<(Vec<u8>, String)>,
+    task_ids: HashSet<String>,
+}
+
+impl ReportedTasks {
+    /// Adds a task journaled under `key`, returning the key of the task it
+    /// evicts
+    fn insert(&mut self, key: Vec<u8>, task_id: String) -> Option<Vec<u8>> {
+        if !self.task_ids.insert(task_id.clone()) {
+            return None;
+        }
+        self.order.push_back((key, task_id));
+        if self.order.len() <= MAX_REPORTED_TASKS {
+            return None;
+        }
+        let (key, task_id) = self.order.pop_front()?;
+        self.task_ids.remove(&task_id);
+        Some(key)
+    }
+
+    fn contains(&self, task_id: &str) -> bool {
+        self.task_ids.contains(task_id)
+    }
+}
 
 #[derive(Debug)]
 pub struct TaskStore {
     tasks: Arc<RwLock<HashMap<String, internal_api::Task>>>,
     pending: Arc<RwLock<HashSet<String>>>,
+    running: Arc<RwLock<HashSet<String>>>,
+    // running tasks cancelled by the coordinator, their results are dropped
+    cancelled: Arc<RwLock<HashSet<String>>>,
     finished: Arc<RwLock<HashMap<String, internal_api::TaskResult>>>,
+    reported: RwLock<ReportedTasks>,
+    draining: AtomicBool,
+    journal: Option<TaskJournal>,
     tx: watch::Sender<()>,
     rx: watch::Receiver<()>,
 }
@@ -22,37 +141,240 @@ impl TaskStore {
         Self {
             tasks: Arc::new(RwLock::new(HashMap::new())),
             pending: Arc::new(RwLock::new(HashSet::new())),
+            running: Arc::new(RwLock::new(HashSet::new())),
+            cancelled: Arc::new(RwLock::new(HashSet::new())),
             finished: Arc::new(RwLock::new(HashMap::new())),
+            reported: RwLock::new(ReportedTasks::default()),
+            draining: AtomicBool::new(false),
+            journal: None,
             tx,
             rx,
         }
     }
 
+    /// Opens a task store journaled at `path`. Tasks received before a
+    /// restart are run again unless they finished, finished results that
+    /// weren't reported yet are reported again and recently reported tasks
+    /// aren't run again.
+    pub fn open(path: &str) -> Result<Self> {
+        let journal = TaskJournal::open(path)?;
+        let reported = journal.load_reported()?;
+        let tasks: Vec<internal_api::Task> = TaskJournal::load(&journal.tasks)?;
+        let finished: Vec<internal_api::TaskResult> = TaskJournal::load(&journal.finished)?;
+        let task_ids: HashSet<_> = tasks.iter().map(|task| task.id.as_str()).collect();
+        // results of tasks that were dropped can't be reported
+        let (finished, orphaned): (Vec<_>, Vec<_>) = finished
+            .into_iter()
+            .partition(|result| task_ids.contains(result.task_id.as_str()));
+        for result in orphaned {
+            TaskJournal::remove(&journal.finished, &result.task_id);
+        }
+        let finished: HashMap<_, _> = finished
+            .into_iter()
+            .map(|result| (result.task_id.clone(), result))
+            .collect();
+        let pending: HashSet<_> = tasks
+            .iter()
+            .map(|task| task.id.clone())
+            .filter(|task_id| !finished.contains_key(task_id))
+            .collect();
+        info!(
+            "replaying task journal at {}: {} pending tasks, {} unreported results",
+            path,
+            pending.len(),
+            finished.len()
+        );
+        let store = Self {
+            tasks: Arc::new(RwLock::new(
+                tasks
+                    .into_iter()
+                    .map(|task| (task.id.clone(), task))
+                    .collect(),
+            )),
+            pending: Arc::new(RwLock::new(pending)),
+            finished: Arc::new(RwLock::new(finished)),
+            reported: RwLock::new(reported),
+            journal: Some(journal),
+            ..Self::new()
+        };
+        // wake up the executor to run the pending tasks and report results
+        store.tx.send(()).unwrap();
+        Ok(store)
+    }
+
+    /// Returns the executor id stored in the journal, storing `new_id` if
+    /// there is none yet. Keeping the id across restarts lets the
+    /// coordinator deliver the tasks assigned before the restart again.
+    pub fn executor_id(&self, new_id: String) -> Result<String> {
+        let Some(journal) = &self.journal else {
+            return Ok(new_id);
+        };
+        if let Some(id) = journal.db.get("executor_id")? {
+            return Ok(String::from_utf8(id.to_vec())?);
+        }
+        journal.db.insert("executor_id", new_id.as_bytes())?;
+        Ok(new_id)
+    }
+
+    /// Forgets a task whose result was reported, remembering its id so that
+    /// it isn't run again when the coordinator delivers it again
     pub fn clear_completed_task(&self, task_id: &str) {
         self.finished.write().unwrap().remove(task_id);
         self.tasks.write().unwrap().remove(task_id);
+        if let Some(journal) = &self.journal {
+            TaskJournal::remove(&journal.finished, task_id);
+            TaskJournal::remove(&journal.tasks, task_id);
+        }
+        let mut reported = self.reported.write().unwrap();
+        if reported.contains(task_id) {
+            return;
+        }
+        let key = self
+            .journal
+            .as_ref()
+            .map(|journal| journal.insert_reported(task_id))
+            .unwrap_or_default();
+        let evicted = reported.insert(key, task_id.to_string());
+        if let (Some(journal), Some(evicted)) = (&self.journal, evicted) {
+            if let Err(err) = journal.reported.remove(evicted) {
+                error!("unable to remove reported task from journal: {}", err);
+            }
+        }
     }
 
+    /// Adds tasks delivered by the coordinator. Tasks the store already
+    /// knows about or recently reported are ignored, the coordinator may
+    /// deliver a task again until it processed its result. No tasks are
+    /// added once the store is drained.
     pub fn add(&self, tasks: Vec<internal_api::Task>) {
-        info!("Adding {} tasks to task store", tasks.len());
+        if self.is_draining() {
+            return;
+        }
         let mut pending = self.pending.write().unwrap();
         let mut tasks_store = self.tasks.write().unwrap();
+        let reported = self.reported.read().unwrap();
+        let mut added = 0;
         for task in tasks {
+            if tasks_store.contains_key(&task.id) || reported.contains(&task.id) {
+                continue;
+            }
+            if let Some(journal) = &self.journal {
+                TaskJournal::insert(&journal.tasks, &task.id, &task);
+            }
             pending.insert(task.id.clone());
             tasks_store.insert(task.id.clone(), task);
+            added += 1;
+        }
+        if added > 0 {
+            info!("Adding {} tasks to task store", added);
+            self.tx.send(()).unwrap();
         }
-        self.tx.send(()).unwrap();
     }
 
+    /// Records the results of tasks. Results of cancelled tasks and of
+    /// tasks the store doesn't know are dropped, they can't be reported.
     pub fn update(&self, task_results: Vec<internal_api::TaskResult>) {
         let mut pending = self.pending.write().unwrap();
+        let mut tasks_store = self.tasks.write().unwrap();
+        let mut running = self.running.write().unwrap();
+        let mut cancelled = self.cancelled.write().unwrap();
         let mut finished = self.finished.write().unwrap();
         for task_result in task_results {
-            pending.remove(&task_result.task_id);
-            finished.insert(task_result.task_id.clone(), task_result);
+            let task_id = task_result.task_id.clone();
+            pending.remove(&task_id);
+            running.remove(&task_id);
+            if cancelled.remove(&task_id) {
+                if let Some(journal) = &self.journal {
+                    TaskJournal::remove(&journal.tasks, &task_id);
+                }
+                tasks_store.remove(&task_id);
+                continue;
+            }
+            if !tasks_store.contains_key(&task_id) {
+                continue;
+            }
+            if let Some(journal) = &self.journal {
+                TaskJournal::insert(&journal.finished, &task_id, &task_result);
+            }
+            finished.insert(task_id, task_result);
+        }
+    }
+
+    /// Drops tasks cancelled by the coordinator. Tasks that are already
+    /// running are not interrupted, they are dropped with their result once
+    /// they finish.
+    pub fn cancel(&self, task_ids: Vec<String>) {
+        if task_ids.is_empty() {
+            return;
+        }
+        info!("Cancelling {} tasks in task store", task_ids.len());
+        let mut pending = self.pending.write().unwrap();
+        let mut tasks_store = self.tasks.write().unwrap();
+        let running = self.running.read().unwrap();
+        let mut cancelled = self.cancelled.write().unwrap();
+        for task_id in task_ids {
+            if running.contains(&task_id) {
+                cancelled.insert(task_id);
+                continue;
+            }
+            if let Some(journal) = &self.journal {
+                TaskJournal::remove(&journal.tasks, &task_id);
+            }
+            pending.remove(&task_id);
+            tasks_store.remove(&task_id);
         }
     }
 
+    /// Marks the tasks as running and returns them, except the ones which
+    /// aren't pending anymore because they were cancelled or drained
+    pub fn start(&self, tasks: Vec<internal_api::Task>) -> Vec<internal_api::Task> {
+        let mut pending = self.pending.write().unwrap();
+        let mut running = self.running.write().unwrap();
+        tasks
+            .into_iter()
+            .filter(|task| pending.remove(&task.id))
+            .inspect(|task| {
+                running.insert(task.id.clone());
+            })
+            .collect()
+    }
+
+    /// Stops accepting tasks and drops the tasks that haven't started yet,
+    /// returning their ids so that they can be handed to other executors
+    pub fn drain(&self) -> Vec<String> {
+        self.draining.store(true, Ordering::SeqCst);
+        let mut pending = self.pending.write().unwrap();
+        let mut tasks_store = self.tasks.write().unwrap();
+        let task_ids: Vec<String> = pending.drain().collect();
+        for task_id in &task_ids {
+            if let Some(journal) = &self.journal {
+                TaskJournal::remove(&journal.tasks, task_id);
+            }
+            tasks_store.remove(task_id);
+        }
+        task_ids
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Number of tasks waiting to run
+    pub fn pending(&self) -> usize {
+        self.pending.read().unwrap().len()
+    }
+
+    /// Number of tasks running
+    pub fn running(&self) -> usize {
+        self.running.read().unwrap().len()
+    }
+
+    /// Returns true when no task is running or waiting for its result to be
+    /// reported
+    pub fn is_idle(&self) -> bool {
+        self.running() == 0 && self.finished.read().unwrap().is_empty()
+    }
+
     pub fn pending_tasks(&self) -> Vec<internal_api::Task> {
         let pending = self.pending.read().unwrap();
         let tasks = self.tasks.read().unwrap();
@@ -81,3 +403,126 @@ impl TaskStore {
         tasks.get(task_id).cloned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use indexify_internal_api as internal_api;
+
+    use super::{TaskStore, MAX_REPORTED_TASKS};
+    use crate::state::store::SledStorableTestFactory;
+
+    fn test_task(id: &str) -> internal_api::Task {
+        internal_api::Task {
+            id: id.to_string(),
+            ..internal_api::Task::spawn_instance_for_store_test()
+        }
+    }
+
+    #[test]
+    fn test_task_journal() {
+        let path = std::env::temp_dir().join(format!("task_journal_{}", nanoid::nanoid!()));
+        let path = path.to_str().unwrap();
+        {
+            let store = TaskStore::open(path).unwrap();
+            assert_eq!("id_1", store.executor_id("id_1".to_string()).unwrap());
+            store.add(vec![test_task("1"), test_task("2"), test_task("3")]);
+            store.update(vec![
+                internal_api::TaskResult::success("1", vec![]),
+                internal_api::TaskResult::success("2", vec![]),
+            ]);
+            store.clear_completed_task("2");
+        }
+
+        let store = TaskStore::open(path).unwrap();
+        assert_eq!("id_1", store.executor_id("id_2".to_string()).unwrap());
+        let pending: Vec<String> = store.pending_tasks().into_iter().map(|t| t.id).collect();
+        assert_eq!(vec!["3".to_string()], pending);
+        let finished: Vec<String> = store
+            .finished_tasks()
+            .into_iter()
+            .map(|r| r.task_id)
+            .collect();
+        assert_eq!(vec!["1".to_string()], finished);
+        assert!(store.get_task("1").is_some());
+
+        // tasks delivered again aren't run twice
+        store.add(vec![test_task("1"), test_task("3")]);
+        assert_eq!(1, store.pending_tasks().len());
+        drop(store);
+
+        // neither are reported tasks delivered after a restart
+        let store = TaskStore::open(path).unwrap();
+        store.add(vec![test_task("2")]);
+        assert_eq!(1, store.pending_tasks().len());
+        assert!(store.get_task("2").is_none());
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_reported_tasks() {
+        let path = std::env::temp_dir().join(format!("task_journal_{}", nanoid::nanoid!()));
+        let path = path.to_str().unwrap();
+        {
+            let store = TaskStore::open(path).unwrap();
+            for i in 0..=MAX_REPORTED_TASKS {
+                store.clear_completed_task(&i.to_string());
+            }
+        }
+        // only the last reported tasks are remembered
+        let store = TaskStore::open(path).unwrap();
+        assert_eq!(
+            MAX_REPORTED_TASKS,
+            store.journal.as_ref().unwrap().reported.len()
+        );
+        store.add(vec![test_task("0"), test_task("1")]);
+        let pending: Vec<String> = store.pending_tasks().into_iter().map(|t| t.id).collect();
+        assert_eq!(vec!["0".to_string()], pending);
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn test_drain() {
+        let store = TaskStore::new();
+        store.add(vec![test_task("1"), test_task("2"), test_task("3")]);
+        let started = store.start(vec![test_task("1")]);
+        assert_eq!(1, started.len());
+        assert_eq!(1, store.running());
+
+        let mut unstarted = store.drain();
+        unstarted.sort();
+        assert_eq!(vec!["2".to_string(), "3".to_string()], unstarted);
+        // drained tasks aren't started and no tasks are added anymore
+        assert!(store.start(vec![test_task("2")]).is_empty());
+        store.add(vec![test_task("4")]);
+        assert!(store.pending_tasks().is_empty());
+        assert!(!store.is_idle());
+
+        store.update(vec![internal_api::TaskResult::success("1", vec![])]);
+        assert_eq!(0, store.running());
+        assert!(!store.is_idle());
+        store.clear_completed_task("1");
+        assert!(store.is_idle());
+    }
+
+    #[test]
+    fn test_cancel_running_task() {
+        let store = TaskStore::new();
+        store.add(vec![test_task("1"), test_task("2")]);
+        assert_eq!(1, store.start(vec![test_task("1")]).len());
+        store.cancel(vec!["1".to_string(), "2".to_string()]);
+        assert!(store.pending_tasks().is_empty());
+        assert!(!store.is_idle());
+
+        // the result of the cancelled task is dropped
+        store.update(vec![internal_api::TaskResult::success("1", vec![])]);
+        assert!(store.finished_tasks().is_empty());
+        assert!(store.get_task("1").is_none());
+        assert!(store.is_idle());
+
+        // results of unknown tasks aren't kept either
+        store.update(vec![internal_api::TaskResult::success("3", vec![])]);
+        assert!(store.is_idle());
+    }
+}
@@ -20,6 +20,7 @@ pub mod db_utils {
         );
         internal_api::ExtractorDescription {
             name: DEFAULT_TEST_EXTRACTOR.to_string(),
+            version: "0.1.0".to_string(),
             description: "test_description".to_string(),
             input_params: json!({}),
             outputs,
@@ -1,255 +1,358 @@
-use anyhow::Result;
+use std::{collections::VecDeque, fmt::Debug, sync::Arc};
+
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{cmp::max, fmt::Debug, sync::Arc};
-use strum_macros::{Display, EnumString};
 use thiserror::Error;
 
-use crate::{EmbeddingGeneratorError, EmbeddingGeneratorTS};
-
 #[derive(Error, Debug)]
 pub enum TextSplitterError {
-    #[error(transparent)]
-    TokenizerError(#[from] EmbeddingGeneratorError),
+    #[error("invalid pattern: {0}")]
+    InvalidPattern(#[from] regex::Error),
 
-    #[error("unknown splitter kind: `{0}`")]
-    UnknownSplitterKind(String),
+    #[error("invalid chunk size: {0}")]
+    InvalidChunkSize(String),
 }
 
 pub type TextSplitterTS = Arc<dyn TextSplitter + Send + Sync>;
 
-#[derive(Display, Serialize, Deserialize, Clone, Debug, EnumString)]
+fn default_separators() -> Vec<String> {
+    vec![
+        "\n\n".to_string(),
+        "\n".to_string(),
+        ". ".to_string(),
+        " ".to_string(),
+    ]
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "splitter", rename_all = "snake_case")]
 pub enum TextSplitterKind {
-    #[strum(serialize = "noop")]
-    Noop,
+    /// Splits at line breaks
+    NewLine,
 
-    #[strum(serialize = "regex")]
+    /// Splits after every match of the pattern
     Regex { pattern: String },
 
-    #[strum(serialize = "new_line")]
-    NewLine,
-}
+    /// Splits at the first separator that yields small enough pieces,
+    /// falling back to the following separators for pieces that are still
+    /// too large
+    Recursive {
+        #[serde(default = "default_separators")]
+        separators: Vec<String>,
+    },
 
-async fn merge_tokens(
-    tokens: Vec<u64>,
-    max_tokens_per_chunk: u64,
-    token_overlap: u64,
-) -> Result<Vec<Vec<u64>>, TextSplitterError> {
-    let step_size = max(
-        max_tokens_per_chunk.checked_sub(token_overlap).unwrap_or(1),
-        1,
-    );
-
-    let chunk_tokens: Vec<Vec<u64>> = (0..tokens.len())
-        .step_by(step_size as usize)
-        .map(|start_idx| {
-            let end_idx = usize::min(start_idx + max_tokens_per_chunk as usize, tokens.len());
-            tokens[start_idx..end_idx].to_vec()
-        })
-        .collect();
-    Ok(chunk_tokens)
+    /// Splits at markdown headings first, chunks never span two sections
+    Markdown,
 }
 
-#[async_trait::async_trait]
+/// Splitters cut a document into pieces ending at their boundaries and
+/// merge adjacent pieces into chunks of at most `chunk_size` characters,
+/// consecutive chunks sharing up to `overlap` characters. Chunks are
+/// substrings of the document.
 pub trait TextSplitter {
-    async fn split(
+    fn split(
         &self,
         doc: &str,
-        max_tokens: u64,
-        max_token_overlap: u64,
+        chunk_size: usize,
+        overlap: usize,
     ) -> Result<Vec<String>, TextSplitterError>;
-
-    async fn tokenize_encode(
-        &self,
-        input: Vec<String>,
-    ) -> Result<Vec<Vec<u64>>, EmbeddingGeneratorError>;
-
-    async fn tokenize_decode(
-        &self,
-        input: Vec<Vec<u64>>,
-    ) -> Result<Vec<String>, EmbeddingGeneratorError>;
 }
 
-pub fn get_splitter(
-    kind: TextSplitterKind,
-    embedding_generator: EmbeddingGeneratorTS,
-) -> Result<TextSplitterTS, TextSplitterError> {
+pub fn get_splitter(kind: TextSplitterKind) -> Result<TextSplitterTS, TextSplitterError> {
     match kind {
-        TextSplitterKind::NewLine => Ok(Arc::new(NewLineSplitter {
-            embedding_generator,
+        TextSplitterKind::NewLine => Ok(Arc::new(NewLineSplitter)),
+        TextSplitterKind::Regex { pattern } => Ok(Arc::new(RegexSplitter {
+            pattern: Regex::new(&pattern)?,
         })),
-        TextSplitterKind::Regex { pattern: p } => Ok(Arc::new(RegexSplitter {
-            pattern: p,
-            embedding_generator,
-        })),
-        TextSplitterKind::Noop => Ok(Arc::new(NoOpTextSplitter)),
+        TextSplitterKind::Recursive { separators } => {
+            Ok(Arc::new(RecursiveCharacterSplitter { separators }))
+        }
+        TextSplitterKind::Markdown => Ok(Arc::new(MarkdownSplitter)),
     }
 }
 
-pub struct NewLineSplitter {
-    embedding_generator: EmbeddingGeneratorTS,
+fn char_len(text: &str) -> usize {
+    text.chars().count()
 }
 
-#[async_trait::async_trait]
-impl TextSplitter for NewLineSplitter {
-    async fn split(
-        &self,
-        doc: &str,
-        max_tokens: u64,
-        max_token_overlap: u64,
-    ) -> Result<Vec<String>, TextSplitterError> {
-        let split_across_newlines = doc
-            .split('\n')
-            .map(|s| s.to_owned())
-            .collect::<Vec<String>>();
-        let tokens: Vec<Vec<u64>> = self.tokenize_encode(split_across_newlines).await?;
-        let flattened_tokens = tokens.into_iter().flatten().collect();
-        let chunk_tokens = merge_tokens(flattened_tokens, max_tokens, max_token_overlap).await?;
-        self.tokenize_decode(chunk_tokens)
-            .await
-            .map_err(|e| e.into())
-    }
-
-    async fn tokenize_encode(
-        &self,
-        input: Vec<String>,
-    ) -> Result<Vec<Vec<u64>>, EmbeddingGeneratorError> {
-        self.embedding_generator.tokenize_encode(input).await
+fn check_chunk_size(chunk_size: usize, overlap: usize) -> Result<(), TextSplitterError> {
+    if chunk_size == 0 {
+        return Err(TextSplitterError::InvalidChunkSize(
+            "chunk_size must be greater than 0".to_string(),
+        ));
     }
-
-    async fn tokenize_decode(
-        &self,
-        input: Vec<Vec<u64>>,
-    ) -> Result<Vec<String>, EmbeddingGeneratorError> {
-        self.embedding_generator.tokenize_decode(input).await
+    if overlap >= chunk_size {
+        return Err(TextSplitterError::InvalidChunkSize(format!(
+            "overlap {} must be smaller than chunk_size {}",
+            overlap, chunk_size
+        )));
     }
+    Ok(())
 }
 
-pub struct NoOpTextSplitter;
-
-#[async_trait::async_trait]
-impl TextSplitter for NoOpTextSplitter {
-    async fn split(
-        &self,
-        doc: &str,
-        _max_tokens: u64,
-        _max_token_overlap: u64,
-    ) -> Result<Vec<String>, TextSplitterError> {
-        Ok(vec![doc.to_owned()])
+/// Cuts text into windows of `chunk_size` characters, each starting
+/// `chunk_size - overlap` characters after the previous one
+fn split_chars(text: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let step_size = chunk_size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start_idx = 0;
+    loop {
+        let end_idx = usize::min(start_idx + chunk_size, chars.len());
+        chunks.push(chars[start_idx..end_idx].iter().collect());
+        if end_idx == chars.len() {
+            break;
+        }
+        start_idx += step_size;
     }
+    chunks
+}
 
-    async fn tokenize_encode(
-        &self,
-        _input: Vec<String>,
-    ) -> Result<Vec<Vec<u64>>, EmbeddingGeneratorError> {
-        Ok(vec![vec![]])
+fn merge_splits(splits: Vec<String>, chunk_size: usize, overlap: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current: VecDeque<String> = VecDeque::new();
+    let mut current_len = 0;
+    for split in splits {
+        let len = char_len(&split);
+        if len == 0 {
+            continue;
+        }
+        if len > chunk_size {
+            // pieces that don't fit into a chunk on their own are cut at
+            // character boundaries
+            if !current.is_empty() {
+                chunks.push(current.drain(..).collect());
+                current_len = 0;
+            }
+            chunks.extend(split_chars(&split, chunk_size, overlap));
+            continue;
+        }
+        if current_len + len > chunk_size {
+            chunks.push(current.iter().map(String::as_str).collect());
+            while current_len > overlap || current_len + len > chunk_size {
+                let Some(front) = current.pop_front() else {
+                    break;
+                };
+                current_len -= char_len(&front);
+            }
+        }
+        current_len += len;
+        current.push_back(split);
+    }
+    if !current.is_empty() {
+        chunks.push(current.drain(..).collect());
     }
+    chunks
+}
+
+pub struct NewLineSplitter;
 
-    async fn tokenize_decode(
+impl TextSplitter for NewLineSplitter {
+    fn split(
         &self,
-        _input: Vec<Vec<u64>>,
-    ) -> Result<Vec<String>, EmbeddingGeneratorError> {
-        Ok(vec![])
+        doc: &str,
+        chunk_size: usize,
+        overlap: usize,
+    ) -> Result<Vec<String>, TextSplitterError> {
+        check_chunk_size(chunk_size, overlap)?;
+        let lines = doc.split_inclusive('\n').map(|s| s.to_owned()).collect();
+        Ok(merge_splits(lines, chunk_size, overlap))
     }
 }
 
 pub struct RegexSplitter {
-    pub pattern: String,
-    pub embedding_generator: EmbeddingGeneratorTS,
+    pub pattern: Regex,
 }
 
-#[async_trait::async_trait]
 impl TextSplitter for RegexSplitter {
-    async fn split(
+    fn split(
         &self,
         doc: &str,
-        max_tokens: u64,
-        max_token_overlap: u64,
+        chunk_size: usize,
+        overlap: usize,
     ) -> Result<Vec<String>, TextSplitterError> {
-        let closing_tag_pattern = Regex::new(&self.pattern).unwrap();
+        check_chunk_size(chunk_size, overlap)?;
         let mut splits: Vec<String> = Vec::new();
         let mut start_index = 0;
-
-        for mat in closing_tag_pattern.find_iter(doc) {
-            let end_index = mat.end();
-            let part = &doc[(start_index + 1)..end_index];
-            splits.push(part.to_string());
-            start_index = end_index;
+        for mat in self.pattern.find_iter(doc) {
+            if mat.end() == start_index {
+                continue;
+            }
+            splits.push(doc[start_index..mat.end()].to_string());
+            start_index = mat.end();
         }
-        let mut chunks: Vec<String> = Vec::new();
-        for split in splits {
-            let tokens = self.tokenize_encode(vec![split]).await?;
-            let flattened_tokens = tokens.into_iter().flatten().collect();
-            let split_chunk_tokens =
-                merge_tokens(flattened_tokens, max_tokens, max_token_overlap).await?;
-            let split_chunks = self
-                .tokenize_decode(split_chunk_tokens)
-                .await
-                .map_err(TextSplitterError::TokenizerError)?;
-            chunks.extend(split_chunks);
+        if start_index < doc.len() {
+            splits.push(doc[start_index..].to_string());
         }
+        Ok(merge_splits(splits, chunk_size, overlap))
+    }
+}
 
-        Ok(chunks)
+pub struct RecursiveCharacterSplitter {
+    pub separators: Vec<String>,
+}
+
+impl RecursiveCharacterSplitter {
+    fn split_pieces(text: &str, separators: &[String], chunk_size: usize) -> Vec<String> {
+        if char_len(text) <= chunk_size {
+            return vec![text.to_string()];
+        }
+        let Some((separator, rest)) = separators.split_first() else {
+            return vec![text.to_string()];
+        };
+        if separator.is_empty() || !text.contains(separator.as_str()) {
+            return Self::split_pieces(text, rest, chunk_size);
+        }
+        text.split_inclusive(separator.as_str())
+            .flat_map(|piece| Self::split_pieces(piece, rest, chunk_size))
+            .collect()
     }
+}
 
-    async fn tokenize_encode(
+impl TextSplitter for RecursiveCharacterSplitter {
+    fn split(
         &self,
-        input: Vec<String>,
-    ) -> Result<Vec<Vec<u64>>, EmbeddingGeneratorError> {
-        self.embedding_generator.tokenize_encode(input).await
+        doc: &str,
+        chunk_size: usize,
+        overlap: usize,
+    ) -> Result<Vec<String>, TextSplitterError> {
+        check_chunk_size(chunk_size, overlap)?;
+        let pieces = Self::split_pieces(doc, &self.separators, chunk_size);
+        Ok(merge_splits(pieces, chunk_size, overlap))
     }
+}
 
-    async fn tokenize_decode(
+pub struct MarkdownSplitter;
+
+impl MarkdownSplitter {
+    fn is_heading(line: &str) -> bool {
+        let line = line.trim_start();
+        let level = line.chars().take_while(|c| *c == '#').count();
+        (1..=6).contains(&level) && line[level..].starts_with([' ', '\t', '\n'])
+    }
+
+    /// Splits the document into sections, each starting at a heading.
+    /// Headings inside fenced code blocks are ignored.
+    fn sections(doc: &str) -> Vec<String> {
+        let mut sections = Vec::new();
+        let mut current = String::new();
+        let mut in_code_block = false;
+        for line in doc.split_inclusive('\n') {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block && Self::is_heading(line) && !current.is_empty() {
+                sections.push(std::mem::take(&mut current));
+            }
+            current.push_str(line);
+        }
+        if !current.is_empty() {
+            sections.push(current);
+        }
+        sections
+    }
+}
+
+impl TextSplitter for MarkdownSplitter {
+    fn split(
         &self,
-        input: Vec<Vec<u64>>,
-    ) -> Result<Vec<String>, EmbeddingGeneratorError> {
-        self.embedding_generator.tokenize_decode(input).await
+        doc: &str,
+        chunk_size: usize,
+        overlap: usize,
+    ) -> Result<Vec<String>, TextSplitterError> {
+        check_chunk_size(chunk_size, overlap)?;
+        let separators = default_separators();
+        let chunks = Self::sections(doc)
+            .into_iter()
+            .flat_map(|section| {
+                let pieces =
+                    RecursiveCharacterSplitter::split_pieces(&section, &separators, chunk_size);
+                merge_splits(pieces, chunk_size, overlap)
+            })
+            .collect();
+        Ok(chunks)
     }
 }
 
 #[cfg(test)]
 mod tests {
-
     use std::fs;
 
-    use crate::sentence_transformers;
-
     use super::*;
 
-    #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_basic_flat_xml() {
-        let model = Arc::new(sentence_transformers::AllMiniLmL6V2::new().unwrap());
-        let xml = r#"
-    <a id=7 role=combobox title=Search type=search aria-label=Search> </a>
+    #[test]
+    fn test_basic_flat_xml() {
+        let xml = r#"<a id=7 role=combobox title=Search type=search aria-label=Search> </a>
     <l id=0>About</l>
     <l id=1>Store</l>
     <l id=2 aria-label=Gmail (opens a new tab)>Gmail</l>
     <l id=3 aria-label=Search for Images (opens a new tab)>Images</l>
     <b id=4 aria-label=Google apps/>
     "#;
-        let splitter = super::RegexSplitter {
+        let splitter = get_splitter(TextSplitterKind::Regex {
             pattern: r"<\/[^>]+>".into(),
-            embedding_generator: model,
-        };
-        let chunks = splitter.split(xml, 512, 0).await.unwrap();
+        })
+        .unwrap();
+        let chunks = splitter.split(xml, 80, 0).unwrap();
         assert_eq!(chunks.len(), 5);
-        // TODO fix the decoding of tokens to exclude all the whitespaces.
-        assert_eq!(chunks[1], "< l id = 0 > about < / l >");
+        assert_eq!(chunks[1], "\n    <l id=0>About</l>\n    <l id=1>Store</l>");
+        assert_eq!(chunks.concat(), xml);
     }
 
-    #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn test_new_line_splitter() {
-        let model = Arc::new(sentence_transformers::AllMiniLmL6V2::new().unwrap());
-        let splitter = get_splitter(TextSplitterKind::NewLine, model).unwrap();
+    #[test]
+    fn test_new_line_splitter() {
+        let splitter = get_splitter(TextSplitterKind::NewLine).unwrap();
         let doc = fs::read_to_string("./src/text_splitters/state_of_the_union.txt").unwrap();
-        let chunks = splitter.split(&doc, 512, 0).await.unwrap();
-        assert_eq!(chunks.len(), 19);
+        let chunks = splitter.split(&doc, 2000, 0).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| char_len(c) <= 2000));
+        assert_eq!(chunks.concat(), doc);
 
         let doc1 = "embiid is the mvp";
-        let chunks1 = splitter.split(doc1, 512, 0).await.unwrap();
-        assert_eq!(chunks1[0], doc1);
-        assert_eq!(chunks1.len(), 1);
+        let chunks1 = splitter.split(doc1, 512, 0).unwrap();
+        assert_eq!(chunks1, vec![doc1]);
+
+        assert!(splitter.split(doc1, 10, 10).is_err());
+    }
+
+    #[test]
+    fn test_recursive_splitter() {
+        let splitter = get_splitter(TextSplitterKind::Recursive {
+            separators: default_separators(),
+        })
+        .unwrap();
+        let doc = "one two three four\n\nfive six seven eight nine ten";
+        let chunks = splitter.split(doc, 20, 0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                "one two three four\n\n",
+                "five six seven ",
+                "eight nine ten"
+            ]
+        );
+
+        // consecutive chunks share up to `overlap` characters
+        let chunks = splitter.split("aaaa bbbb cccc dddd", 10, 5).unwrap();
+        assert_eq!(chunks, vec!["aaaa bbbb ", "bbbb cccc ", "cccc dddd"]);
+
+        // words longer than a chunk are cut at character boundaries
+        let chunks = splitter.split("abcdefghij", 4, 1).unwrap();
+        assert_eq!(chunks, vec!["abcd", "defg", "ghij"]);
+    }
+
+    #[test]
+    fn test_markdown_splitter() {
+        let splitter = get_splitter(TextSplitterKind::Markdown).unwrap();
+        let doc = "# Title\nintro\n## Install\n```\n# not a heading\n```\n## Usage\nrun it\n";
+        let chunks = splitter.split(doc, 100, 0).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                "# Title\nintro\n",
+                "## Install\n```\n# not a heading\n```\n",
+                "## Usage\nrun it\n"
+            ]
+        );
     }
 }